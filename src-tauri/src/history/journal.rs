@@ -0,0 +1,381 @@
+use super::*;
+
+/// 播放列表修改前后的状态，`None` 表示不存在（新建或删除）
+///
+/// 修改已有的播放列表时 `before`/`after` 不含曲目，曲目的变化记录在 `tracks` 中；
+/// 旧版本的记录没有 `tracks`，保存的是完整的播放列表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistChange {
+    pub id: String,
+    pub before: Option<Playlist>,
+    pub after: Option<Playlist>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracks: Option<TrackSplice>,
+}
+
+/// 曲目列表的一处替换：从 `start` 开始，`removed` 替换为 `inserted`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackSplice {
+    pub start: usize,
+    pub removed: Vec<Track>,
+    pub inserted: Vec<Track>,
+}
+
+impl TrackSplice {
+    /// 去掉前后相同的曲目，只保留中间变化的部分
+    pub fn between(before: &[Track], after: &[Track]) -> Self {
+        let start = before
+            .iter()
+            .zip(after)
+            .take_while(|(old, new)| old == new)
+            .count();
+        let end = before[start..]
+            .iter()
+            .rev()
+            .zip(after[start..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        Self {
+            start,
+            removed: before[start..before.len() - end].to_vec(),
+            inserted: after[start..after.len() - end].to_vec(),
+        }
+    }
+
+    /// 撤销时把 `inserted` 换回 `removed`，重做时反之
+    pub fn apply(&self, tracks: &mut Vec<Track>, undo: bool) {
+        let (current, target) = if undo {
+            (&self.inserted, &self.removed)
+        } else {
+            (&self.removed, &self.inserted)
+        };
+        let start = self.start.min(tracks.len());
+        let end = (start + current.len()).min(tracks.len());
+        tracks.splice(start..end, target.iter().cloned());
+    }
+}
+
+/// 单个曲目的标签修改，撤销和重做时分别写回文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagChange {
+    pub track_id: String,
+    pub undo: TagEdit,
+    pub redo: TagEdit,
+}
+
+/// 一次操作中可撤销的修改
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum HistoryChange {
+    Playlists {
+        changes: Vec<PlaylistChange>,
+    },
+    Tags {
+        changes: Vec<TagChange>,
+    },
+    /// 编码修复写回的文本字段，撤销时写回原来的文本
+    TagEncoding {
+        files: Vec<FileEncodingRepair>,
+    },
+    /// 从音乐库移除的曲目，撤销时从记录的快照中恢复
+    LibraryRemoval {
+        track_ids: Vec<String>,
+    },
+}
+
+/// 一次操作的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,
+    pub created_at: String,
+    /// 操作说明，如 "Delete playlist"
+    pub label: String,
+    pub changes: Vec<HistoryChange>,
+    /// 执行前写入的快照文件名，记录被丢弃时一并删除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+}
+
+/// 操作日志，`position` 之前的记录可撤销，之后的可重做
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationHistory {
+    pub entries: Vec<HistoryEntry>,
+    pub position: usize,
+}
+
+impl OperationHistory {
+    /// 追加记录：丢弃可重做的记录，超出上限时删除最早的记录，返回被丢弃的记录
+    pub fn push(&mut self, entry: HistoryEntry, limit: usize) -> Vec<HistoryEntry> {
+        let position = self.position.min(self.entries.len());
+        let mut dropped: Vec<HistoryEntry> = self.entries.drain(position..).collect();
+        self.entries.push(entry);
+        let excess = self.entries.len().saturating_sub(limit.max(1));
+        dropped.extend(self.entries.drain(..excess));
+        self.position = self.entries.len();
+        dropped
+    }
+
+    pub fn next_undo(&self) -> Option<&HistoryEntry> {
+        self.position
+            .checked_sub(1)
+            .and_then(|index| self.entries.get(index))
+    }
+
+    pub fn next_redo(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.position)
+    }
+}
+
+// 比较序列化结果，播放列表本身没有实现 PartialEq
+fn same_playlist(a: &Playlist, b: &Playlist) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+// 播放列表的名称、描述等信息，不含曲目
+fn without_tracks(playlist: &Playlist) -> Playlist {
+    Playlist {
+        tracks: Vec::new(),
+        ..playlist.clone()
+    }
+}
+
+/// 对比修改前后的全部播放列表，只保留有变化的
+pub fn playlist_changes(before: &[Playlist], after: &[Playlist]) -> Vec<PlaylistChange> {
+    let mut changes: Vec<PlaylistChange> = before
+        .iter()
+        .filter_map(|old| {
+            let new = after.iter().find(|p| p.id == old.id);
+            if new.is_some_and(|new| same_playlist(old, new)) {
+                return None;
+            }
+            let Some(new) = new else {
+                return Some(PlaylistChange {
+                    id: old.id.clone(),
+                    before: Some(old.clone()),
+                    after: None,
+                    tracks: None,
+                });
+            };
+            Some(PlaylistChange {
+                id: old.id.clone(),
+                before: Some(without_tracks(old)),
+                after: Some(without_tracks(new)),
+                tracks: Some(TrackSplice::between(&old.tracks, &new.tracks)),
+            })
+        })
+        .collect();
+
+    changes.extend(
+        after
+            .iter()
+            .filter(|new| !before.iter().any(|old| old.id == new.id))
+            .map(|new| PlaylistChange {
+                id: new.id.clone(),
+                before: None,
+                after: Some(new.clone()),
+                tracks: None,
+            }),
+    );
+    changes
+}
+
+/// 把播放列表恢复为修改前（撤销）或修改后（重做）的状态
+pub fn apply_playlist_changes(
+    playlists: &mut Vec<Playlist>,
+    changes: &[PlaylistChange],
+    undo: bool,
+) {
+    for change in changes {
+        let target = if undo { &change.before } else { &change.after };
+        let position = playlists.iter().position(|p| p.id == change.id);
+        match (position, target, &change.tracks) {
+            (Some(index), Some(playlist), Some(splice)) => {
+                let mut tracks = std::mem::take(&mut playlists[index].tracks);
+                splice.apply(&mut tracks, undo);
+                playlists[index] = Playlist {
+                    tracks,
+                    ..playlist.clone()
+                };
+            }
+            (Some(index), Some(playlist), None) => playlists[index] = playlist.clone(),
+            (Some(index), None, _) => {
+                playlists.remove(index);
+            }
+            // 只记录了曲目变化的播放列表已不存在，无法恢复
+            (None, Some(_), Some(_)) => {}
+            (None, Some(playlist), None) => playlists.push(playlist.clone()),
+            (None, None, _) => {}
+        }
+    }
+}
+
+/// 从快照中把移除的曲目放回音乐库，已存在相同ID或路径的曲目跳过，返回放回的ID
+pub fn restore_tracks(
+    library: &mut MusicLibrary,
+    snapshot: &MusicLibrary,
+    track_ids: &[String],
+) -> Vec<String> {
+    let ids: HashSet<&str> = track_ids.iter().map(|id| id.as_str()).collect();
+    let mut existing: HashSet<String> = library
+        .tracks
+        .iter()
+        .flat_map(|t| [t.id.clone(), t.file_path.clone()])
+        .collect();
+
+    let mut restored = Vec::new();
+    for track in snapshot
+        .tracks
+        .iter()
+        .filter(|t| ids.contains(t.id.as_str()))
+    {
+        if existing.contains(&track.id) || existing.contains(&track.file_path) {
+            continue;
+        }
+        existing.insert(track.id.clone());
+        existing.insert(track.file_path.clone());
+        library.tracks.push(track.clone());
+        restored.push(track.id.clone());
+    }
+    restored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::LibraryTrack;
+
+    fn entry(label: &str) -> HistoryEntry {
+        HistoryEntry {
+            id: label.to_string(),
+            created_at: String::new(),
+            label: label.to_string(),
+            changes: Vec::new(),
+            snapshot: None,
+        }
+    }
+
+    fn playlist(id: &str, name: &str) -> Playlist {
+        Playlist {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            tracks: Vec::new(),
+            created_at: String::new(),
+            updated_at: String::new(),
+            smart: None,
+        }
+    }
+
+    #[test]
+    fn test_push_undo_redo() {
+        let mut history = OperationHistory::default();
+        for label in ["a", "b", "c"] {
+            assert!(history.push(entry(label), 3).is_empty());
+        }
+        assert_eq!(history.next_undo().unwrap().label, "c");
+        assert!(history.next_redo().is_none());
+
+        history.position -= 2;
+        assert_eq!(history.next_undo().unwrap().label, "a");
+        assert_eq!(history.next_redo().unwrap().label, "b");
+
+        // A new operation discards what could be redone
+        let dropped = history.push(entry("d"), 3);
+        assert_eq!(dropped.len(), 2);
+        assert_eq!(history.entries.len(), 2);
+
+        // Retention limit drops the oldest entries
+        history.push(entry("e"), 3);
+        let dropped = history.push(entry("f"), 3);
+        assert_eq!(dropped[0].label, "a");
+        let labels: Vec<&str> = history.entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["d", "e", "f"]);
+        assert_eq!(history.position, 3);
+    }
+
+    #[test]
+    fn test_playlist_changes_round_trip() {
+        let before = vec![
+            playlist("1", "Keep"),
+            playlist("2", "Old"),
+            playlist("3", "Gone"),
+        ];
+        let after = vec![
+            playlist("1", "Keep"),
+            playlist("2", "New"),
+            playlist("4", "Added"),
+        ];
+        let changes = playlist_changes(&before, &after);
+        assert_eq!(changes.len(), 3);
+
+        let mut playlists = after.clone();
+        apply_playlist_changes(&mut playlists, &changes, true);
+        let names: Vec<&str> = playlists.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Keep", "Old", "Gone"]);
+
+        apply_playlist_changes(&mut playlists, &changes, false);
+        let names: Vec<&str> = playlists.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Keep", "New", "Added"]);
+    }
+
+    #[test]
+    fn test_playlist_changes_store_only_changed_tracks() {
+        let track = |n: usize| Track {
+            title: n.to_string(),
+            file_path: format!("/music/{}.mp3", n),
+            ..Default::default()
+        };
+        let mut before = playlist("1", "Big");
+        before.tracks = (0..5000).map(track).collect();
+        let mut after = before.clone();
+        let moved = after.tracks.remove(10);
+        after.tracks.insert(12, moved);
+        after.name = "Renamed".to_string();
+
+        let changes = playlist_changes(&[before.clone()], &[after.clone()]);
+        let splice = changes[0].tracks.as_ref().unwrap();
+        assert_eq!(splice.start, 10);
+        assert_eq!((splice.removed.len(), splice.inserted.len()), (3, 3));
+        assert!(changes[0].before.as_ref().unwrap().tracks.is_empty());
+
+        let mut playlists = vec![after.clone()];
+        apply_playlist_changes(&mut playlists, &changes, true);
+        assert_eq!(playlists[0].name, "Big");
+        assert_eq!(playlists[0].tracks, before.tracks);
+        apply_playlist_changes(&mut playlists, &changes, false);
+        assert_eq!(playlists[0].name, "Renamed");
+        assert_eq!(playlists[0].tracks, after.tracks);
+
+        // Entries written before splices were recorded hold whole playlists
+        let legacy: PlaylistChange = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "before": before,
+            "after": after,
+        }))
+        .unwrap();
+        apply_playlist_changes(&mut playlists, &[legacy], true);
+        assert_eq!(playlists[0].tracks, before.tracks);
+    }
+
+    #[test]
+    fn test_restore_tracks() {
+        let track = |id: &str, path: &str| LibraryTrack {
+            id: id.to_string(),
+            file_path: path.to_string(),
+            ..Default::default()
+        };
+        let mut snapshot = MusicLibrary::new();
+        snapshot.tracks = vec![
+            track("1", "/a.mp3"),
+            track("2", "/b.mp3"),
+            track("3", "/c.mp3"),
+        ];
+
+        // "/b.mp3" was rescanned under a new ID in the meantime
+        let mut library = MusicLibrary::new();
+        library.tracks = vec![track("9", "/b.mp3")];
+        let ids = vec!["1".to_string(), "2".to_string()];
+        assert_eq!(restore_tracks(&mut library, &snapshot, &ids), vec!["1"]);
+        assert_eq!(library.tracks.len(), 2);
+    }
+}
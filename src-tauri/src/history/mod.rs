@@ -0,0 +1,288 @@
+use crate::library::editor::{load_cover_picture, write_tag_edits};
+use crate::library::encoding::rewrite_encoding_repairs;
+use crate::library::search::update_search_index;
+use crate::library::{
+    load_library_from_file, lock_library, save_library_to_file, FieldEdit, FileEncodingRepair,
+    MusicLibrary, TagEdit,
+};
+use crate::playlist::{
+    load_playlists_from_file, lock_playlists, save_playlists_to_file, Playlist, Track,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+pub mod journal;
+
+pub use journal::*;
+
+/// 操作日志保留的记录数，超出时删除最早的记录和对应快照
+const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// 撤销或重做的结果，有错误时日志位置不变，可以重试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryReport {
+    pub entry: HistoryEntry,
+    pub errors: Vec<String>,
+}
+
+fn get_app_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    Ok(app_data_dir)
+}
+
+fn get_history_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_app_data_dir(app)?.join("operation_history.json"))
+}
+
+fn get_snapshot_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = get_app_data_dir(app)?.join("snapshots");
+    if !dir.exists() {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+    }
+    Ok(dir)
+}
+
+fn load_history(app: &AppHandle) -> Result<OperationHistory, String> {
+    let file_path = get_history_file_path(app)?;
+    if !file_path.exists() {
+        return Ok(OperationHistory::default());
+    }
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read operation history: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse operation history: {}", e))
+}
+
+fn save_history(app: &AppHandle, history: &OperationHistory) -> Result<(), String> {
+    let file_path = get_history_file_path(app)?;
+    let content = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize operation history: {}", e))?;
+    fs::write(&file_path, content).map_err(|e| format!("Failed to write operation history: {}", e))
+}
+
+/// 破坏性操作执行前写入快照，返回快照文件名
+pub(crate) fn write_snapshot<T: Serialize>(app: &AppHandle, value: &T) -> Result<String, String> {
+    let name = format!("{}.json", uuid::Uuid::new_v4());
+    let content =
+        serde_json::to_string(value).map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+    fs::write(get_snapshot_dir(app)?.join(&name), content)
+        .map_err(|e| format!("Failed to write snapshot: {}", e))?;
+    Ok(name)
+}
+
+fn read_snapshot<T: DeserializeOwned>(app: &AppHandle, name: &str) -> Result<T, String> {
+    let content = fs::read_to_string(get_snapshot_dir(app)?.join(name))
+        .map_err(|e| format!("Failed to read snapshot: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse snapshot: {}", e))
+}
+
+// 删除不再被引用的快照，失败时只留下多余文件
+fn remove_snapshots(app: &AppHandle, entries: &[HistoryEntry]) {
+    let Ok(dir) = get_snapshot_dir(app) else {
+        return;
+    };
+    for name in entries.iter().filter_map(|entry| entry.snapshot.as_deref()) {
+        let _ = fs::remove_file(dir.join(name));
+    }
+}
+
+/// 记录一次操作，之后可以撤销；没有修改时只删除快照
+pub(crate) fn record_operation(
+    app: &AppHandle,
+    label: &str,
+    changes: Vec<HistoryChange>,
+    snapshot: Option<String>,
+) -> Result<(), String> {
+    let entry = HistoryEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        label: label.to_string(),
+        changes,
+        snapshot,
+    };
+    if entry.changes.is_empty() {
+        remove_snapshots(app, &[entry]);
+        return Ok(());
+    }
+
+    let mut history = load_history(app)?;
+    let dropped = history.push(entry, MAX_HISTORY_ENTRIES);
+    save_history(app, &history)?;
+    remove_snapshots(app, &dropped);
+    Ok(())
+}
+
+/// 记录播放列表的修改
+pub(crate) fn record_playlist_changes(
+    app: &AppHandle,
+    label: &str,
+    before: &[Playlist],
+    after: &[Playlist],
+) -> Result<(), String> {
+    let changes = playlist_changes(before, after);
+    let changes = if changes.is_empty() {
+        Vec::new()
+    } else {
+        vec![HistoryChange::Playlists { changes }]
+    };
+    record_operation(app, label, changes, None)
+}
+
+// 按记录重新写入标签，返回写入失败的曲目
+async fn rewrite_tags(
+    app: &AppHandle,
+    changes: &[TagChange],
+    undo: bool,
+) -> Result<Vec<String>, String> {
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    let mut errors = Vec::new();
+    let mut edits = Vec::new();
+    for change in changes {
+        match library.tracks.iter().position(|t| t.id == change.track_id) {
+            Some(index) => {
+                let edit = if undo { &change.undo } else { &change.redo };
+                edits.push((index, edit.clone()));
+            }
+            None => errors.push(format!("Track not found: {}", change.track_id)),
+        }
+    }
+
+    let cover = edits
+        .iter()
+        .find_map(|(_, edit)| match &edit.cover {
+            FieldEdit::Set(path) => Some(load_cover_picture(path)),
+            _ => None,
+        })
+        .transpose()?;
+    let report = write_tag_edits(&mut library, edits, cover).await?;
+    if !report.updated.is_empty() {
+        save_library_to_file(app, &library)?;
+        let changed: Vec<String> = report.updated.iter().map(|t| t.id.clone()).collect();
+        update_search_index(app, &library, &changed, &[]);
+    }
+
+    errors.extend(report.errors);
+    Ok(errors)
+}
+
+// 撤销时从快照放回移除的曲目，重做时再次移除
+async fn apply_library_removal(
+    app: &AppHandle,
+    track_ids: &[String],
+    snapshot: Option<&str>,
+    undo: bool,
+) -> Result<(), String> {
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    let (changed, removed) = if undo {
+        let snapshot = snapshot.ok_or_else(|| "Snapshot not found".to_string())?;
+        let saved: MusicLibrary = read_snapshot(app, snapshot)?;
+        // 清空后恢复时连同扫描目录一起恢复
+        if library.last_scanned_paths.is_empty() {
+            library.last_scanned_paths = saved.last_scanned_paths.clone();
+        }
+        (restore_tracks(&mut library, &saved, track_ids), Vec::new())
+    } else {
+        let ids: HashSet<&String> = track_ids.iter().collect();
+        let removed: Vec<String> = library
+            .tracks
+            .iter()
+            .filter(|t| ids.contains(&t.id))
+            .map(|t| t.id.clone())
+            .collect();
+        library.tracks.retain(|t| !ids.contains(&t.id));
+        (Vec::new(), removed)
+    };
+
+    if !changed.is_empty() || !removed.is_empty() {
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+        save_library_to_file(app, &library)?;
+        update_search_index(app, &library, &changed, &removed);
+    }
+    Ok(())
+}
+
+// 按顺序应用（撤销时逆序）一条记录中的修改
+async fn apply_entry(
+    app: &AppHandle,
+    entry: &HistoryEntry,
+    undo: bool,
+) -> Result<Vec<String>, String> {
+    let mut errors = Vec::new();
+    let changes: Vec<&HistoryChange> = if undo {
+        entry.changes.iter().rev().collect()
+    } else {
+        entry.changes.iter().collect()
+    };
+
+    for change in changes {
+        match change {
+            HistoryChange::Playlists { changes } => {
+                let _lock = lock_playlists(app).await;
+                let mut playlists = load_playlists_from_file(app).await?;
+                apply_playlist_changes(&mut playlists, changes, undo);
+                save_playlists_to_file(app, &playlists).await?;
+            }
+            HistoryChange::Tags { changes } => {
+                errors.extend(rewrite_tags(app, changes, undo).await?);
+            }
+            HistoryChange::TagEncoding { files } => {
+                errors.extend(rewrite_encoding_repairs(app, files, undo).await?);
+            }
+            HistoryChange::LibraryRemoval { track_ids } => {
+                apply_library_removal(app, track_ids, entry.snapshot.as_deref(), undo).await?;
+            }
+        }
+    }
+    Ok(errors)
+}
+
+/// 获取操作日志，`position` 之前的记录可撤销
+#[tauri::command]
+pub async fn get_operation_history(app: AppHandle) -> Result<OperationHistory, String> {
+    load_history(&app)
+}
+
+/// 撤销最近一次操作，没有可撤销的操作时返回 None
+#[tauri::command]
+pub async fn undo_operation(app: AppHandle) -> Result<Option<HistoryReport>, String> {
+    let mut history = load_history(&app)?;
+    let Some(entry) = history.next_undo().cloned() else {
+        return Ok(None);
+    };
+
+    let errors = apply_entry(&app, &entry, true).await?;
+    if errors.is_empty() {
+        history.position -= 1;
+        save_history(&app, &history)?;
+    }
+    Ok(Some(HistoryReport { entry, errors }))
+}
+
+/// 重做最近一次撤销的操作，没有可重做的操作时返回 None
+#[tauri::command]
+pub async fn redo_operation(app: AppHandle) -> Result<Option<HistoryReport>, String> {
+    let mut history = load_history(&app)?;
+    let Some(entry) = history.next_redo().cloned() else {
+        return Ok(None);
+    };
+
+    let errors = apply_entry(&app, &entry, false).await?;
+    if errors.is_empty() {
+        history.position += 1;
+        save_history(&app, &history)?;
+    }
+    Ok(Some(HistoryReport { entry, errors }))
+}
@@ -1,9 +1,17 @@
 mod audio;
+mod history;
 mod library;
 mod playlist;
+mod scan;
 mod video;
 
 use audio::{AudioState, PlaybackState};
+use library::{
+    LibraryLockState, LibraryWatcherState, QueryCacheState, SearchIndex, SearchIndexState,
+};
+use playlist::PlaylistLockState;
+use scan::ScanJobsState;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -15,12 +23,42 @@ fn greet(name: &str) -> String {
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let audio_state: AudioState = Arc::new(Mutex::new(PlaybackState::default()));
+    let watcher_state: LibraryWatcherState = Arc::new(Mutex::new(None));
+    let scan_jobs: ScanJobsState = Arc::new(Mutex::new(HashMap::new()));
+    let search_index: SearchIndexState = Arc::new(Mutex::new(SearchIndex::new()));
+    let query_cache: QueryCacheState = Arc::default();
+    let library_lock: LibraryLockState = Arc::new(tokio::sync::Mutex::new(()));
+    let playlist_lock: PlaylistLockState = Arc::new(tokio::sync::Mutex::new(()));
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
         .manage(audio_state)
+        .manage(watcher_state)
+        .manage(scan_jobs)
+        .manage(search_index)
+        .manage(query_cache)
+        .manage(library_lock)
+        .manage(playlist_lock)
+        .setup(|app| {
+            // 旧版本音乐库文件升级到当前格式
+            if let Err(e) = library::rescan::upgrade_library(app.handle()) {
+                eprintln!("Failed to upgrade music library: {}", e);
+            }
+            // 旧版本播放列表条目按路径关联到音乐库曲目
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = playlist::upgrade_playlists(&handle).await {
+                    eprintln!("Failed to upgrade playlists: {}", e);
+                }
+            });
+            // 启动时自动监听已扫描的库目录
+            if let Err(e) = library::watcher::start_watching(app.handle()) {
+                eprintln!("Failed to start library watcher: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             library::scan_music_files,
@@ -28,6 +66,38 @@ pub fn run() {
             library::get_saved_library,
             library::save_library,
             library::clear_library,
+            library::rescan::rescan_library,
+            library::rescan::start_library_scan,
+            library::query::query_tracks,
+            library::search::search_tracks,
+            library::query::get_track_index_groups,
+            library::encoding::repair_tag_encoding,
+            library::editor::edit_track_tags,
+            library::editor::edit_tracks_tags,
+            library::guess::preview_tag_guesses,
+            library::guess::apply_tag_guesses,
+            library::organize::organize_library,
+            library::organize::get_organize_journal,
+            library::organize::undo_organize,
+            library::duplicates::find_duplicates,
+            library::relink::check_library_health,
+            library::relink::relink_missing,
+            library::relink::remove_missing_entries,
+            library::rating::set_track_rating,
+            library::rating::set_track_loved,
+            library::rating::record_playback,
+            library::rating::sync_track_stats,
+            library::browse::get_albums,
+            library::browse::get_album_tracks,
+            library::browse::get_artists,
+            library::browse::get_genres,
+            library::browse::get_decades,
+            library::artwork::get_track_artwork,
+            library::artwork::get_album_artwork,
+            library::artwork::clear_artwork_cache,
+            library::watcher::start_library_watcher,
+            library::watcher::stop_library_watcher,
+            library::watcher::get_watched_library_paths,
             audio::play_audio,
             audio::pause_audio,
             audio::resume_audio,
@@ -42,7 +112,28 @@ pub fn run() {
             playlist::add_track_to_playlist,
             playlist::remove_track_from_playlist,
             playlist::update_playlist_info,
+            playlist::move_playlist_tracks,
+            playlist::insert_tracks_into_playlist,
+            playlist::remove_tracks_from_playlist,
+            playlist::remove_duplicate_playlist_tracks,
+            playlist::sort_playlist,
+            playlist::shuffle_playlist,
+            playlist::duplicate_playlist,
+            playlist::merge_playlists,
+            playlist::create_smart_playlist,
+            playlist::update_smart_playlist,
+            playlist::preview_smart_playlist,
+            playlist::import_playlist,
+            playlist::export_playlist,
+            playlist::resolve_playlist,
+            playlist::create_playlist_from_resolution,
+            history::get_operation_history,
+            history::undo_operation,
+            history::redo_operation,
             video::scan_video_files,
+            video::rescan_video_library,
+            video::start_video_scan,
+            video::query_videos,
             video::get_video_metadata,
             video::validate_video_file,
             video::get_supported_video_formats,
@@ -50,7 +141,9 @@ pub fn run() {
             video::test_thumbnail_generation,
             video::get_saved_video_library,
             video::save_video_library,
-            video::clear_video_library
+            video::clear_video_library,
+            scan::cancel_scan_job,
+            scan::get_active_scan_jobs
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,261 @@
+use super::*;
+use image::ImageFormat;
+use lofty::picture::{Picture, PictureType};
+use sha2::{Digest, Sha256};
+
+/// 缓存的封面尺寸（像素，正方形边界内等比缩放）
+pub const ARTWORK_SIZES: [u32; 3] = [64, 256, 512];
+
+/// 目录中可作为封面的文件名（不含扩展名，忽略大小写）
+const FOLDER_COVER_NAMES: [&str; 4] = ["cover", "folder", "front", "albumart"];
+const FOLDER_COVER_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// 选择不小于请求尺寸的最小缓存尺寸，超出时使用最大尺寸
+pub fn artwork_variant_size(requested: Option<u32>) -> u32 {
+    let requested = requested.unwrap_or(ARTWORK_SIZES[1]);
+    ARTWORK_SIZES
+        .iter()
+        .copied()
+        .find(|size| *size >= requested)
+        .unwrap_or(ARTWORK_SIZES[ARTWORK_SIZES.len() - 1])
+}
+
+// 优先使用封面类型的图片，其次是第一张图片
+fn pick_picture(pictures: &[Picture]) -> Option<&Picture> {
+    pictures
+        .iter()
+        .find(|p| p.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures.first())
+}
+
+/// 读取标签中内嵌的封面
+pub fn read_embedded_artwork(path: &Path) -> Option<Vec<u8>> {
+    let tagged_file = Probe::open(path).ok()?.read().ok()?;
+    let primary = tagged_file
+        .primary_tag()
+        .and_then(|t| pick_picture(t.pictures()));
+    let picture = primary.or_else(|| {
+        tagged_file
+            .tags()
+            .iter()
+            .find_map(|t| pick_picture(t.pictures()))
+    })?;
+    Some(picture.data().to_vec())
+}
+
+// 是否为目录中的封面文件，如 `cover.jpg`、`Folder.PNG`
+pub(crate) fn is_folder_artwork(path: &Path) -> bool {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+    FOLDER_COVER_NAMES
+        .iter()
+        .any(|name| stem.eq_ignore_ascii_case(name))
+        && FOLDER_COVER_EXTENSIONS
+            .iter()
+            .any(|e| ext.eq_ignore_ascii_case(e))
+}
+
+/// 在曲目所在目录查找 `cover.jpg`、`folder.png`、`front.*` 等封面文件
+pub fn find_folder_artwork(track_path: &Path) -> Option<PathBuf> {
+    let dir = track_path.parent()?;
+    let mut candidates: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_folder_artwork(path))
+        .collect();
+
+    // 按文件名优先级选择
+    candidates.sort_by_key(|path| {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        FOLDER_COVER_NAMES
+            .iter()
+            .position(|name| *name == stem)
+            .unwrap_or(usize::MAX)
+    });
+    candidates.into_iter().next()
+}
+
+/// 曲目封面原始数据：内嵌封面优先，其次是目录中的封面文件
+pub fn read_track_artwork(track_path: &Path) -> Option<Vec<u8>> {
+    read_embedded_artwork(track_path)
+        .or_else(|| find_folder_artwork(track_path).and_then(|path| fs::read(path).ok()))
+}
+
+fn artwork_file_name(hash: &str, size: u32) -> String {
+    format!("{}_{}.jpg", hash, size)
+}
+
+/// 按内容哈希缓存各尺寸的封面，已缓存时不重新解码，返回哈希
+pub fn cache_artwork(cache_dir: &Path, data: &[u8]) -> Result<String, String> {
+    let hash = format!("{:x}", Sha256::digest(data));
+    let missing: Vec<u32> = ARTWORK_SIZES
+        .iter()
+        .copied()
+        .filter(|size| !cache_dir.join(artwork_file_name(&hash, *size)).exists())
+        .collect();
+    if missing.is_empty() {
+        return Ok(hash);
+    }
+
+    fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Failed to create artwork cache directory: {}", e))?;
+    let image =
+        image::load_from_memory(data).map_err(|e| format!("Failed to decode artwork: {}", e))?;
+
+    for size in missing {
+        let target = cache_dir.join(artwork_file_name(&hash, size));
+        // 先写临时文件再重命名，避免并发读取到不完整的图片
+        let temp = cache_dir.join(format!("{}.{}.tmp", hash, uuid::Uuid::new_v4()));
+        image
+            .thumbnail(size, size)
+            .to_rgb8()
+            .save_with_format(&temp, ImageFormat::Jpeg)
+            .map_err(|e| format!("Failed to write artwork: {}", e))?;
+        fs::rename(&temp, &target).map_err(|e| format!("Failed to write artwork: {}", e))?;
+    }
+
+    Ok(hash)
+}
+
+fn get_artwork_cache_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join("artwork"))
+}
+
+// 依次尝试曲目，返回第一个找到的封面在缓存中的路径
+async fn resolve_artwork(
+    app: &AppHandle,
+    track_paths: Vec<PathBuf>,
+    size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let cache_dir = get_artwork_cache_dir(app)?;
+    let size = artwork_variant_size(size);
+
+    tokio::task::spawn_blocking(move || {
+        for track_path in track_paths {
+            if let Some(data) = read_track_artwork(&track_path) {
+                let hash = cache_artwork(&cache_dir, &data)?;
+                let path = cache_dir.join(artwork_file_name(&hash, size));
+                return Ok(Some(path.to_string_lossy().to_string()));
+            }
+        }
+        Ok(None)
+    })
+    .await
+    .map_err(|e| format!("Artwork task failed: {}", e))?
+}
+
+/// 获取曲目封面的缓存路径，没有封面时返回 `None`
+#[tauri::command]
+pub async fn get_track_artwork(
+    app: AppHandle,
+    track_id: String,
+    size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let library = load_library_from_file(&app)?;
+    let track = library
+        .tracks
+        .iter()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| format!("Track not found: {}", track_id))?;
+
+    resolve_artwork(&app, vec![PathBuf::from(&track.file_path)], size).await
+}
+
+/// 获取专辑封面的缓存路径，按曲目顺序使用第一个找到的封面
+#[tauri::command]
+pub async fn get_album_artwork(
+    app: AppHandle,
+    album_key: String,
+    size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let library = load_library_from_file(&app)?;
+    let mut tracks: Vec<&LibraryTrack> = library
+        .tracks
+        .iter()
+        .filter(|t| browse::album_group_key(t) == album_key)
+        .collect();
+    tracks.sort_by(|a, b| browse::compare_album_order(a, b));
+
+    let paths = tracks
+        .into_iter()
+        .map(|t| PathBuf::from(&t.file_path))
+        .collect();
+    resolve_artwork(&app, paths, size).await
+}
+
+/// 清空封面缓存
+#[tauri::command]
+pub async fn clear_artwork_cache(app: AppHandle) -> Result<(), String> {
+    let cache_dir = get_artwork_cache_dir(&app)?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)
+            .map_err(|e| format!("Failed to clear artwork cache: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support::temp_dir;
+    use image::{Rgb, RgbImage};
+    use std::io::Cursor;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_pixel(width, height, Rgb([200, 30, 30]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_artwork_variant_size() {
+        assert_eq!(artwork_variant_size(None), 256);
+        assert_eq!(artwork_variant_size(Some(48)), 64);
+        assert_eq!(artwork_variant_size(Some(300)), 512);
+        assert_eq!(artwork_variant_size(Some(2000)), 512);
+    }
+
+    #[test]
+    fn test_find_folder_artwork_prefers_cover() {
+        let dir = temp_dir("folder");
+        fs::write(dir.join("01 - Song.mp3"), b"").unwrap();
+        fs::write(dir.join("Folder.PNG"), b"").unwrap();
+        fs::write(dir.join("Cover.jpg"), b"").unwrap();
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let found = find_folder_artwork(&dir.join("01 - Song.mp3")).unwrap();
+        assert_eq!(found.file_name().unwrap(), "Cover.jpg");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_artwork_writes_all_sizes() {
+        let dir = temp_dir("cache");
+        let data = png_bytes(1000, 800);
+
+        let hash = cache_artwork(&dir, &data).unwrap();
+        for size in ARTWORK_SIZES {
+            let path = dir.join(artwork_file_name(&hash, size));
+            let (width, height) = image::image_dimensions(&path).unwrap();
+            assert_eq!(width, size);
+            assert!(height <= size);
+        }
+
+        // 相同内容命中缓存
+        assert_eq!(cache_artwork(&dir, &data).unwrap(), hash);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,391 @@
+use super::*;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// 没有专辑艺术家的合辑使用的专辑艺术家名
+const VARIOUS_ARTISTS: &str = "Various Artists";
+
+/// 专辑概要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlbumSummary {
+    /// 分组键，用于获取专辑曲目
+    pub key: String,
+    pub title: String,
+    pub album_artist: String,
+    pub year: Option<u32>,
+    pub track_count: usize,
+    pub total_duration: f64,
+    /// 用于获取封面的代表曲目
+    pub cover_track_id: String,
+}
+
+/// 艺术家概要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtistSummary {
+    pub name: String,
+    pub album_count: usize,
+    pub track_count: usize,
+}
+
+/// 流派概要
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenreSummary {
+    pub name: String,
+    pub track_count: usize,
+}
+
+/// 年代概要，`decade` 为年代起始年份，如 1980
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecadeSummary {
+    pub decade: u32,
+    pub track_count: usize,
+}
+
+// 专辑艺术家，没有时合辑归入 "Various Artists"，否则退回到曲目艺术家
+pub(crate) fn album_artist_of(track: &LibraryTrack) -> &str {
+    match track
+        .album_artist
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+    {
+        Some(album_artist) => album_artist,
+        None if track.compilation => VARIOUS_ARTISTS,
+        None => &track.artist,
+    }
+}
+
+/// 曲目所属专辑的分组键：规范化后的专辑艺术家 + 专辑名，繁简和全半角不同也会合并
+pub fn album_group_key(track: &LibraryTrack) -> String {
+    format!(
+        "{}\u{1F}{}",
+        normalize::normalize_text(album_artist_of(track)),
+        normalize::normalize_text(&track.album)
+    )
+}
+
+/// 专辑内曲目顺序：碟号、音轨号，缺失时按文件路径
+pub fn compare_album_order(a: &LibraryTrack, b: &LibraryTrack) -> Ordering {
+    a.disc_number
+        .unwrap_or(1)
+        .cmp(&b.disc_number.unwrap_or(1))
+        .then_with(|| {
+            a.track_number
+                .unwrap_or(u32::MAX)
+                .cmp(&b.track_number.unwrap_or(u32::MAX))
+        })
+        .then_with(|| a.file_path.cmp(&b.file_path))
+}
+
+/// 按专辑分组
+pub fn group_albums(tracks: &[LibraryTrack]) -> Vec<AlbumSummary> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, Vec<&LibraryTrack>> = HashMap::new();
+    for track in tracks {
+        let key = album_group_key(track);
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| {
+                order.push(key);
+                Vec::new()
+            })
+            .push(track);
+    }
+
+    let mut albums: Vec<AlbumSummary> = order
+        .into_iter()
+        .filter_map(|key| {
+            let mut tracks = groups.remove(&key)?;
+            tracks.sort_by(|a, b| compare_album_order(a, b));
+            let first = tracks[0];
+
+            Some(AlbumSummary {
+                key,
+                title: first.album.clone(),
+                album_artist: album_artist_of(first).to_string(),
+                year: tracks.iter().filter_map(|t| t.year).min(),
+                track_count: tracks.len(),
+                total_duration: tracks.iter().map(|t| t.duration).sum(),
+                cover_track_id: first.id.clone(),
+            })
+        })
+        .collect();
+
+    albums.sort_by_cached_key(|a| {
+        (
+            pinyin::collated(&a.title),
+            pinyin::collated(&a.album_artist),
+        )
+    });
+    albums
+}
+
+/// 按艺术家分组，多值艺术家的曲目计入每位艺术家，专辑数按专辑名去重
+pub fn group_artists(tracks: &[LibraryTrack]) -> Vec<ArtistSummary> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, (String, usize, HashSet<String>)> = HashMap::new();
+    for track in tracks {
+        for artist in track.artist_names() {
+            let key = normalize::normalize_text(artist);
+            let entry = groups.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                (artist.to_string(), 0, HashSet::new())
+            });
+            entry.1 += 1;
+            entry.2.insert(normalize::normalize_text(&track.album));
+        }
+    }
+
+    let mut artists: Vec<ArtistSummary> = order
+        .into_iter()
+        .filter_map(|key| {
+            let (name, track_count, albums) = groups.remove(&key)?;
+            Some(ArtistSummary {
+                name,
+                album_count: albums.len(),
+                track_count,
+            })
+        })
+        .collect();
+
+    artists.sort_by_cached_key(|a| pinyin::collated(&a.name));
+    artists
+}
+
+/// 按流派分组，没有流派的曲目不计入，多值流派计入每个流派
+pub fn group_genres(tracks: &[LibraryTrack]) -> Vec<GenreSummary> {
+    let mut order: Vec<String> = Vec::new();
+    let mut groups: HashMap<String, GenreSummary> = HashMap::new();
+    for genre in tracks.iter().flat_map(|t| t.genre_names()) {
+        if genre.trim().is_empty() {
+            continue;
+        }
+        let key = normalize::normalize_text(genre);
+        groups
+            .entry(key.clone())
+            .or_insert_with(|| {
+                order.push(key);
+                GenreSummary {
+                    name: genre.to_string(),
+                    track_count: 0,
+                }
+            })
+            .track_count += 1;
+    }
+
+    let mut genres: Vec<GenreSummary> = order
+        .into_iter()
+        .filter_map(|key| groups.remove(&key))
+        .collect();
+    genres.sort_by_cached_key(|g| pinyin::collated(&g.name));
+    genres
+}
+
+/// 按年代分组，没有年份的曲目不计入
+pub fn group_decades(tracks: &[LibraryTrack]) -> Vec<DecadeSummary> {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for year in tracks.iter().filter_map(|t| t.year) {
+        *counts.entry(year / 10 * 10).or_default() += 1;
+    }
+
+    let mut decades: Vec<DecadeSummary> = counts
+        .into_iter()
+        .map(|(decade, track_count)| DecadeSummary {
+            decade,
+            track_count,
+        })
+        .collect();
+    decades.sort_by_key(|d| d.decade);
+    decades
+}
+
+/// 获取所有专辑，指定艺术家时只返回包含该艺术家曲目的专辑（按年份排序）
+#[tauri::command]
+pub async fn get_albums(
+    app: AppHandle,
+    artist: Option<String>,
+) -> Result<Vec<AlbumSummary>, String> {
+    let library = load_library_from_file(&app)?;
+
+    match artist {
+        Some(artist) => {
+            let keys: HashSet<String> = library
+                .tracks
+                .iter()
+                .filter(|t| {
+                    t.artist_names()
+                        .iter()
+                        .any(|name| normalize::normalized_eq(name, &artist))
+                })
+                .map(album_group_key)
+                .collect();
+            let mut albums: Vec<AlbumSummary> = group_albums(&library.tracks)
+                .into_iter()
+                .filter(|album| keys.contains(&album.key))
+                .collect();
+            albums.sort_by_cached_key(|a| (a.year, pinyin::collated(&a.title)));
+            Ok(albums)
+        }
+        None => Ok(group_albums(&library.tracks)),
+    }
+}
+
+/// 获取专辑曲目，按碟号和音轨号排序
+#[tauri::command]
+pub async fn get_album_tracks(
+    app: AppHandle,
+    album_key: String,
+) -> Result<Vec<LibraryTrack>, String> {
+    let library = load_library_from_file(&app)?;
+    let mut tracks: Vec<LibraryTrack> = library
+        .tracks
+        .into_iter()
+        .filter(|t| album_group_key(t) == album_key)
+        .collect();
+    tracks.sort_by(compare_album_order);
+    Ok(tracks)
+}
+
+/// 获取所有艺术家
+#[tauri::command]
+pub async fn get_artists(app: AppHandle) -> Result<Vec<ArtistSummary>, String> {
+    let library = load_library_from_file(&app)?;
+    Ok(group_artists(&library.tracks))
+}
+
+/// 获取所有流派
+#[tauri::command]
+pub async fn get_genres(app: AppHandle) -> Result<Vec<GenreSummary>, String> {
+    let library = load_library_from_file(&app)?;
+    Ok(group_genres(&library.tracks))
+}
+
+/// 获取所有年代
+#[tauri::command]
+pub async fn get_decades(app: AppHandle) -> Result<Vec<DecadeSummary>, String> {
+    let library = load_library_from_file(&app)?;
+    Ok(group_decades(&library.tracks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn track(id: &str, title: &str, artist: &str, album: &str) -> LibraryTrack {
+        test_support::track(id)
+            .title(title)
+            .artist(artist)
+            .album(album)
+            .duration(200.0)
+            .build()
+    }
+
+    #[test]
+    fn test_group_albums_merges_normalized_names() {
+        let mut a = track("1", "吻别", "張學友", "吻別");
+        a.year = Some(1993);
+        let b = track("2", "我真的受伤了", "张学友", "吻别");
+        let c = track("3", "Yesterday", "The Beatles", "Help!");
+
+        let albums = group_albums(&[a, b, c]);
+        assert_eq!(albums.len(), 2);
+
+        let album = albums.iter().find(|a| a.title == "吻別").unwrap();
+        assert_eq!(album.track_count, 2);
+        assert_eq!(album.total_duration, 400.0);
+        assert_eq!(album.year, Some(1993));
+        assert_eq!(album.album_artist, "張學友");
+    }
+
+    #[test]
+    fn test_compilation_without_album_artist() {
+        let tracks = vec![
+            track("1", "A", "Artist A", "Hits"),
+            track("2", "B", "Artist B", "Hits"),
+        ];
+        // 没有专辑艺术家时按曲目艺术家分组
+        assert_eq!(group_albums(&tracks).len(), 2);
+
+        let compilation: Vec<LibraryTrack> = tracks
+            .iter()
+            .cloned()
+            .map(|mut t| {
+                t.compilation = true;
+                t
+            })
+            .collect();
+        let albums = group_albums(&compilation);
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].album_artist, VARIOUS_ARTISTS);
+
+        let tracks: Vec<LibraryTrack> = tracks
+            .into_iter()
+            .map(|mut t| {
+                t.album_artist = Some("Various Artists".to_string());
+                t
+            })
+            .collect();
+        let albums = group_albums(&tracks);
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].album_artist, "Various Artists");
+    }
+
+    #[test]
+    fn test_album_track_order() {
+        let mut tracks = vec![
+            track("1", "Disc 2 Track 1", "X", "Album"),
+            track("2", "Disc 1 Track 2", "X", "Album"),
+            track("3", "Disc 1 Track 1", "X", "Album"),
+        ];
+        tracks[0].disc_number = Some(2);
+        tracks[0].track_number = Some(1);
+        tracks[1].track_number = Some(2);
+        tracks[2].disc_number = Some(1);
+        tracks[2].track_number = Some(1);
+
+        tracks.sort_by(compare_album_order);
+        let ids: Vec<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "2", "1"]);
+
+        let albums = group_albums(&tracks);
+        assert_eq!(albums[0].cover_track_id, "3");
+    }
+
+    #[test]
+    fn test_artists_genres_and_decades() {
+        let mut tracks = vec![
+            track("1", "Yesterday", "The Beatles", "Help!"),
+            track("2", "Help!", "The Beatles", "Help!"),
+            track("3", "Hey Jude", "the beatles", "Hey Jude"),
+            track("4", "晴天", "周杰伦", "叶惠美"),
+        ];
+        tracks[0].year = Some(1965);
+        tracks[1].year = Some(1965);
+        tracks[2].year = Some(1968);
+        tracks[3].year = Some(2003);
+        tracks[0].genre = Some("Rock".to_string());
+        tracks[2].genre = Some("rock".to_string());
+        tracks[3].genre = Some("Pop".to_string());
+
+        tracks[3].artists = vec!["周杰伦".to_string(), "费玉清".to_string()];
+
+        let artists = group_artists(&tracks);
+        let names: Vec<&str> = artists.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["费玉清", "The Beatles", "周杰伦"]);
+        assert_eq!(artists[1].album_count, 2);
+        assert_eq!(artists[1].track_count, 3);
+        assert_eq!(artists[2].track_count, 1);
+
+        let genres = group_genres(&tracks);
+        let genres: Vec<(&str, usize)> = genres
+            .iter()
+            .map(|g| (g.name.as_str(), g.track_count))
+            .collect();
+        assert_eq!(genres, vec![("Pop", 1), ("Rock", 2)]);
+
+        let decades = group_decades(&tracks);
+        let decades: Vec<(u32, usize)> =
+            decades.iter().map(|d| (d.decade, d.track_count)).collect();
+        assert_eq!(decades, vec![(1960, 3), (2000, 1)]);
+    }
+}
@@ -0,0 +1,456 @@
+use super::*;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Read;
+
+/// 标签匹配时允许的时长差（秒）
+const DURATION_TOLERANCE: f64 = 2.0;
+/// 声纹比较前按时长预筛选，时长相差过大的不比较（秒）
+const ACOUSTIC_DURATION_TOLERANCE: f64 = 15.0;
+
+/// 重复的判断依据，按可信度从低到高
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DuplicateLevel {
+    /// 规范化后的艺术家和标题相同，时长相差不超过2秒
+    Tags,
+    /// 声纹相同，如同一录音的不同编码版本
+    Acoustic,
+    /// 文件内容完全相同
+    Identical,
+}
+
+/// 一组重复的曲目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub level: DuplicateLevel,
+    pub track_ids: Vec<String>,
+    /// 建议保留的曲目
+    pub keep: String,
+    /// 声纹匹配时组内最低的相似度
+    pub similarity: Option<f32>,
+}
+
+/// 重复检测结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub errors: Vec<String>,
+}
+
+// 编码的优先级：无损 > 较新的有损编码 > MP3
+fn codec_rank(track: &LibraryTrack) -> u32 {
+    if track.audio.lossless {
+        return 3;
+    }
+    match track.audio.codec.as_deref() {
+        Some("AAC" | "Opus" | "Vorbis") => 2,
+        Some("MP3") => 1,
+        _ => 0,
+    }
+}
+
+// 已填写的标签数
+fn tag_completeness(track: &LibraryTrack) -> usize {
+    [
+        track.artist != "Unknown Artist",
+        track.album != "Unknown Album",
+        track.album_artist.is_some(),
+        track.genre.is_some(),
+        track.year.is_some(),
+        track.track_number.is_some(),
+        track.disc_number.is_some(),
+        track.composer.is_some(),
+        track.lyrics.is_some(),
+        track.musicbrainz.recording_id.is_some(),
+    ]
+    .iter()
+    .filter(|filled| **filled)
+    .count()
+}
+
+/// 从一组重复曲目中选出建议保留的：依次比较编码、比特率和标签完整度
+pub fn suggest_keep<'a>(tracks: &[&'a LibraryTrack]) -> Option<&'a LibraryTrack> {
+    tracks.iter().copied().max_by(|a, b| {
+        codec_rank(a)
+            .cmp(&codec_rank(b))
+            .then_with(|| {
+                a.audio
+                    .bitrate()
+                    .unwrap_or(0)
+                    .cmp(&b.audio.bitrate().unwrap_or(0))
+            })
+            .then_with(|| tag_completeness(a).cmp(&tag_completeness(b)))
+            // 条件相同时保留路径较短的
+            .then_with(|| b.file_path.len().cmp(&a.file_path.len()))
+    })
+}
+
+fn make_group(
+    level: DuplicateLevel,
+    tracks: &[&LibraryTrack],
+    similarity: Option<f32>,
+) -> Option<DuplicateGroup> {
+    if tracks.len() < 2 {
+        return None;
+    }
+    Some(DuplicateGroup {
+        level,
+        track_ids: tracks.iter().map(|t| t.id.clone()).collect(),
+        keep: suggest_keep(tracks)?.id.clone(),
+        similarity,
+    })
+}
+
+/// 按规范化的艺术家和标题分组，再按时长拆分
+pub fn find_tag_duplicates(tracks: &[LibraryTrack]) -> Vec<DuplicateGroup> {
+    let mut by_key: HashMap<String, Vec<&LibraryTrack>> = HashMap::new();
+    for track in tracks {
+        let key = format!(
+            "{}\u{1F}{}",
+            normalize::normalize_text(&track.artist),
+            normalize::normalize_text(&track.title)
+        );
+        by_key.entry(key).or_default().push(track);
+    }
+
+    let mut groups = Vec::new();
+    for mut candidates in by_key.into_values().filter(|c| c.len() > 1) {
+        candidates.sort_by(|a, b| a.duration.total_cmp(&b.duration));
+        let mut cluster: Vec<&LibraryTrack> = Vec::new();
+        for track in candidates {
+            if let Some(first) = cluster.first() {
+                if track.duration - first.duration > DURATION_TOLERANCE {
+                    groups.extend(make_group(DuplicateLevel::Tags, &cluster, None));
+                    cluster.clear();
+                }
+            }
+            cluster.push(track);
+        }
+        groups.extend(make_group(DuplicateLevel::Tags, &cluster, None));
+    }
+    groups
+}
+
+fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// 只对大小相同的文件计算哈希
+async fn find_identical_duplicates(
+    tracks: &[LibraryTrack],
+    errors: &mut Vec<String>,
+) -> Vec<DuplicateGroup> {
+    let mut by_size: HashMap<u64, Vec<&LibraryTrack>> = HashMap::new();
+    for track in tracks.iter().filter(|t| t.file_size > 0) {
+        by_size.entry(track.file_size).or_default().push(track);
+    }
+    let candidates: HashMap<PathBuf, &LibraryTrack> = by_size
+        .into_values()
+        .filter(|c| c.len() > 1)
+        .flatten()
+        .map(|t| (PathBuf::from(&t.file_path), t))
+        .collect();
+
+    let files = candidates.keys().cloned().collect();
+    let mut results = probe_in_parallel(&ScanJob::new(), files, hash_file);
+    let mut by_hash: HashMap<String, Vec<&LibraryTrack>> = HashMap::new();
+    while let Some((path, result)) = results.next().await {
+        match result {
+            Ok(hash) => {
+                if let Some(track) = candidates.get(&path) {
+                    by_hash.entry(hash).or_default().push(track);
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", path.to_string_lossy(), e)),
+        }
+    }
+
+    by_hash
+        .into_values()
+        .filter_map(|tracks| make_group(DuplicateLevel::Identical, &tracks, None))
+        .collect()
+}
+
+/// 缓存的声纹，文件未修改时复用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFingerprint {
+    file_size: u64,
+    modified_at: i64,
+    fingerprint: Vec<u32>,
+}
+
+fn get_fingerprint_cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join("fingerprints.json"))
+}
+
+// 缓存损坏时重新计算即可
+fn load_fingerprint_cache(app: &AppHandle) -> HashMap<String, CachedFingerprint> {
+    get_fingerprint_cache_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_fingerprint_cache(
+    app: &AppHandle,
+    cache: &HashMap<String, CachedFingerprint>,
+) -> Result<(), String> {
+    let path = get_fingerprint_cache_path(app)?;
+    let content = serde_json::to_string(cache)
+        .map_err(|e| format!("Failed to serialize fingerprint cache: {}", e))?;
+    fs::write(path, content).map_err(|e| format!("Failed to write fingerprint cache: {}", e))
+}
+
+/// 已缓存的声纹，按曲目ID索引，不计算缺失的声纹
+pub(crate) fn cached_fingerprints(app: &AppHandle) -> HashMap<String, Vec<u32>> {
+    load_fingerprint_cache(app)
+        .into_iter()
+        .map(|(id, cached)| (id, cached.fingerprint))
+        .collect()
+}
+
+// 计算缺失的声纹，已删除曲目的缓存一并清理
+async fn load_fingerprints(
+    app: &AppHandle,
+    tracks: &[LibraryTrack],
+    errors: &mut Vec<String>,
+) -> HashMap<String, Vec<u32>> {
+    let mut cache = load_fingerprint_cache(app);
+    let ids: HashSet<&str> = tracks.iter().map(|t| t.id.as_str()).collect();
+    cache.retain(|id, _| ids.contains(id.as_str()));
+
+    let stale: HashMap<PathBuf, &LibraryTrack> = tracks
+        .iter()
+        .filter(|track| {
+            cache.get(&track.id).is_none_or(|cached| {
+                cached.file_size != track.file_size || cached.modified_at != track.modified_at
+            })
+        })
+        .map(|track| (PathBuf::from(&track.file_path), track))
+        .collect();
+
+    let files = stale.keys().cloned().collect();
+    let mut results = probe_in_parallel(&ScanJob::new(), files, fingerprint::fingerprint_file);
+    while let Some((path, result)) = results.next().await {
+        let Some(track) = stale.get(&path) else {
+            continue;
+        };
+        match result {
+            Ok(fingerprint) => {
+                cache.insert(
+                    track.id.clone(),
+                    CachedFingerprint {
+                        file_size: track.file_size,
+                        modified_at: track.modified_at,
+                        fingerprint,
+                    },
+                );
+            }
+            Err(e) => errors.push(format!("{}: {}", path.to_string_lossy(), e)),
+        }
+    }
+
+    if let Err(e) = save_fingerprint_cache(app, &cache) {
+        errors.push(e);
+    }
+    cache
+        .into_iter()
+        .map(|(id, cached)| (id, cached.fingerprint))
+        .collect()
+}
+
+fn find_root(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    parents[index] = root;
+    root
+}
+
+/// 按声纹分组，已确认内容完全相同的曲目之间不再比较
+pub fn find_acoustic_duplicates(
+    tracks: &[LibraryTrack],
+    fingerprints: &HashMap<String, Vec<u32>>,
+    identical: &[DuplicateGroup],
+) -> Vec<DuplicateGroup> {
+    let identical_group: HashMap<&str, usize> = identical
+        .iter()
+        .enumerate()
+        .flat_map(|(group, g)| g.track_ids.iter().map(move |id| (id.as_str(), group)))
+        .collect();
+
+    let mut candidates: Vec<&LibraryTrack> = tracks
+        .iter()
+        .filter(|t| fingerprints.contains_key(&t.id))
+        .collect();
+    candidates.sort_by(|a, b| a.duration.total_cmp(&b.duration));
+
+    let mut parents: Vec<usize> = (0..candidates.len()).collect();
+    let mut similarity: HashMap<usize, f32> = HashMap::new();
+    for (i, a) in candidates.iter().enumerate() {
+        for (j, b) in candidates.iter().enumerate().skip(i + 1) {
+            if b.duration - a.duration > ACOUSTIC_DURATION_TOLERANCE {
+                break;
+            }
+            let same_file = identical_group
+                .get(a.id.as_str())
+                .is_some_and(|group| identical_group.get(b.id.as_str()) == Some(group));
+            if same_file {
+                continue;
+            }
+            if let Some(score) =
+                fingerprint::fingerprint_similarity(&fingerprints[&a.id], &fingerprints[&b.id])
+            {
+                let (root_a, root_b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                let merged = [root_a, root_b]
+                    .iter()
+                    .filter_map(|root| similarity.get(root))
+                    .fold(score, |min, s| min.min(*s));
+                parents[root_b] = root_a;
+                similarity.insert(root_a, merged);
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<&LibraryTrack>> = HashMap::new();
+    for (index, track) in candidates.iter().enumerate() {
+        let root = find_root(&mut parents, index);
+        clusters.entry(root).or_default().push(track);
+    }
+    clusters
+        .into_iter()
+        .filter_map(|(root, tracks)| {
+            make_group(
+                DuplicateLevel::Acoustic,
+                &tracks,
+                similarity.get(&root).copied(),
+            )
+        })
+        .collect()
+}
+
+/// 检测音乐库中的重复曲目
+///
+/// `acoustic` 为 true 时解码音频计算声纹，耗时较长，声纹会缓存到应用数据目录
+#[tauri::command]
+pub async fn find_duplicates(app: AppHandle, acoustic: bool) -> Result<DuplicateReport, String> {
+    let library = load_library_from_file(&app)?;
+    let mut report = DuplicateReport::default();
+
+    let identical = find_identical_duplicates(&library.tracks, &mut report.errors).await;
+    if acoustic {
+        let fingerprints = load_fingerprints(&app, &library.tracks, &mut report.errors).await;
+        report.groups.extend(find_acoustic_duplicates(
+            &library.tracks,
+            &fingerprints,
+            &identical,
+        ));
+    }
+    report.groups.extend(identical);
+    report.groups.extend(find_tag_duplicates(&library.tracks));
+
+    // 可信度高的在前，同级按建议保留的曲目排序保证结果稳定
+    report
+        .groups
+        .sort_by(|a, b| b.level.cmp(&a.level).then_with(|| a.keep.cmp(&b.keep)));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn track(id: &str, artist: &str, title: &str, duration: f64) -> LibraryTrack {
+        test_support::track(id)
+            .artist(artist)
+            .title(title)
+            .album("Unknown Album")
+            .duration(duration)
+            .build()
+    }
+
+    #[test]
+    fn test_tag_duplicates_use_normalized_text_and_duration() {
+        let tracks = vec![
+            track("1", "周杰倫", "晴天", 269.0),
+            track("2", "周杰伦", " 晴天", 270.5),
+            track("3", "周杰伦", "晴天", 300.0),
+            track("4", "Jay Chou", "晴天", 269.0),
+        ];
+        let groups = find_tag_duplicates(&tracks);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].track_ids, vec!["1", "2"]);
+    }
+
+    #[test]
+    fn test_suggest_keep_prefers_quality_then_tags() {
+        let mut mp3 = track("mp3", "A", "B", 200.0);
+        mp3.audio.codec = Some("MP3".to_string());
+        mp3.audio.audio_bitrate = Some(320);
+        mp3.year = Some(2000);
+        let mut flac = track("flac", "A", "B", 200.0);
+        flac.audio.codec = Some("FLAC".to_string());
+        flac.audio.lossless = true;
+        flac.audio.audio_bitrate = Some(900);
+        let mut tagged = mp3.clone();
+        tagged.id = "tagged".to_string();
+        tagged.genre = Some("Pop".to_string());
+
+        assert_eq!(suggest_keep(&[&mp3, &flac]).unwrap().id, "flac");
+        assert_eq!(suggest_keep(&[&mp3, &tagged]).unwrap().id, "tagged");
+    }
+
+    #[test]
+    fn test_acoustic_groups_skip_identical_files() {
+        let tracks = vec![
+            track("1", "A", "Song", 200.0),
+            track("2", "B", "Song (Remaster)", 201.0),
+            track("3", "C", "Copy", 200.0),
+            track("4", "D", "Other", 200.5),
+        ];
+        let same: Vec<u32> = (0..100u32).map(|i| i.wrapping_mul(2654435761)).collect();
+        let other: Vec<u32> = (0..100u32).map(|i| !i.wrapping_mul(2654435761)).collect();
+        let fingerprints: HashMap<String, Vec<u32>> = [
+            ("1", same.clone()),
+            ("2", same.clone()),
+            ("3", same),
+            ("4", other),
+        ]
+        .into_iter()
+        .map(|(id, fp)| (id.to_string(), fp))
+        .collect();
+
+        let identical = vec![DuplicateGroup {
+            level: DuplicateLevel::Identical,
+            track_ids: vec!["1".to_string(), "3".to_string()],
+            keep: "1".to_string(),
+            similarity: None,
+        }];
+        let groups = find_acoustic_duplicates(&tracks, &fingerprints, &identical);
+        assert_eq!(groups.len(), 1);
+        let mut ids = groups[0].track_ids.clone();
+        ids.sort();
+        // 1和3内容相同不比较，但都与2声纹相同，仍归为一组
+        assert_eq!(ids, vec!["1", "2", "3"]);
+        assert_eq!(groups[0].similarity, Some(1.0));
+    }
+}
@@ -0,0 +1,696 @@
+use super::*;
+use crate::history::{record_operation, HistoryChange, TagChange};
+use lofty::config::WriteOptions;
+use lofty::picture::{MimeType, Picture, PictureType};
+use lofty::tag::{Tag, TagType};
+use std::sync::Arc;
+
+/// 批量编辑时单个字段的操作，未指定的字段保持原值
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", content = "value", rename_all = "camelCase")]
+pub enum FieldEdit<T> {
+    #[default]
+    Keep,
+    Set(T),
+    Clear,
+}
+
+impl<T> FieldEdit<T> {
+    pub fn is_keep(&self) -> bool {
+        matches!(self, FieldEdit::Keep)
+    }
+}
+
+/// 标签编辑内容，同时用于单曲和批量编辑
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagEdit {
+    pub title: FieldEdit<String>,
+    pub artist: FieldEdit<String>,
+    pub album: FieldEdit<String>,
+    pub album_artist: FieldEdit<String>,
+    pub genre: FieldEdit<String>,
+    pub year: FieldEdit<u32>,
+    pub track_number: FieldEdit<u32>,
+    pub track_total: FieldEdit<u32>,
+    pub disc_number: FieldEdit<u32>,
+    pub disc_total: FieldEdit<u32>,
+    pub lyrics: FieldEdit<String>,
+    /// 封面图片文件路径
+    pub cover: FieldEdit<String>,
+    /// 评分（半星为单位，0-10）
+    pub rating: FieldEdit<u8>,
+    pub play_count: FieldEdit<u32>,
+}
+
+impl TagEdit {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_keep()
+            && self.artist.is_keep()
+            && self.album.is_keep()
+            && self.album_artist.is_keep()
+            && self.genre.is_keep()
+            && self.year.is_keep()
+            && self.track_number.is_keep()
+            && self.track_total.is_keep()
+            && self.disc_number.is_keep()
+            && self.disc_total.is_keep()
+            && self.lyrics.is_keep()
+            && self.cover.is_keep()
+            && self.rating.is_keep()
+            && self.play_count.is_keep()
+    }
+
+    /// 撤销这次编辑用的编辑：修改过的字段恢复为曲目原来的值
+    ///
+    /// 没有保存原来的封面图片，封面无法恢复；"Unknown Artist"、以文件名作标题等
+    /// 显示用的占位值不是原来的标签，撤销时清除该字段
+    pub fn inverse(&self, track: &LibraryTrack) -> TagEdit {
+        let text = |field: guess::GuessField, value: &str| {
+            Some(value.to_string()).filter(|v| !guess::is_placeholder(field, v, track))
+        };
+        TagEdit {
+            title: restore_field(&self.title, text(guess::GuessField::Title, &track.title)),
+            artist: restore_field(&self.artist, text(guess::GuessField::Artist, &track.artist)),
+            album: restore_field(&self.album, text(guess::GuessField::Album, &track.album)),
+            album_artist: restore_field(&self.album_artist, track.album_artist.clone()),
+            genre: restore_field(
+                &self.genre,
+                track
+                    .genre
+                    .as_deref()
+                    .and_then(|genre| text(guess::GuessField::Genre, genre)),
+            ),
+            year: restore_field(&self.year, track.year),
+            track_number: restore_field(&self.track_number, track.track_number),
+            track_total: restore_field(&self.track_total, track.track_total),
+            disc_number: restore_field(&self.disc_number, track.disc_number),
+            disc_total: restore_field(&self.disc_total, track.disc_total),
+            lyrics: restore_field(&self.lyrics, track.lyrics.clone()),
+            cover: FieldEdit::Keep,
+            rating: restore_field(&self.rating, track.rating),
+            play_count: restore_field(&self.play_count, Some(track.play_count)),
+        }
+    }
+
+    // 数值字段不能为0，年份需为四位数以内
+    fn validate(&self) -> Result<(), String> {
+        let numbers = [
+            ("track number", &self.track_number),
+            ("track total", &self.track_total),
+            ("disc number", &self.disc_number),
+            ("disc total", &self.disc_total),
+        ];
+        for (name, edit) in numbers {
+            if let FieldEdit::Set(0) = edit {
+                return Err(format!("Invalid {}: must be greater than 0", name));
+            }
+        }
+        if let FieldEdit::Set(year) = self.year {
+            if year == 0 || year > 9999 {
+                return Err(format!("Invalid year: {}", year));
+            }
+        }
+        if let FieldEdit::Set(rating) = self.rating {
+            if rating > rating::MAX_RATING {
+                return Err(format!("Invalid rating: {}", rating));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 被编辑的字段恢复为原值，原来没有值时清除
+fn restore_field<T>(edit: &FieldEdit<T>, previous: Option<T>) -> FieldEdit<T> {
+    if edit.is_keep() {
+        return FieldEdit::Keep;
+    }
+    previous.map(FieldEdit::Set).unwrap_or(FieldEdit::Clear)
+}
+
+/// 记录已写入文件的标签编辑以便撤销，`edits` 为编辑前的曲目和对应的编辑
+pub(crate) fn record_tag_edits(
+    app: &AppHandle,
+    label: &str,
+    edits: Vec<(&LibraryTrack, &TagEdit)>,
+) -> Result<(), String> {
+    let changes: Vec<TagChange> = edits
+        .into_iter()
+        .map(|(track, edit)| TagChange {
+            track_id: track.id.clone(),
+            undo: edit.inverse(track),
+            redo: edit.clone(),
+        })
+        .collect();
+    if changes.is_empty() {
+        return Ok(());
+    }
+    record_operation(app, label, vec![HistoryChange::Tags { changes }], None)
+}
+
+/// 批量编辑结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagEditReport {
+    pub updated: Vec<LibraryTrack>,
+    pub errors: Vec<String>,
+}
+
+// 可写入的标签格式：ID3v2、Vorbis Comments、MP4、APE
+fn is_writable_tag_type(tag_type: TagType) -> bool {
+    matches!(
+        tag_type,
+        TagType::Id3v2 | TagType::VorbisComments | TagType::Mp4Ilst | TagType::Ape
+    )
+}
+
+/// 检查文件能否写入标签，只读文件和不支持的格式返回错误
+pub fn validate_tag_target(path: &Path) -> Result<(), String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    if metadata.permissions().readonly() {
+        return Err("File is read-only".to_string());
+    }
+
+    let tagged_file = Probe::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .read()
+        .map_err(|e| format!("Unsupported format: {}", e))?;
+    let tag_type = tagged_file.primary_tag_type();
+    if !is_writable_tag_type(tag_type) {
+        return Err(format!(
+            "Unsupported format for tag writing: {:?} ({:?})",
+            tagged_file.file_type(),
+            tag_type
+        ));
+    }
+    Ok(())
+}
+
+/// 读取封面图片文件，只接受常见图片格式
+pub fn load_cover_picture(path: &str) -> Result<Picture, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read cover image: {}", e))?;
+    let format =
+        image::guess_format(&data).map_err(|_| format!("Unsupported cover image: {}", path))?;
+    let mime_type = match format {
+        image::ImageFormat::Jpeg => MimeType::Jpeg,
+        image::ImageFormat::Png => MimeType::Png,
+        image::ImageFormat::Gif => MimeType::Gif,
+        image::ImageFormat::Bmp => MimeType::Bmp,
+        image::ImageFormat::Tiff => MimeType::Tiff,
+        other => return Err(format!("Unsupported cover image format: {:?}", other)),
+    };
+    Ok(Picture::new_unchecked(
+        PictureType::CoverFront,
+        Some(mime_type),
+        None,
+        data,
+    ))
+}
+
+// 文本字段：设置空白文本等同于清除
+fn apply_text(tag: &mut Tag, key: ItemKey, edit: &FieldEdit<String>) {
+    match edit {
+        FieldEdit::Keep => {}
+        FieldEdit::Set(value) if !value.trim().is_empty() => {
+            tag.insert_text(key, value.trim().to_string());
+        }
+        FieldEdit::Set(_) | FieldEdit::Clear => {
+            tag.remove_key(&key);
+        }
+    }
+}
+
+fn apply_number(
+    tag: &mut Tag,
+    edit: &FieldEdit<u32>,
+    set: fn(&mut Tag, u32),
+    remove: fn(&mut Tag),
+) {
+    match edit {
+        FieldEdit::Keep => {}
+        FieldEdit::Set(value) => set(tag, *value),
+        FieldEdit::Clear => remove(tag),
+    }
+}
+
+/// 将编辑内容应用到标签，封面需预先通过 `load_cover_picture` 读取
+pub fn apply_tag_edit(tag: &mut Tag, edit: &TagEdit, cover: Option<&Picture>) {
+    apply_text(tag, ItemKey::TrackTitle, &edit.title);
+    apply_text(tag, ItemKey::TrackArtist, &edit.artist);
+    // 修改艺术家时移除旧的多值艺术家标签，避免两者不一致
+    if !edit.artist.is_keep() {
+        tag.remove_key(&ItemKey::TrackArtists);
+    }
+    apply_text(tag, ItemKey::AlbumTitle, &edit.album);
+    apply_text(tag, ItemKey::AlbumArtist, &edit.album_artist);
+    // 流派可能有多个条目（如Vorbis的多个GENRE），修改时全部移除后再写入
+    if !edit.genre.is_keep() {
+        tag.remove_key(&ItemKey::Genre);
+    }
+    apply_text(tag, ItemKey::Genre, &edit.genre);
+    apply_text(tag, ItemKey::Lyrics, &edit.lyrics);
+
+    apply_number(tag, &edit.year, Tag::set_year, Tag::remove_year);
+    apply_number(tag, &edit.track_number, Tag::set_track, Tag::remove_track);
+    apply_number(
+        tag,
+        &edit.track_total,
+        Tag::set_track_total,
+        Tag::remove_track_total,
+    );
+    apply_number(tag, &edit.disc_number, Tag::set_disk, Tag::remove_disk);
+    apply_number(
+        tag,
+        &edit.disc_total,
+        Tag::set_disk_total,
+        Tag::remove_disk_total,
+    );
+
+    rating::apply_rating(tag, &edit.rating);
+    rating::apply_play_count(tag, &edit.play_count);
+
+    match (&edit.cover, cover) {
+        (FieldEdit::Set(_), Some(picture)) => {
+            tag.remove_picture_type(PictureType::CoverFront);
+            tag.push_picture(picture.clone());
+        }
+        (FieldEdit::Clear, _) => {
+            while !tag.pictures().is_empty() {
+                tag.remove_picture(0);
+            }
+        }
+        _ => {}
+    }
+}
+
+// 写入单个文件并重新读取元数据和文件指纹
+// 转换标签格式，`re_map` 会丢弃 POPM 等格式专有内容，评分和播放次数按目标格式重新写入
+fn convert_tag(source: &Tag, tag_type: TagType) -> Tag {
+    let mut tag = source.clone();
+    tag.re_map(tag_type);
+    if let Some(rating) = rating::read_rating(source) {
+        rating::apply_rating(&mut tag, &FieldEdit::Set(rating));
+    }
+    if let Some(count) = rating::read_play_count(source) {
+        rating::apply_play_count(&mut tag, &FieldEdit::Set(count));
+    }
+    tag
+}
+
+fn write_file_tags(
+    path: &Path,
+    edit: &TagEdit,
+    cover: Option<&Picture>,
+) -> Result<(Track, FileFingerprint), String> {
+    let tagged_file = Probe::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .read()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let tag_type = tagged_file.primary_tag_type();
+
+    // 没有主标签时（如只有ID3v1的MP3）沿用已有标签内容转换为主标签格式
+    let mut tag = match tagged_file.primary_tag() {
+        Some(tag) => tag.clone(),
+        None => tagged_file
+            .first_tag()
+            .map(|tag| convert_tag(tag, tag_type))
+            .unwrap_or_else(|| Tag::new(tag_type)),
+    };
+
+    apply_tag_edit(&mut tag, edit, cover);
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|e| format!("Failed to write tags: {}", e))?;
+
+    let track = extract_metadata(&path.to_string_lossy())?;
+    let fingerprint = FileFingerprint::read(path)?;
+    Ok((track, fingerprint))
+}
+
+/// 将每个曲目各自的编辑写回文件，并同步更新音乐库中的条目（不保存音乐库）
+///
+/// 写入前先检查所有文件，有只读或不支持的文件时不做任何修改
+pub(crate) async fn write_tag_edits(
+    library: &mut MusicLibrary,
+    edits: Vec<(usize, TagEdit)>,
+    cover: Option<Picture>,
+) -> Result<TagEditReport, String> {
+    let edits: HashMap<PathBuf, (usize, TagEdit)> = edits
+        .into_iter()
+        .filter(|(_, edit)| !edit.is_empty())
+        .map(|(index, edit)| {
+            (
+                PathBuf::from(&library.tracks[index].file_path),
+                (index, edit),
+            )
+        })
+        .collect();
+
+    let files: Vec<PathBuf> = edits.keys().cloned().collect();
+    let mut invalid: Vec<String> = files
+        .iter()
+        .filter_map(|path| {
+            validate_tag_target(path)
+                .err()
+                .map(|e| format!("{}: {}", path.to_string_lossy(), e))
+        })
+        .collect();
+    if !invalid.is_empty() {
+        invalid.sort();
+        return Err(invalid.join("\n"));
+    }
+
+    let edits = Arc::new(edits);
+    let cover = Arc::new(cover);
+    let worker_edits = edits.clone();
+    let mut results = probe_in_parallel(&ScanJob::new(), files, move |path| {
+        let (_, edit) = worker_edits
+            .get(path)
+            .ok_or_else(|| "Missing tag edit".to_string())?;
+        write_file_tags(path, edit, cover.as_ref().as_ref())
+    });
+
+    let mut report = TagEditReport::default();
+    while let Some((path, result)) = results.next().await {
+        let Some((index, edit)) = edits.get(&path) else {
+            continue;
+        };
+        let index = *index;
+        match result {
+            Ok((track, fingerprint)) => {
+                let previous = &library.tracks[index];
+                let mut updated =
+                    LibraryTrack::from_track(track, previous.id.clone(), &fingerprint);
+                // 编辑过的评分和播放次数以编辑内容为准（部分格式无法写入播放次数），
+                // 其他用户数据沿用旧条目
+                updated.keep_user_data(previous);
+                match edit.rating {
+                    FieldEdit::Keep => {}
+                    FieldEdit::Set(rating) => updated.rating = Some(rating).filter(|r| *r > 0),
+                    FieldEdit::Clear => updated.rating = None,
+                }
+                match edit.play_count {
+                    FieldEdit::Keep => {}
+                    FieldEdit::Set(count) => updated.play_count = count,
+                    FieldEdit::Clear => updated.play_count = 0,
+                }
+                library.tracks[index] = updated.clone();
+                report.updated.push(updated);
+            }
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", path.to_string_lossy(), e)),
+        }
+    }
+    if !report.updated.is_empty() {
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+    }
+
+    Ok(report)
+}
+
+// 按ID查找曲目在音乐库中的位置
+pub(crate) fn track_indices(
+    library: &MusicLibrary,
+    track_ids: &[String],
+) -> Result<Vec<usize>, String> {
+    track_ids
+        .iter()
+        .map(|id| {
+            library
+                .tracks
+                .iter()
+                .position(|t| &t.id == id)
+                .ok_or_else(|| format!("Track not found: {}", id))
+        })
+        .collect()
+}
+
+/// 编辑多个曲目的标签并写回文件，音乐库条目同步更新
+///
+/// 写入前先检查所有文件，有只读或不支持的文件时不做任何修改
+#[tauri::command]
+pub async fn edit_tracks_tags(
+    app: AppHandle,
+    track_ids: Vec<String>,
+    edit: TagEdit,
+) -> Result<TagEditReport, String> {
+    edit.validate()?;
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let indices = track_indices(&library, &track_ids)?;
+    if edit.is_empty() || indices.is_empty() {
+        return Ok(TagEditReport::default());
+    }
+
+    let cover = match &edit.cover {
+        FieldEdit::Set(path) => Some(load_cover_picture(path)?),
+        _ => None,
+    };
+    // 编辑前的条目，用于生成撤销记录
+    let previous: HashMap<String, LibraryTrack> = indices
+        .iter()
+        .map(|index| {
+            (
+                library.tracks[*index].id.clone(),
+                library.tracks[*index].clone(),
+            )
+        })
+        .collect();
+    let edits = indices
+        .into_iter()
+        .map(|index| (index, edit.clone()))
+        .collect();
+    let report = write_tag_edits(&mut library, edits, cover).await?;
+
+    if !report.updated.is_empty() {
+        save_library_to_file(&app, &library)?;
+        let changed: Vec<String> = report.updated.iter().map(|t| t.id.clone()).collect();
+        search::update_search_index(&app, &library, &changed, &[]);
+
+        let edits = report
+            .updated
+            .iter()
+            .filter_map(|track| previous.get(&track.id))
+            .map(|track| (track, &edit))
+            .collect();
+        record_tag_edits(&app, "Edit tags", edits)?;
+    }
+
+    Ok(report)
+}
+
+/// 编辑单个曲目的标签，返回更新后的音乐库条目
+#[tauri::command]
+pub async fn edit_track_tags(
+    app: AppHandle,
+    track_id: String,
+    edit: TagEdit,
+) -> Result<LibraryTrack, String> {
+    let report = edit_tracks_tags(app.clone(), vec![track_id.clone()], edit).await?;
+    if let Some(error) = report.errors.into_iter().next() {
+        return Err(error);
+    }
+    match report.updated.into_iter().next() {
+        Some(track) => Ok(track),
+        None => load_library_from_file(&app)?
+            .tracks
+            .into_iter()
+            .find(|t| t.id == track_id)
+            .ok_or_else(|| format!("Track not found: {}", track_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+    use lofty::tag::{ItemValue, TagItem};
+
+    fn sample_tag() -> Tag {
+        let mut tag = Tag::new(TagType::Id3v2);
+        tag.insert_text(ItemKey::TrackTitle, "晴天".to_string());
+        tag.insert_text(ItemKey::TrackArtist, "周杰伦".to_string());
+        tag.insert_text(ItemKey::TrackArtists, "周杰伦;费玉清".to_string());
+        tag.insert_text(ItemKey::AlbumTitle, "叶惠美".to_string());
+        tag.insert_text(ItemKey::Genre, "Pop".to_string());
+        tag.set_year(2003);
+        tag.set_track(3);
+        tag
+    }
+
+    #[test]
+    fn test_convert_tag_keeps_rating_and_play_count() {
+        let mut source = sample_tag();
+        rating::apply_rating(&mut source, &FieldEdit::Set(8));
+        rating::apply_play_count(&mut source, &FieldEdit::Set(5));
+        // 读取文件时 POPM 帧作为格式专有内容保存在通用标签中
+        let source = Tag::from(lofty::id3::v2::Id3v2Tag::from(source));
+
+        let tag = convert_tag(&source, TagType::VorbisComments);
+        assert_eq!(tag.tag_type(), TagType::VorbisComments);
+        assert_eq!(tag.get_string(&ItemKey::AlbumTitle), Some("叶惠美"));
+        assert_eq!(rating::read_rating(&tag), Some(8));
+        assert_eq!(rating::read_play_count(&tag), Some(5));
+    }
+
+    #[test]
+    fn test_keep_set_clear() {
+        let mut tag = sample_tag();
+        let edit = TagEdit {
+            album: FieldEdit::Set("  七里香 ".to_string()),
+            genre: FieldEdit::Clear,
+            year: FieldEdit::Set(2004),
+            track_number: FieldEdit::Clear,
+            disc_number: FieldEdit::Set(1),
+            ..Default::default()
+        };
+        apply_tag_edit(&mut tag, &edit, None);
+
+        assert_eq!(tag.title().as_deref(), Some("晴天"));
+        assert_eq!(tag.album().as_deref(), Some("七里香"));
+        assert!(tag.get_string(&ItemKey::Genre).is_none());
+        assert_eq!(tag.year(), Some(2004));
+        assert_eq!(tag.track(), None);
+        assert_eq!(tag.disk(), Some(1));
+        // 未修改艺术家时保留多值标签
+        assert!(tag.get_string(&ItemKey::TrackArtists).is_some());
+    }
+
+    #[test]
+    fn test_artist_edit_replaces_multi_value() {
+        let mut tag = sample_tag();
+        let edit = TagEdit {
+            artist: FieldEdit::Set("Jay Chou".to_string()),
+            title: FieldEdit::Set("   ".to_string()),
+            ..Default::default()
+        };
+        apply_tag_edit(&mut tag, &edit, None);
+
+        assert_eq!(tag.artist().as_deref(), Some("Jay Chou"));
+        assert!(tag.get_string(&ItemKey::TrackArtists).is_none());
+        assert!(tag.title().is_none());
+    }
+
+    #[test]
+    fn test_genre_edit_replaces_multi_value() {
+        let mut tag = Tag::new(TagType::VorbisComments);
+        for genre in ["Rock", "Pop"] {
+            tag.push(TagItem::new(
+                ItemKey::Genre,
+                ItemValue::Text(genre.to_string()),
+            ));
+        }
+        let edit = TagEdit {
+            genre: FieldEdit::Set("Jazz".to_string()),
+            ..Default::default()
+        };
+        apply_tag_edit(&mut tag, &edit, None);
+        assert_eq!(
+            tag.get_strings(&ItemKey::Genre).collect::<Vec<_>>(),
+            vec!["Jazz"]
+        );
+
+        let edit = TagEdit {
+            genre: FieldEdit::Clear,
+            ..Default::default()
+        };
+        tag.push(TagItem::new(
+            ItemKey::Genre,
+            ItemValue::Text("Pop".to_string()),
+        ));
+        apply_tag_edit(&mut tag, &edit, None);
+        assert_eq!(tag.get_strings(&ItemKey::Genre).count(), 0);
+    }
+
+    #[test]
+    fn test_cover_replace_and_clear() {
+        let mut tag = sample_tag();
+        let picture = |data: &[u8]| {
+            Picture::new_unchecked(
+                PictureType::CoverFront,
+                Some(MimeType::Png),
+                None,
+                data.to_vec(),
+            )
+        };
+        tag.push_picture(picture(b"old"));
+
+        let edit = TagEdit {
+            cover: FieldEdit::Set("cover.png".to_string()),
+            ..Default::default()
+        };
+        apply_tag_edit(&mut tag, &edit, Some(&picture(b"new")));
+        assert_eq!(tag.pictures().len(), 1);
+        assert_eq!(tag.pictures()[0].data(), b"new");
+
+        let edit = TagEdit {
+            cover: FieldEdit::Clear,
+            ..Default::default()
+        };
+        apply_tag_edit(&mut tag, &edit, None);
+        assert!(tag.pictures().is_empty());
+    }
+
+    #[test]
+    fn test_edit_deserialize_and_validate() {
+        let edit: TagEdit = serde_json::from_str(
+            r#"{"title":{"action":"set","value":"Song"},"genre":{"action":"clear"},"year":{"action":"keep"}}"#,
+        )
+        .unwrap();
+        assert_eq!(edit.title, FieldEdit::Set("Song".to_string()));
+        assert_eq!(edit.genre, FieldEdit::Clear);
+        assert!(edit.year.is_keep() && edit.artist.is_keep());
+        assert!(edit.validate().is_ok());
+
+        let invalid = TagEdit {
+            track_number: FieldEdit::Set(0),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(TagEdit::default().is_empty());
+    }
+
+    #[test]
+    fn test_inverse_edit() {
+        let track = LibraryTrack {
+            title: "晴天".to_string(),
+            year: Some(2003),
+            rating: None,
+            ..Default::default()
+        };
+        let edit = TagEdit {
+            title: FieldEdit::Set("七里香".to_string()),
+            genre: FieldEdit::Clear,
+            year: FieldEdit::Set(2004),
+            rating: FieldEdit::Set(8),
+            cover: FieldEdit::Clear,
+            ..Default::default()
+        };
+        let inverse = edit.inverse(&track);
+        assert_eq!(inverse.title, FieldEdit::Set("晴天".to_string()));
+        assert_eq!(inverse.genre, FieldEdit::Clear);
+        assert_eq!(inverse.year, FieldEdit::Set(2003));
+        assert_eq!(inverse.rating, FieldEdit::Clear);
+        assert!(inverse.artist.is_keep() && inverse.cover.is_keep());
+
+        // 没有标签的文件，占位值不写回标签
+        let untagged = test_support::track("1")
+            .title("01 Intro")
+            .artist("Unknown Artist")
+            .album("Unknown Album")
+            .genre("Unknown")
+            .path("/music/01 Intro.mp3")
+            .build();
+        let edit = TagEdit {
+            title: FieldEdit::Set("Intro".to_string()),
+            artist: FieldEdit::Set("The xx".to_string()),
+            album: FieldEdit::Set("xx".to_string()),
+            genre: FieldEdit::Set("Indie".to_string()),
+            ..Default::default()
+        };
+        let inverse = edit.inverse(&untagged);
+        assert_eq!(inverse.title, FieldEdit::Clear);
+        assert_eq!(inverse.artist, FieldEdit::Clear);
+        assert_eq!(inverse.album, FieldEdit::Clear);
+        assert_eq!(inverse.genre, FieldEdit::Clear);
+    }
+}
@@ -0,0 +1,462 @@
+use super::*;
+use crate::history::{record_operation, HistoryChange};
+use crate::scan::ScanReporter;
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, BIG5, GBK, SHIFT_JIS, UTF_8};
+use lofty::config::WriteOptions;
+use lofty::tag::{Tag, TagType};
+
+/// 可能被误标为Latin-1的旧编码
+const LEGACY_ENCODINGS: [&Encoding; 3] = [GBK, BIG5, SHIFT_JIS];
+
+/// 可修复的文本标签字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextTagField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    AlbumArtist,
+    Composer,
+    Comment,
+    Lyrics,
+}
+
+impl TextTagField {
+    pub const ALL: [TextTagField; 8] = [
+        TextTagField::Title,
+        TextTagField::Artist,
+        TextTagField::Album,
+        TextTagField::Genre,
+        TextTagField::AlbumArtist,
+        TextTagField::Composer,
+        TextTagField::Comment,
+        TextTagField::Lyrics,
+    ];
+
+    pub fn item_key(self) -> ItemKey {
+        match self {
+            TextTagField::Title => ItemKey::TrackTitle,
+            TextTagField::Artist => ItemKey::TrackArtist,
+            TextTagField::Album => ItemKey::AlbumTitle,
+            TextTagField::Genre => ItemKey::Genre,
+            TextTagField::AlbumArtist => ItemKey::AlbumArtist,
+            TextTagField::Composer => ItemKey::Composer,
+            TextTagField::Comment => ItemKey::Comment,
+            TextTagField::Lyrics => ItemKey::Lyrics,
+        }
+    }
+
+    pub fn read(self, tag: &Tag) -> Option<String> {
+        tag.get_string(&self.item_key()).map(|s| s.to_string())
+    }
+
+    pub fn write(self, tag: &mut Tag, value: String) {
+        tag.insert_text(self.item_key(), value);
+    }
+}
+
+// 按Latin-1解码得到的文本还原为原始字节，不含高位字节时无需修复
+fn latin1_bytes(text: &str) -> Option<Vec<u8>> {
+    if text.chars().any(|c| c as u32 > 0xFF) || text.is_ascii() {
+        return None;
+    }
+    Some(text.chars().map(|c| c as u32 as u8).collect())
+}
+
+// 解码结果只能包含ASCII、中日文字和全角标点，否则视为误判
+fn is_plausible(text: &str) -> bool {
+    let mut cjk = 0;
+    for c in text.chars().filter(|c| !c.is_ascii()) {
+        match c as u32 {
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0x3040..=0x30FF => cjk += 1,
+            0x3000..=0x303F | 0xFF00..=0xFFEF => {}
+            _ if matches!(c, '·' | '—' | '…' | '“' | '”' | '‘' | '’') => {}
+            _ => return false,
+        }
+    }
+    cjk > 0
+}
+
+// 高位字节全部成对出现且位于GB2312区间，短文本无法统计检测时的兜底判断
+fn looks_like_gb2312(bytes: &[u8]) -> bool {
+    bytes
+        .split(|b| b.is_ascii())
+        .all(|run| run.len() % 2 == 0 && run.iter().all(|&b| b >= 0xA1 && b != 0xFF))
+}
+
+/// 标签格式是否可能含有误按Latin-1读取的文本
+///
+/// 只有ID3标签以Latin-1保存文本，Vorbis、APE、MP4等格式规定使用UTF-8，不做修复
+pub fn may_contain_mojibake(tag_type: TagType) -> bool {
+    matches!(tag_type, TagType::Id3v1 | TagType::Id3v2)
+}
+
+/// 用旧编码重新解码一段被误按Latin-1读取的文本
+///
+/// ID3v2的UTF-16/UTF-8帧在读取后无法区分，解码结果必须像中日文才采用
+pub fn decode_legacy(text: &str, encoding: &'static Encoding) -> Option<String> {
+    let bytes = latin1_bytes(text)?;
+    let decoded = encoding.decode_without_bom_handling_and_without_replacement(&bytes)?;
+    (decoded != text && is_plausible(&decoded)).then(|| decoded.into_owned())
+}
+
+/// 检测同一文件多个标签字段共同的实际编码，未发现乱码时返回 `None`
+///
+/// 多个字段合并检测，比单独检测短文本更可靠
+pub fn detect_legacy_encoding(samples: &[&str]) -> Option<&'static Encoding> {
+    let candidates: Vec<Vec<u8>> = samples.iter().filter_map(|s| latin1_bytes(s)).collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let joined = candidates.join(&b' ');
+
+    let decodes_all = |encoding: &'static Encoding| {
+        samples
+            .iter()
+            .filter(|s| latin1_bytes(s).is_some())
+            .all(|s| decode_legacy(s, encoding).is_some())
+    };
+
+    // UTF-8字节被当作Latin-1读取
+    if std::str::from_utf8(&joined).is_ok() && decodes_all(UTF_8) {
+        return Some(UTF_8);
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(&joined, true);
+    let guess = detector.guess(None, true);
+    if LEGACY_ENCODINGS.contains(&guess) && decodes_all(guess) {
+        return Some(guess);
+    }
+
+    if looks_like_gb2312(&joined) && decodes_all(GBK) {
+        return Some(GBK);
+    }
+
+    None
+}
+
+/// 修复提取到的元数据中的乱码，只修改内存中的值，不写回文件
+pub fn repair_track_encoding(track: &mut Track) -> Option<&'static Encoding> {
+    let single_values = [
+        &mut track.title,
+        &mut track.artist,
+        &mut track.album,
+        &mut track.genre,
+        &mut track.album_artist,
+        &mut track.composer,
+        &mut track.comment,
+        &mut track.lyrics,
+        &mut track.conductor,
+        &mut track.sort_names.title,
+        &mut track.sort_names.artist,
+        &mut track.sort_names.album,
+        &mut track.sort_names.album_artist,
+        &mut track.sort_names.composer,
+    ];
+    let mut fields: Vec<&mut String> = single_values
+        .into_iter()
+        .filter_map(|field| field.as_mut())
+        .chain(track.artists.iter_mut())
+        .chain(track.genres.iter_mut())
+        .collect();
+
+    let samples: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+    let encoding = detect_legacy_encoding(&samples)?;
+
+    for field in fields.iter_mut() {
+        if let Some(repaired) = decode_legacy(field.as_str(), encoding) {
+            **field = repaired;
+        }
+    }
+
+    Some(encoding)
+}
+
+/// 单个字段的修复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldRepair {
+    pub field: TextTagField,
+    pub original: String,
+    pub repaired: String,
+}
+
+/// 单个文件的修复结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEncodingRepair {
+    pub path: String,
+    pub encoding: String,
+    pub fields: Vec<FieldRepair>,
+}
+
+/// 编码修复报告，`dry_run` 时只预览不写入
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EncodingRepairReport {
+    pub dry_run: bool,
+    pub scanned: usize,
+    pub written: usize,
+    pub files: Vec<FileEncodingRepair>,
+    pub errors: Vec<String>,
+}
+
+// 读取文件标签并检测乱码，非预览模式下以UTF-8写回
+fn repair_file_tags(path: &Path, dry_run: bool) -> Result<Option<FileEncodingRepair>, String> {
+    let tagged_file = Probe::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?
+        .read()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let Some(tag) = tagged_file
+        .primary_tag()
+        .filter(|tag| may_contain_mojibake(tag.tag_type()))
+    else {
+        return Ok(None);
+    };
+
+    let values: Vec<(TextTagField, String)> = TextTagField::ALL
+        .iter()
+        .filter_map(|field| field.read(tag).map(|value| (*field, value)))
+        .collect();
+    let samples: Vec<&str> = values.iter().map(|(_, value)| value.as_str()).collect();
+    let Some(encoding) = detect_legacy_encoding(&samples) else {
+        return Ok(None);
+    };
+
+    let fields: Vec<FieldRepair> = values
+        .into_iter()
+        .filter_map(|(field, original)| {
+            let repaired = decode_legacy(&original, encoding)?;
+            Some(FieldRepair {
+                field,
+                original,
+                repaired,
+            })
+        })
+        .collect();
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    if !dry_run {
+        let values = fields
+            .iter()
+            .map(|repair| (repair.field, repair.repaired.clone()));
+        save_text_fields(tag.clone(), path, values)?;
+    }
+
+    Ok(Some(FileEncodingRepair {
+        path: path.to_string_lossy().to_string(),
+        encoding: encoding.name().to_string(),
+        fields,
+    }))
+}
+
+// 写入文本字段并保存标签
+fn save_text_fields(
+    mut tag: Tag,
+    path: &Path,
+    values: impl IntoIterator<Item = (TextTagField, String)>,
+) -> Result<(), String> {
+    // ID3v1无法保存Unicode文本，改为写入ID3v2标签
+    if tag.tag_type() == TagType::Id3v1 {
+        tag.re_map(TagType::Id3v2);
+    }
+    for (field, value) in values {
+        field.write(&mut tag, value);
+    }
+    tag.save_to_path(path, WriteOptions::default())
+        .map_err(|e| format!("Failed to write tags: {}", e))
+}
+
+// 已写回的文件若在音乐库中，立即重新读取
+async fn reread_library_files(
+    app: &AppHandle,
+    library: &mut MusicLibrary,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    let paths: Vec<String> = paths
+        .into_iter()
+        .filter(|path| library.tracks.iter().any(|track| &track.file_path == path))
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let mut reporter = ScanReporter::silent();
+    let diff = rescan::rescan_library_tracks(library, &paths, &mut reporter).await?;
+    save_library_to_file(app, library)?;
+    search::update_search_index(app, library, &diff.changed_ids(), &diff.removed);
+    Ok(())
+}
+
+/// 撤销（写回原来的文本）或重做编码修复，返回写入失败的文件
+///
+/// 原为ID3v1的标签已改写为ID3v2，撤销时不会删除
+pub(crate) async fn rewrite_encoding_repairs(
+    app: &AppHandle,
+    files: &[FileEncodingRepair],
+    undo: bool,
+) -> Result<Vec<String>, String> {
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    let mut errors = Vec::new();
+    let mut written = Vec::new();
+    for file in files {
+        let path = Path::new(&file.path);
+        let values = file.fields.iter().map(|repair| {
+            let value = if undo {
+                &repair.original
+            } else {
+                &repair.repaired
+            };
+            (repair.field, value.clone())
+        });
+        let result = Probe::open(path)
+            .and_then(|probe| probe.read())
+            .map_err(|e| format!("Failed to read metadata: {}", e))
+            .and_then(|tagged_file| {
+                let tag = tagged_file
+                    .primary_tag()
+                    .cloned()
+                    .ok_or_else(|| "No tag to rewrite".to_string())?;
+                save_text_fields(tag, path, values)
+            });
+        match result {
+            Ok(()) => written.push(file.path.clone()),
+            Err(e) => errors.push(format!("{}: {}", file.path, e)),
+        }
+    }
+    reread_library_files(app, &mut library, written).await?;
+    Ok(errors)
+}
+
+/// 检测并修复标签乱码，可指定文件或目录，未指定时处理整个音乐库
+///
+/// `dry_run` 为 true 时只返回预览报告，不修改任何文件
+#[tauri::command]
+pub async fn repair_tag_encoding(
+    app: AppHandle,
+    paths: Option<Vec<String>>,
+    dry_run: bool,
+) -> Result<EncodingRepairReport, String> {
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let mut report = EncodingRepairReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let files: Vec<PathBuf> = match paths {
+        Some(paths) => {
+            let mut files = Vec::new();
+            for path in paths {
+                match rescan::collect_audio_files(&path, &ScanJob::new()).await {
+                    Ok(found) => files.extend(found),
+                    Err(e) => report.errors.push(format!("{}: {}", path, e)),
+                }
+            }
+            files
+        }
+        None => library
+            .tracks
+            .iter()
+            .map(|track| PathBuf::from(&track.file_path))
+            .collect(),
+    };
+    report.scanned = files.len();
+
+    let mut results = probe_in_parallel(&ScanJob::new(), files, move |path| {
+        repair_file_tags(path, dry_run)
+    });
+    while let Some((path, result)) = results.next().await {
+        match result {
+            Ok(Some(repair)) => report.files.push(repair),
+            Ok(None) => {}
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", path.to_string_lossy(), e)),
+        }
+    }
+    report.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    if dry_run {
+        return Ok(report);
+    }
+    report.written = report.files.len();
+
+    let written = report.files.iter().map(|file| file.path.clone()).collect();
+    reread_library_files(&app, &mut library, written).await?;
+    if !report.files.is_empty() {
+        record_operation(
+            &app,
+            "Repair tag encoding",
+            vec![HistoryChange::TagEncoding {
+                files: report.files.clone(),
+            }],
+            None,
+        )?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 模拟旧编码字节被按Latin-1读取后的乱码
+    fn mojibake(text: &str, encoding: &'static Encoding) -> String {
+        let (bytes, _, _) = encoding.encode(text);
+        bytes.iter().map(|&b| b as char).collect()
+    }
+
+    #[test]
+    fn test_detects_legacy_encodings() {
+        let cases = [
+            ("张学友 - 吻别", GBK),
+            ("陳奕迅 - 十年", BIG5),
+            ("鄧麗君 - 月亮代表我的心", BIG5),
+            ("宇多田ヒカル", SHIFT_JIS),
+            ("周杰倫", UTF_8),
+        ];
+        for (text, encoding) in cases {
+            let garbled = mojibake(text, encoding);
+            let detected = detect_legacy_encoding(&[&garbled]);
+            assert_eq!(detected, Some(encoding), "{}", text);
+            assert_eq!(decode_legacy(&garbled, encoding).as_deref(), Some(text));
+        }
+    }
+
+    #[test]
+    fn test_short_fields_detected_together() {
+        let mut track = Track {
+            title: Some(mojibake("晴天", GBK)),
+            artist: Some(mojibake("周杰伦", GBK)),
+            album: Some("Ye Hui Mei".to_string()),
+            artists: vec![mojibake("周杰伦", GBK)],
+            ..Default::default()
+        };
+
+        assert_eq!(repair_track_encoding(&mut track), Some(GBK));
+        assert_eq!(track.title.as_deref(), Some("晴天"));
+        assert_eq!(track.artist.as_deref(), Some("周杰伦"));
+        assert_eq!(track.album.as_deref(), Some("Ye Hui Mei"));
+        assert_eq!(track.artists, vec!["周杰伦"]);
+    }
+
+    #[test]
+    fn test_latin_text_is_untouched() {
+        for text in [
+            "Beyoncé",
+            "Mötley Crüe",
+            "Sigur Rós",
+            "Björk",
+            "Ænima",
+            "déjà vu",
+        ] {
+            assert_eq!(detect_legacy_encoding(&[text]), None, "{}", text);
+        }
+        // 能按UTF-8解码但结果不像中日文的文本保持原样
+        assert_eq!(decode_legacy("CafÃ©", UTF_8), None);
+        assert_eq!(detect_legacy_encoding(&["周杰伦", "Jay"]), None);
+    }
+}
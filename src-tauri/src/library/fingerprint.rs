@@ -0,0 +1,315 @@
+use super::*;
+use rodio::{Decoder, Source};
+use std::f32::consts::PI;
+use std::io::BufReader;
+
+/// 指纹计算使用的采样率
+const FINGERPRINT_SAMPLE_RATE: u32 = 11025;
+/// 分析帧长度（样本数，需为2的幂）
+const FRAME_SIZE: usize = 4096;
+/// 帧移，相邻帧重叠2/3
+const FRAME_STEP: usize = FRAME_SIZE / 3;
+/// 最多分析的音频时长（秒）
+const MAX_FINGERPRINT_SECONDS: u32 = 120;
+/// 色度特征的平滑窗口（帧数）
+const SMOOTHING_FRAMES: usize = 4;
+/// 比较时允许的最大时间偏移（帧数，约12秒）
+const MAX_ALIGN_OFFSET: usize = 100;
+/// 比较时至少需要重叠的帧数
+const MIN_OVERLAP: usize = 40;
+/// 误码率低于该值时认为是同一首歌
+const MATCH_BIT_ERROR_RATE: f32 = 0.25;
+
+// 原地迭代基2 FFT
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// 线性插值重采样
+fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let length = (samples.len() as f64 / ratio) as usize;
+    (0..length)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let current = samples[index];
+            let next = samples.get(index + 1).copied().unwrap_or(current);
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+// 每帧12个半音的能量，已归一化
+fn chroma_frames(samples: &[f32]) -> Vec<[f32; 12]> {
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+
+    // 预先计算每个频率分量对应的半音，只使用28Hz到3520Hz
+    let bin_hz = FINGERPRINT_SAMPLE_RATE as f32 / FRAME_SIZE as f32;
+    let bins: Vec<(usize, usize)> = (1..FRAME_SIZE / 2)
+        .filter_map(|bin| {
+            let freq = bin as f32 * bin_hz;
+            if !(28.0..=3520.0).contains(&freq) {
+                return None;
+            }
+            let note = 12.0 * (freq / 27.5).log2();
+            Some((bin, (note.round() as usize) % 12))
+        })
+        .collect();
+
+    let mut frames = Vec::new();
+    let mut re = vec![0.0; FRAME_SIZE];
+    let mut im = vec![0.0; FRAME_SIZE];
+    let mut start = 0;
+    while start + FRAME_SIZE <= samples.len() {
+        for i in 0..FRAME_SIZE {
+            re[i] = samples[start + i] * window[i];
+            im[i] = 0.0;
+        }
+        fft(&mut re, &mut im);
+
+        let mut chroma = [0.0f32; 12];
+        for &(bin, note) in &bins {
+            chroma[note] += re[bin] * re[bin] + im[bin] * im[bin];
+        }
+        let norm = chroma.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 1e-6 {
+            chroma.iter_mut().for_each(|v| *v /= norm);
+        }
+        frames.push(chroma);
+        start += FRAME_STEP;
+    }
+    frames
+}
+
+// 相邻若干帧取平均，减少编码差异带来的抖动
+fn smooth(frames: &[[f32; 12]]) -> Vec<[f32; 12]> {
+    if frames.len() < SMOOTHING_FRAMES {
+        return Vec::new();
+    }
+    frames
+        .windows(SMOOTHING_FRAMES)
+        .map(|window| {
+            let mut sum = [0.0f32; 12];
+            for frame in window {
+                for (total, value) in sum.iter_mut().zip(frame) {
+                    *total += value;
+                }
+            }
+            sum.map(|v| v / SMOOTHING_FRAMES as f32)
+        })
+        .collect()
+}
+
+// 由相邻两帧的色度特征生成32位子指纹：
+// 0-11位比较相邻半音，12-23位比较相隔四个半音，24-31位比较前后帧的能量变化
+fn sub_fingerprint(current: &[f32; 12], next: &[f32; 12]) -> u32 {
+    let mut bits = 0u32;
+    for band in 0..12 {
+        if current[band] > current[(band + 1) % 12] {
+            bits |= 1 << band;
+        }
+        if current[band] > current[(band + 4) % 12] {
+            bits |= 1 << (12 + band);
+        }
+    }
+    for band in 0..8 {
+        let before = current[band] + current[band + 4];
+        let after = next[band] + next[band + 4];
+        if after > before {
+            bits |= 1 << (24 + band);
+        }
+    }
+    bits
+}
+
+/// 由单声道采样计算音频指纹，不同编码、码率和音量的同一录音得到相近的结果
+pub fn compute_fingerprint(samples: &[f32], sample_rate: u32) -> Vec<u32> {
+    let samples = resample(samples, sample_rate, FINGERPRINT_SAMPLE_RATE);
+    let frames = smooth(&chroma_frames(&samples));
+    frames
+        .windows(2)
+        .map(|pair| sub_fingerprint(&pair[0], &pair[1]))
+        .collect()
+}
+
+/// 两个指纹在最佳对齐位置的误码率，重叠不足时返回 `None`
+pub fn fingerprint_bit_error_rate(a: &[u32], b: &[u32]) -> Option<f32> {
+    let mut best: Option<f32> = None;
+    for offset in -(MAX_ALIGN_OFFSET as isize)..=MAX_ALIGN_OFFSET as isize {
+        let (a_start, b_start) = if offset >= 0 {
+            (offset as usize, 0)
+        } else {
+            (0, (-offset) as usize)
+        };
+        if a_start >= a.len() || b_start >= b.len() {
+            continue;
+        }
+        let overlap = (a.len() - a_start).min(b.len() - b_start);
+        if overlap < MIN_OVERLAP {
+            continue;
+        }
+
+        let errors: u32 = a[a_start..a_start + overlap]
+            .iter()
+            .zip(&b[b_start..b_start + overlap])
+            .map(|(x, y)| (x ^ y).count_ones())
+            .sum();
+        let rate = errors as f32 / (overlap as f32 * 32.0);
+        if best.is_none_or(|b| rate < b) {
+            best = Some(rate);
+        }
+    }
+    best
+}
+
+/// 两个指纹的相似度（0到1），达到匹配阈值时返回
+pub fn fingerprint_similarity(a: &[u32], b: &[u32]) -> Option<f32> {
+    fingerprint_bit_error_rate(a, b)
+        .filter(|rate| *rate < MATCH_BIT_ERROR_RATE)
+        .map(|rate| 1.0 - rate)
+}
+
+/// 解码音频文件开头部分并计算指纹
+pub fn fingerprint_file(path: &Path) -> Result<Vec<u32>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let decoder =
+        Decoder::new(BufReader::new(file)).map_err(|e| format!("Failed to decode audio: {}", e))?;
+    let channels = decoder.channels().max(1) as usize;
+    let sample_rate = decoder.sample_rate();
+    let limit = (sample_rate * MAX_FINGERPRINT_SECONDS) as usize * channels;
+
+    // 混合为单声道
+    let mut mono = Vec::with_capacity(limit / channels);
+    let mut frame_sum = 0.0f32;
+    for (index, sample) in decoder.take(limit).enumerate() {
+        frame_sum += sample as f32 / i16::MAX as f32;
+        if (index + 1) % channels == 0 {
+            mono.push(frame_sum / channels as f32);
+            frame_sum = 0.0;
+        }
+    }
+
+    let fingerprint = compute_fingerprint(&mono, sample_rate);
+    if fingerprint.len() < MIN_OVERLAP {
+        return Err("Audio is too short to fingerprint".to_string());
+    }
+    Ok(fingerprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 合成测试音频：每0.5秒切换一个和弦
+    fn synthesize(chords: &[[f32; 3]], sample_rate: u32, amplitude: f32, seconds: u32) -> Vec<f32> {
+        let chord_samples = sample_rate as usize / 2;
+        (0..(sample_rate * seconds) as usize)
+            .map(|i| {
+                let chord = &chords[(i / chord_samples) % chords.len()];
+                let t = i as f32 / sample_rate as f32;
+                let noise = ((i * 7919) % 101) as f32 / 101.0 - 0.5;
+                chord
+                    .iter()
+                    .map(|freq| (2.0 * PI * freq * t).sin())
+                    .sum::<f32>()
+                    * amplitude
+                    / 3.0
+                    + noise * 0.01
+            })
+            .collect()
+    }
+
+    const SONG: [[f32; 3]; 7] = [
+        [261.6, 329.6, 392.0],
+        [220.0, 261.6, 329.6],
+        [349.2, 440.0, 523.3],
+        [392.0, 493.9, 587.3],
+        [293.7, 349.2, 440.0],
+        [329.6, 415.3, 493.9],
+        [246.9, 311.1, 370.0],
+    ];
+    const OTHER_SONG: [[f32; 3]; 5] = [
+        [277.2, 349.2, 415.3],
+        [311.1, 392.0, 466.2],
+        [185.0, 233.1, 277.2],
+        [370.0, 466.2, 554.4],
+        [207.7, 261.6, 311.1],
+    ];
+
+    #[test]
+    fn test_fft_finds_frequency() {
+        let n = 1024;
+        let mut re: Vec<f32> = (0..n)
+            .map(|i| (2.0 * PI * 64.0 * i as f32 / n as f32).cos())
+            .collect();
+        let mut im = vec![0.0; n];
+        fft(&mut re, &mut im);
+        let peak = (0..n / 2)
+            .max_by(|a, b| {
+                let ma = re[*a].hypot(im[*a]);
+                let mb = re[*b].hypot(im[*b]);
+                ma.total_cmp(&mb)
+            })
+            .unwrap();
+        assert_eq!(peak, 64);
+    }
+
+    #[test]
+    fn test_same_recording_matches_across_encodings() {
+        let original = compute_fingerprint(&synthesize(&SONG, 44100, 0.8, 30), 44100);
+        let reencoded = compute_fingerprint(&synthesize(&SONG, 22050, 0.3, 30), 22050);
+        assert!(original.len() > MIN_OVERLAP);
+        assert!(fingerprint_similarity(&original, &reencoded).is_some());
+
+        let other = compute_fingerprint(&synthesize(&OTHER_SONG, 44100, 0.8, 30), 44100);
+        assert!(fingerprint_similarity(&original, &other).is_none());
+    }
+
+    #[test]
+    fn test_offset_is_aligned() {
+        let samples = synthesize(&SONG, 22050, 0.8, 30);
+        let full = compute_fingerprint(&samples, 22050);
+        // 去掉开头3秒，模拟片头长度不同的版本
+        let trimmed = compute_fingerprint(&samples[22050 * 3..], 22050);
+        let rate = fingerprint_bit_error_rate(&full, &trimmed).unwrap();
+        assert!(rate < MATCH_BIT_ERROR_RATE, "bit error rate {}", rate);
+    }
+}
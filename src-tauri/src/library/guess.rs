@@ -0,0 +1,540 @@
+use super::*;
+
+/// 文件名模式中可用的字段，`%ignore%` 匹配任意内容但不使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GuessField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Year,
+    Track,
+    Disc,
+    Ignore,
+}
+
+impl GuessField {
+    fn from_name(name: &str) -> Option<Self> {
+        let field = match name.to_lowercase().as_str() {
+            "title" => GuessField::Title,
+            "artist" => GuessField::Artist,
+            "album" => GuessField::Album,
+            "albumartist" => GuessField::AlbumArtist,
+            "genre" => GuessField::Genre,
+            "year" => GuessField::Year,
+            "track" => GuessField::Track,
+            "disc" => GuessField::Disc,
+            "ignore" => GuessField::Ignore,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            GuessField::Year | GuessField::Track | GuessField::Disc
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PatternToken {
+    Literal(String),
+    Field(GuessField),
+}
+
+/// 解析后的文件名模式，如 `%artist%/%album%/%track% - %title%`
+///
+/// `/` 分隔目录层级，模式从路径末尾开始匹配，文件扩展名不参与匹配
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuessPattern {
+    tokens: Vec<PatternToken>,
+    depth: usize,
+}
+
+impl GuessPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim().replace('\\', "/");
+        let pattern = pattern.trim_matches('/');
+        let mut tokens = Vec::new();
+        let mut rest = pattern;
+
+        while !rest.is_empty() {
+            let Some(start) = rest.find('%') else {
+                tokens.push(PatternToken::Literal(rest.to_string()));
+                break;
+            };
+            if start > 0 {
+                tokens.push(PatternToken::Literal(rest[..start].to_string()));
+            }
+            let after = &rest[start + 1..];
+            let end = after
+                .find('%')
+                .ok_or_else(|| format!("Unclosed placeholder in pattern: {}", pattern))?;
+            let name = &after[..end];
+            let field = GuessField::from_name(name)
+                .ok_or_else(|| format!("Unknown placeholder: %{}%", name))?;
+            if matches!(tokens.last(), Some(PatternToken::Field(_))) {
+                return Err(format!(
+                    "Placeholders must be separated by text: %{}%",
+                    name
+                ));
+            }
+            tokens.push(PatternToken::Field(field));
+            rest = &after[end + 1..];
+        }
+
+        if !tokens.iter().any(|t| matches!(t, PatternToken::Field(_))) {
+            return Err("Pattern contains no placeholders".to_string());
+        }
+
+        Ok(Self {
+            depth: pattern.matches('/').count() + 1,
+            tokens,
+        })
+    }
+
+    /// 匹配路径末尾的若干层目录和文件名，匹配失败返回 `None`
+    pub fn guess(&self, path: &Path) -> Option<TagGuess> {
+        let stem = path.file_stem()?.to_string_lossy().to_string();
+        let mut parts = vec![stem];
+        let mut dir = path.parent();
+        while parts.len() < self.depth {
+            let current = dir?;
+            parts.push(current.file_name()?.to_string_lossy().to_string());
+            dir = current.parent();
+        }
+        parts.reverse();
+
+        let text: Vec<char> = parts.join("/").chars().collect();
+        let mut captures = Vec::new();
+        if !match_tokens(&self.tokens, &text, &mut captures) {
+            return None;
+        }
+
+        let mut guess = TagGuess::default();
+        for (field, value) in captures {
+            guess.set(field, value);
+        }
+        Some(guess)
+    }
+}
+
+// 回溯匹配，字段取最短可行内容，不跨越目录层级
+fn match_tokens(
+    tokens: &[PatternToken],
+    text: &[char],
+    captures: &mut Vec<(GuessField, String)>,
+) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+
+    match token {
+        PatternToken::Literal(literal) => {
+            let literal: Vec<char> = literal.chars().collect();
+            text.len() >= literal.len()
+                && text[..literal.len()]
+                    .iter()
+                    .zip(&literal)
+                    .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+                && match_tokens(rest, &text[literal.len()..], captures)
+        }
+        PatternToken::Field(field) => {
+            for end in 1..=text.len() {
+                let c = text[end - 1];
+                if c == '/' || (field.is_numeric() && !c.is_ascii_digit()) {
+                    break;
+                }
+                captures.push((*field, text[..end].iter().collect()));
+                if match_tokens(rest, &text[end..], captures) {
+                    return true;
+                }
+                captures.pop();
+            }
+            false
+        }
+    }
+}
+
+/// 从路径推测出的标签
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TagGuess {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+}
+
+impl TagGuess {
+    fn set(&mut self, field: GuessField, value: String) {
+        let text = Some(value.trim().to_string()).filter(|s| !s.is_empty());
+        let number = value.trim().parse::<u32>().ok().filter(|n| *n > 0);
+        match field {
+            GuessField::Title => self.title = text,
+            GuessField::Artist => self.artist = text,
+            GuessField::Album => self.album = text,
+            GuessField::AlbumArtist => self.album_artist = text,
+            GuessField::Genre => self.genre = text,
+            GuessField::Year => self.year = number,
+            GuessField::Track => self.track_number = number,
+            GuessField::Disc => self.disc_number = number,
+            GuessField::Ignore => {}
+        }
+    }
+}
+
+/// 单个字段的变化
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuessChange {
+    pub field: GuessField,
+    pub current: Option<String>,
+    pub proposed: String,
+}
+
+/// 单个曲目的推测结果预览
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagGuessPreview {
+    pub track_id: String,
+    pub path: String,
+    pub changes: Vec<GuessChange>,
+}
+
+/// 应用推测结果的报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagGuessReport {
+    pub updated: Vec<LibraryTrack>,
+    pub unmatched: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// 缺失或使用默认占位值（"Unknown Artist"、以文件名作标题等）的字段视为空
+pub(crate) fn is_placeholder(field: GuessField, value: &str, track: &LibraryTrack) -> bool {
+    let path = Path::new(&track.file_path);
+    let file_name = path.file_name().map(|s| s.to_string_lossy());
+    let file_stem = path.file_stem().map(|s| s.to_string_lossy());
+    value.trim().is_empty()
+        || match field {
+            GuessField::Title => {
+                file_name.as_deref() == Some(value) || file_stem.as_deref() == Some(value)
+            }
+            GuessField::Artist => value == "Unknown Artist",
+            GuessField::Album => value == "Unknown Album",
+            GuessField::Genre => value == "Unknown",
+            _ => false,
+        }
+}
+
+/// 对比推测结果与曲目现有标签，`overwrite` 为 false 时只填补空字段
+pub fn guess_changes(track: &LibraryTrack, guess: &TagGuess, overwrite: bool) -> Vec<GuessChange> {
+    let fields = [
+        (
+            GuessField::Title,
+            Some(track.title.clone()),
+            guess.title.clone(),
+        ),
+        (
+            GuessField::Artist,
+            Some(track.artist.clone()),
+            guess.artist.clone(),
+        ),
+        (
+            GuessField::Album,
+            Some(track.album.clone()),
+            guess.album.clone(),
+        ),
+        (
+            GuessField::AlbumArtist,
+            track.album_artist.clone(),
+            guess.album_artist.clone(),
+        ),
+        (GuessField::Genre, track.genre.clone(), guess.genre.clone()),
+        (
+            GuessField::Year,
+            track.year.map(|n| n.to_string()),
+            guess.year.map(|n| n.to_string()),
+        ),
+        (
+            GuessField::Track,
+            track.track_number.map(|n| n.to_string()),
+            guess.track_number.map(|n| n.to_string()),
+        ),
+        (
+            GuessField::Disc,
+            track.disc_number.map(|n| n.to_string()),
+            guess.disc_number.map(|n| n.to_string()),
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter_map(|(field, current, proposed)| {
+            let proposed = proposed?;
+            let current = current.filter(|value| !is_placeholder(field, value, track));
+            match &current {
+                Some(value) if value == &proposed => None,
+                Some(_) if !overwrite => None,
+                _ => Some(GuessChange {
+                    field,
+                    current,
+                    proposed,
+                }),
+            }
+        })
+        .collect()
+}
+
+// 只修改音乐库条目，不写入文件
+fn apply_to_library_track(track: &mut LibraryTrack, changes: &[GuessChange]) {
+    for change in changes {
+        let value = change.proposed.clone();
+        match change.field {
+            GuessField::Title => track.title = value,
+            GuessField::Artist => {
+                track.artist = value;
+                track.artists.clear();
+            }
+            GuessField::Album => track.album = value,
+            GuessField::AlbumArtist => track.album_artist = Some(value),
+            GuessField::Genre => {
+                track.genre = Some(value);
+                track.genres.clear();
+            }
+            GuessField::Year => track.year = value.parse().ok(),
+            GuessField::Track => track.track_number = value.parse().ok(),
+            GuessField::Disc => track.disc_number = value.parse().ok(),
+            GuessField::Ignore => {}
+        }
+    }
+}
+
+// 转换为标签编辑，未变化的字段保持不变
+fn changes_to_edit(changes: &[GuessChange]) -> TagEdit {
+    let mut edit = TagEdit::default();
+    for change in changes {
+        let text = FieldEdit::Set(change.proposed.clone());
+        let number = change
+            .proposed
+            .parse()
+            .map(FieldEdit::Set)
+            .unwrap_or_default();
+        match change.field {
+            GuessField::Title => edit.title = text,
+            GuessField::Artist => edit.artist = text,
+            GuessField::Album => edit.album = text,
+            GuessField::AlbumArtist => edit.album_artist = text,
+            GuessField::Genre => edit.genre = text,
+            GuessField::Year => edit.year = number,
+            GuessField::Track => edit.track_number = number,
+            GuessField::Disc => edit.disc_number = number,
+            GuessField::Ignore => {}
+        }
+    }
+    edit
+}
+
+// 曲目在音乐库中的位置及其推测的改动
+type TrackGuesses = Vec<(usize, Vec<GuessChange>)>;
+
+// 对指定曲目（未指定时为整个音乐库）计算推测结果
+fn collect_guesses(
+    library: &MusicLibrary,
+    track_ids: Option<&[String]>,
+    pattern: &GuessPattern,
+    overwrite: bool,
+) -> Result<(TrackGuesses, Vec<String>), String> {
+    let indices = match track_ids {
+        Some(ids) => editor::track_indices(library, ids)?,
+        None => (0..library.tracks.len()).collect(),
+    };
+
+    let mut guesses = Vec::new();
+    let mut unmatched = Vec::new();
+    for index in indices {
+        let track = &library.tracks[index];
+        match pattern.guess(Path::new(&track.file_path)) {
+            Some(guess) => {
+                let changes = guess_changes(track, &guess, overwrite);
+                if !changes.is_empty() {
+                    guesses.push((index, changes));
+                }
+            }
+            None => unmatched.push(track.file_path.clone()),
+        }
+    }
+    Ok((guesses, unmatched))
+}
+
+/// 预览按模式从路径推测出的标签变化，不修改任何内容
+#[tauri::command]
+pub async fn preview_tag_guesses(
+    app: AppHandle,
+    track_ids: Option<Vec<String>>,
+    pattern: String,
+    overwrite: bool,
+) -> Result<Vec<TagGuessPreview>, String> {
+    let pattern = GuessPattern::parse(&pattern)?;
+    let library = load_library_from_file(&app)?;
+    let (guesses, _) = collect_guesses(&library, track_ids.as_deref(), &pattern, overwrite)?;
+
+    Ok(guesses
+        .into_iter()
+        .map(|(index, changes)| TagGuessPreview {
+            track_id: library.tracks[index].id.clone(),
+            path: library.tracks[index].file_path.clone(),
+            changes,
+        })
+        .collect())
+}
+
+/// 应用推测出的标签，`write_to_files` 为 false 时只更新音乐库
+///
+/// 只更新音乐库时，文件修改后重新扫描会以文件中的标签为准
+#[tauri::command]
+pub async fn apply_tag_guesses(
+    app: AppHandle,
+    track_ids: Option<Vec<String>>,
+    pattern: String,
+    overwrite: bool,
+    write_to_files: bool,
+) -> Result<TagGuessReport, String> {
+    let pattern = GuessPattern::parse(&pattern)?;
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let (guesses, unmatched) =
+        collect_guesses(&library, track_ids.as_deref(), &pattern, overwrite)?;
+
+    let mut report = TagGuessReport {
+        unmatched,
+        ..Default::default()
+    };
+    if guesses.is_empty() {
+        return Ok(report);
+    }
+
+    // 写入文件前的条目和编辑，用于生成撤销记录
+    let mut previous: HashMap<String, (LibraryTrack, TagEdit)> = HashMap::new();
+    if write_to_files {
+        let edits: Vec<(usize, TagEdit)> = guesses
+            .iter()
+            .map(|(index, changes)| (*index, changes_to_edit(changes)))
+            .collect();
+        for (index, edit) in &edits {
+            let track = &library.tracks[*index];
+            previous.insert(track.id.clone(), (track.clone(), edit.clone()));
+        }
+        let written = editor::write_tag_edits(&mut library, edits, None).await?;
+        report.updated = written.updated;
+        report.errors = written.errors;
+    } else {
+        for (index, changes) in &guesses {
+            let track = &mut library.tracks[*index];
+            apply_to_library_track(track, changes);
+            report.updated.push(track.clone());
+        }
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+    }
+
+    if !report.updated.is_empty() {
+        save_library_to_file(&app, &library)?;
+        let changed: Vec<String> = report.updated.iter().map(|t| t.id.clone()).collect();
+        search::update_search_index(&app, &library, &changed, &[]);
+    }
+    let edits = report
+        .updated
+        .iter()
+        .filter_map(|track| previous.get(&track.id))
+        .map(|(track, edit)| (track, edit))
+        .collect();
+    editor::record_tag_edits(&app, "Guess tags", edits)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn library_track(path: &str) -> LibraryTrack {
+        let file_name = Path::new(path).file_name().unwrap().to_string_lossy();
+        test_support::track("1")
+            .title(&file_name)
+            .artist("Unknown Artist")
+            .album("Unknown Album")
+            .path(path)
+            .build()
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert!(GuessPattern::parse("%artist%/%album%/%track% - %title%").is_ok());
+        assert!(GuessPattern::parse("%artist%%title%").is_err());
+        assert!(GuessPattern::parse("%singer% - %title%").is_err());
+        assert!(GuessPattern::parse("%artist - %title%").is_err());
+        assert!(GuessPattern::parse("no fields").is_err());
+    }
+
+    #[test]
+    fn test_guess_from_folders() {
+        let pattern = GuessPattern::parse("%artist%/%album%/%track% - %title%").unwrap();
+        let guess = pattern
+            .guess(Path::new("/music/周杰伦/叶惠美/03 - 晴天.mp3"))
+            .unwrap();
+
+        assert_eq!(guess.artist.as_deref(), Some("周杰伦"));
+        assert_eq!(guess.album.as_deref(), Some("叶惠美"));
+        assert_eq!(guess.track_number, Some(3));
+        assert_eq!(guess.title.as_deref(), Some("晴天"));
+
+        // 目录层级不足或数字字段不是数字时不匹配
+        assert!(pattern.guess(Path::new("song.mp3")).is_none());
+        assert!(pattern
+            .guess(Path::new("/music/A/B/xx - Song.mp3"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_guess_first_separator_wins() {
+        let pattern = GuessPattern::parse("%artist% - %title%").unwrap();
+        let guess = pattern
+            .guess(Path::new(
+                "/downloads/Queen - Bohemian Rhapsody - Remastered.flac",
+            ))
+            .unwrap();
+        assert_eq!(guess.artist.as_deref(), Some("Queen"));
+        assert_eq!(
+            guess.title.as_deref(),
+            Some("Bohemian Rhapsody - Remastered")
+        );
+    }
+
+    #[test]
+    fn test_guess_changes_respect_existing_tags() {
+        let pattern = GuessPattern::parse("%artist% - %title%").unwrap();
+        let mut track = library_track("/downloads/Queen - Bohemian Rhapsody.mp3");
+        let guess = pattern.guess(Path::new(&track.file_path)).unwrap();
+
+        let changes = guess_changes(&track, &guess, false);
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().all(|c| c.current.is_none()));
+
+        track.artist = "Queen (Remastered)".to_string();
+        let changes = guess_changes(&track, &guess, false);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, GuessField::Title);
+
+        let changes = guess_changes(&track, &guess, true);
+        assert_eq!(changes.len(), 2);
+
+        apply_to_library_track(&mut track, &changes);
+        assert_eq!(track.artist, "Queen");
+        assert_eq!(track.title, "Bohemian Rhapsody");
+    }
+}
@@ -1,13 +1,72 @@
+use crate::scan::{probe_in_parallel, ScanJob};
 use futures::future::{BoxFuture, FutureExt};
 use lofty::prelude::*;
 use lofty::probe::Probe;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 use tokio_stream::{wrappers::ReadDirStream, StreamExt};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+pub mod artwork;
+pub mod browse;
+pub mod duplicates;
+pub mod editor;
+pub mod encoding;
+pub mod fingerprint;
+pub mod guess;
+pub mod normalize;
+pub mod organize;
+pub mod pinyin;
+pub mod properties;
+pub mod query;
+pub mod rating;
+pub mod relink;
+pub mod rescan;
+pub mod search;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod watcher;
+
+pub use editor::{FieldEdit, TagEdit};
+pub use encoding::FileEncodingRepair;
+pub use properties::AudioProperties;
+pub use query::{QueryCacheState, QueryPage, TrackSortKey};
+pub use rescan::FileFingerprint;
+pub use search::{SearchIndex, SearchIndexState};
+pub use watcher::LibraryWatcherState;
+
+/// 当前音乐库文件格式版本
+///
+/// 1：多值艺术家/流派、碟号音轨号等扩展标签；2：音频技术参数
+pub const LIBRARY_VERSION: u32 = 2;
+
+/// MusicBrainz 标识
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MusicBrainzIds {
+    pub recording_id: Option<String>,
+    pub track_id: Option<String>,
+    pub release_id: Option<String>,
+    pub release_group_id: Option<String>,
+    pub artist_id: Option<String>,
+    pub release_artist_id: Option<String>,
+}
+
+/// 排序用名称，如 "Beatles, The"
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortNames {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub composer: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Track {
     pub path: String,
     pub title: Option<String>,
@@ -16,19 +75,120 @@ pub struct Track {
     pub genre: Option<String>,
     pub year: Option<u32>,
     pub duration: u64,
+    #[serde(default)]
+    pub album_artist: Option<String>,
+    #[serde(default)]
+    pub composer: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub lyrics: Option<String>,
+    #[serde(default)]
+    pub track_number: Option<u32>,
+    #[serde(default)]
+    pub disc_number: Option<u32>,
+    #[serde(default)]
+    pub track_total: Option<u32>,
+    #[serde(default)]
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub conductor: Option<String>,
+    #[serde(default)]
+    pub bpm: Option<u32>,
+    #[serde(default)]
+    pub compilation: bool,
+    /// 多值艺术家，单值时与 `artist` 相同
+    #[serde(default)]
+    pub artists: Vec<String>,
+    /// 多值流派
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub musicbrainz: MusicBrainzIds,
+    #[serde(default)]
+    pub sort_names: SortNames,
+    #[serde(default)]
+    pub audio: AudioProperties,
+    /// 标签中的评分（半星为单位，0-10）
+    #[serde(default)]
+    pub rating: Option<u8>,
+    /// 标签中的播放次数
+    #[serde(default)]
+    pub play_count: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LibraryTrack {
+    // 稳定ID，重新扫描时保持不变，供播放列表和历史记录引用
+    #[serde(default)]
+    pub id: String,
     pub title: String,
     pub artist: String,
     pub album: String,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub album_artist: Option<String>,
+    #[serde(default)]
+    pub composer: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub lyrics: Option<String>,
+    #[serde(default)]
+    pub track_number: Option<u32>,
+    #[serde(default)]
+    pub disc_number: Option<u32>,
+    #[serde(default)]
+    pub track_total: Option<u32>,
+    #[serde(default)]
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub conductor: Option<String>,
+    #[serde(default)]
+    pub bpm: Option<u32>,
+    #[serde(default)]
+    pub compilation: bool,
+    #[serde(default)]
+    pub artists: Vec<String>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub musicbrainz: MusicBrainzIds,
+    #[serde(default)]
+    pub sort_names: SortNames,
+    #[serde(default)]
+    pub audio: AudioProperties,
+    // 评分（半星为单位，0-10），未评分为 None
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub loved: bool,
+    #[serde(default)]
+    pub play_count: u32,
+    #[serde(default)]
+    pub skip_count: u32,
+    #[serde(default)]
+    pub last_played: Option<String>,
+    // 加入音乐库的时间（RFC 3339）
+    #[serde(default)]
+    pub added_at: Option<String>,
     pub duration: f64,
     pub file_path: String,
+    // 文件指纹（大小 + 修改时间毫秒），用于增量扫描
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub modified_at: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MusicLibrary {
+    // 文件格式版本，旧文件没有此字段，按0处理
+    #[serde(default)]
+    pub version: u32,
     pub tracks: Vec<LibraryTrack>,
     pub last_scanned_paths: Vec<String>,
     pub last_updated: String,
@@ -37,16 +197,179 @@ pub struct MusicLibrary {
 impl MusicLibrary {
     pub fn new() -> Self {
         Self {
+            version: LIBRARY_VERSION,
             tracks: Vec::new(),
             last_scanned_paths: Vec::new(),
             last_updated: chrono::Utc::now().to_rfc3339(),
         }
     }
+
+    // 升级旧版本音乐库：由单值字段推导多值字段，并清空文件指纹，
+    // 使下次扫描重新读取标签和音频参数补全新增字段。返回是否做了升级
+    pub fn migrate(&mut self) -> bool {
+        if self.version >= LIBRARY_VERSION {
+            return false;
+        }
+
+        for track in self.tracks.iter_mut() {
+            if track.artists.is_empty() {
+                track.artists = split_multi_value(&track.artist);
+            }
+            if track.genres.is_empty() {
+                if let Some(genre) = &track.genre {
+                    track.genres = split_multi_value(genre);
+                }
+            }
+            track.file_size = 0;
+            track.modified_at = 0;
+        }
+        self.version = LIBRARY_VERSION;
+
+        true
+    }
+
+    // 为缺少ID的曲目分配ID，优先沿用旧库中相同路径的ID，返回是否分配了新ID
+    pub fn ensure_track_ids(&mut self, previous: Option<&MusicLibrary>) -> bool {
+        let mut assigned = false;
+        for track in self.tracks.iter_mut().filter(|t| t.id.is_empty()) {
+            track.id = previous
+                .and_then(|lib| lib.tracks.iter().find(|t| t.file_path == track.file_path))
+                .map(|t| t.id.clone())
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(new_track_id);
+            assigned = true;
+        }
+        assigned
+    }
+
+    // 沿用旧库中相同ID曲目的用户数据
+    pub fn keep_user_data(&mut self, previous: &MusicLibrary) {
+        let previous: HashMap<&str, &LibraryTrack> = previous
+            .tracks
+            .iter()
+            .map(|track| (track.id.as_str(), track))
+            .collect();
+        for track in self.tracks.iter_mut() {
+            if let Some(old) = previous.get(track.id.as_str()) {
+                track.keep_user_data(old);
+            }
+        }
+    }
+}
+
+impl LibraryTrack {
+    // 由提取到的元数据构建音乐库条目，缺失字段使用与前端一致的默认值
+    pub fn from_track(track: Track, id: String, fingerprint: &FileFingerprint) -> Self {
+        let title = track.title.unwrap_or_else(|| {
+            Path::new(&track.path)
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Unknown")
+                .to_string()
+        });
+
+        // 有毫秒级时长时使用精确值
+        let duration = if track.audio.duration_ms > 0 {
+            track.audio.duration_ms as f64 / 1000.0
+        } else {
+            track.duration as f64
+        };
+
+        Self {
+            id,
+            title,
+            artist: track.artist.unwrap_or_else(|| "Unknown Artist".to_string()),
+            album: track.album.unwrap_or_else(|| "Unknown Album".to_string()),
+            genre: track.genre,
+            year: track.year,
+            album_artist: track.album_artist,
+            composer: track.composer,
+            comment: track.comment,
+            lyrics: track.lyrics,
+            track_number: track.track_number,
+            disc_number: track.disc_number,
+            track_total: track.track_total,
+            disc_total: track.disc_total,
+            conductor: track.conductor,
+            bpm: track.bpm,
+            compilation: track.compilation,
+            artists: track.artists,
+            genres: track.genres,
+            musicbrainz: track.musicbrainz,
+            sort_names: track.sort_names,
+            audio: track.audio,
+            rating: track.rating,
+            play_count: track.play_count.unwrap_or(0),
+            // 按新条目记录加入时间，更新已有条目时由 keep_user_data 沿用旧值
+            added_at: Some(chrono::Utc::now().to_rfc3339()),
+            duration,
+            file_path: track.path,
+            file_size: fingerprint.size,
+            modified_at: fingerprint.modified_at,
+            ..Default::default()
+        }
+    }
+
+    // 沿用旧条目的评分、喜爱标记、播放统计和加入时间，这些数据不一定保存在文件标签中
+    //
+    // 旧条目没有加入时间时保持为空，按文件修改时间计算，不当作刚加入
+    pub fn keep_user_data(&mut self, previous: &LibraryTrack) {
+        self.rating = self.rating.or(previous.rating);
+        self.loved |= previous.loved;
+        self.play_count = self.play_count.max(previous.play_count);
+        self.skip_count = self.skip_count.max(previous.skip_count);
+        self.last_played = self.last_played.take().max(previous.last_played.clone());
+        self.added_at = previous.added_at.clone();
+    }
+
+    // 加入音乐库的时间，旧条目没有记录时使用文件修改时间
+    pub fn added_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.added_at
+            .as_deref()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|time| time.with_timezone(&chrono::Utc))
+            .or_else(|| {
+                Some(self.modified_at)
+                    .filter(|ms| *ms > 0)
+                    .and_then(chrono::DateTime::from_timestamp_millis)
+            })
+    }
+
+    // 所有艺术家，没有多值标签时为单个 `artist`
+    pub fn artist_names(&self) -> Vec<&str> {
+        if self.artists.is_empty() {
+            vec![self.artist.as_str()]
+        } else {
+            self.artists.iter().map(|s| s.as_str()).collect()
+        }
+    }
+
+    // 所有流派，没有多值标签时为单个 `genre`
+    pub fn genre_names(&self) -> Vec<&str> {
+        if self.genres.is_empty() {
+            self.genre.as_deref().into_iter().collect()
+        } else {
+            self.genres.iter().map(|s| s.as_str()).collect()
+        }
+    }
+
+    // 小写文件扩展名，作为音频格式使用
+    pub fn format(&self) -> String {
+        Path::new(&self.file_path)
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_lowercase())
+            .unwrap_or_default()
+    }
+}
+
+pub fn new_track_id() -> String {
+    uuid::Uuid::new_v4().to_string()
 }
 
 #[tauri::command]
 pub async fn scan_music_files(path: String) -> Result<Vec<PathBuf>, String> {
-    scan_directory_recursive(path).await
+    scan_directory_recursive(path, ScanJob::new()).await
 }
 
 #[tauri::command]
@@ -54,31 +377,35 @@ pub async fn get_metadata_for_files(files: Vec<String>) -> Result<Vec<Track>, St
     let mut tracks = Vec::new();
     let mut errors = Vec::new();
 
-    for path_str in files {
-        match extract_metadata(&path_str).await {
+    // 并行读取，结果按输入顺序返回
+    let order: HashMap<PathBuf, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (PathBuf::from(path), index))
+        .collect();
+    let paths = files.iter().map(PathBuf::from).collect();
+    let mut results = probe_in_parallel(&ScanJob::new(), paths, |path| {
+        extract_metadata(&path.to_string_lossy())
+    });
+
+    while let Some((path, result)) = results.next().await {
+        let path_str = path.to_string_lossy().to_string();
+        match result {
             Ok(track) => tracks.push(track),
             Err(e) => {
                 eprintln!("Error processing file {}: {}", path_str, e);
                 errors.push(format!("{}: {}", path_str, e));
                 // 创建一个基本的Track条目，即使元数据提取失败
-                tracks.push(Track {
-                    path: path_str.clone(),
-                    title: Some(
-                        Path::new(&path_str)
-                            .file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("Unknown")
-                            .to_string(),
-                    ),
-                    artist: Some("Unknown Artist".to_string()),
-                    album: Some("Unknown Album".to_string()),
-                    genre: Some("Unknown".to_string()),
-                    year: None,
-                    duration: 0,
-                });
+                tracks.push(fallback_track(&path_str));
             }
         }
     }
+    tracks.sort_by_key(|track| {
+        order
+            .get(&PathBuf::from(&track.path))
+            .copied()
+            .unwrap_or(usize::MAX)
+    });
 
     // 如果有错误但仍有成功的文件，返回成功的结果
     if !tracks.is_empty() {
@@ -91,54 +418,140 @@ pub async fn get_metadata_for_files(files: Vec<String>) -> Result<Vec<Track>, St
     }
 }
 
-async fn extract_metadata(path_str: &str) -> Result<Track, String> {
+// 元数据提取失败时使用的基本Track
+fn fallback_track(path_str: &str) -> Track {
+    Track {
+        path: path_str.to_string(),
+        title: Some(
+            Path::new(path_str)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+        ),
+        artist: Some("Unknown Artist".to_string()),
+        album: Some("Unknown Album".to_string()),
+        genre: Some("Unknown".to_string()),
+        year: None,
+        duration: 0,
+        ..Default::default()
+    }
+}
+
+fn read_string(tag: &lofty::tag::Tag, key: ItemKey) -> Option<String> {
+    tag.get_string(&key)
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// 拆分多值标签，支持分号和空字符分隔（"/"、"&" 常见于艺术家名本身，不作为分隔符）
+pub fn split_multi_value(value: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    for part in value.split([';', '\0']) {
+        let part = part.trim();
+        if !part.is_empty() && !values.iter().any(|v| normalize::normalized_eq(v, part)) {
+            values.push(part.to_string());
+        }
+    }
+    values
+}
+
+// 读取多值标签，依次尝试各个键，返回第一个有值的
+fn read_multi_value(tag: &lofty::tag::Tag, keys: &[ItemKey]) -> Vec<String> {
+    for key in keys {
+        let joined = tag.get_strings(key).collect::<Vec<_>>().join(";");
+        let values = split_multi_value(&joined);
+        if !values.is_empty() {
+            return values;
+        }
+    }
+    Vec::new()
+}
+
+fn extract_metadata(path_str: &str) -> Result<Track, String> {
     let path = PathBuf::from(path_str);
 
-    let tagged_file = Probe::open(&path)
-        .map_err(|e| format!("Failed to open file: {}", e))?
-        .read()
-        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let (tagged_file, audio) = properties::read_tagged_file(&path)?;
 
     let properties = tagged_file.properties();
-    let duration = properties.duration().as_secs();
-
-    let tag = tagged_file.primary_tag();
-    let (title, artist, album, genre, year) = if let Some(t) = tag {
-        (
-            t.title().map(|s| s.to_string()),
-            t.artist().map(|s| s.to_string()),
-            t.album().map(|s| s.to_string()),
-            t.get_string(&ItemKey::Genre).map(|s| s.to_string()),
-            t.year(),
-        )
-    } else {
-        (None, None, None, None, None)
+    let mut track = Track {
+        path: path_str.to_string(),
+        duration: properties.duration().as_secs(),
+        audio,
+        ..Default::default()
     };
 
-    Ok(Track {
-        path: path_str.to_string(),
-        title,
-        artist,
-        album,
-        genre,
-        year,
-        duration,
-    })
+    if let Some(t) = tagged_file.primary_tag() {
+        track.title = t.title().map(|s| s.to_string());
+        track.artist = t.artist().map(|s| s.to_string());
+        track.album = t.album().map(|s| s.to_string());
+        track.genre = t.get_string(&ItemKey::Genre).map(|s| s.to_string());
+        track.year = t.year();
+        track.album_artist = t.get_string(&ItemKey::AlbumArtist).map(|s| s.to_string());
+        track.composer = t.get_string(&ItemKey::Composer).map(|s| s.to_string());
+        track.comment = t.comment().map(|s| s.to_string());
+        track.lyrics = t.get_string(&ItemKey::Lyrics).map(|s| s.to_string());
+        track.track_number = t.track();
+        track.disc_number = t.disk();
+        track.track_total = t.track_total();
+        track.disc_total = t.disk_total();
+        track.conductor = read_string(t, ItemKey::Conductor);
+        track.bpm = read_string(t, ItemKey::IntegerBpm)
+            .or_else(|| read_string(t, ItemKey::Bpm))
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .filter(|bpm| *bpm > 0.0)
+            .map(|bpm| bpm.round() as u32);
+        track.compilation = read_string(t, ItemKey::FlagCompilation)
+            .is_some_and(|s| s.trim() == "1" || s.trim().eq_ignore_ascii_case("true"));
+        track.artists = read_multi_value(t, &[ItemKey::TrackArtists, ItemKey::TrackArtist]);
+        track.genres = read_multi_value(t, &[ItemKey::Genre]);
+        track.musicbrainz = MusicBrainzIds {
+            recording_id: read_string(t, ItemKey::MusicBrainzRecordingId),
+            track_id: read_string(t, ItemKey::MusicBrainzTrackId),
+            release_id: read_string(t, ItemKey::MusicBrainzReleaseId),
+            release_group_id: read_string(t, ItemKey::MusicBrainzReleaseGroupId),
+            artist_id: read_string(t, ItemKey::MusicBrainzArtistId),
+            release_artist_id: read_string(t, ItemKey::MusicBrainzReleaseArtistId),
+        };
+        track.sort_names = SortNames {
+            title: read_string(t, ItemKey::TrackTitleSortOrder),
+            artist: read_string(t, ItemKey::TrackArtistSortOrder),
+            album: read_string(t, ItemKey::AlbumTitleSortOrder),
+            album_artist: read_string(t, ItemKey::AlbumArtistSortOrder),
+            composer: read_string(t, ItemKey::ComposerSortOrder),
+        };
+        track.rating = rating::read_rating(t);
+        track.play_count = rating::read_play_count(t);
+
+        // 旧的GBK/Big5标签常被误标为Latin-1，读取时自动修复显示文本
+        if encoding::may_contain_mojibake(t.tag_type()) {
+            encoding::repair_track_encoding(&mut track);
+        }
+    }
+
+    Ok(track)
 }
 
-fn scan_directory_recursive(path: String) -> BoxFuture<'static, Result<Vec<PathBuf>, String>> {
+// 递归收集目录下的音频文件，任务取消后停止遍历并返回已找到的部分
+fn scan_directory_recursive(
+    path: String,
+    job: ScanJob,
+) -> BoxFuture<'static, Result<Vec<PathBuf>, String>> {
     async move {
         let mut files = Vec::new();
         let read_dir = tokio::fs::read_dir(path).await.map_err(|e| e.to_string())?;
         let mut stream = ReadDirStream::new(read_dir);
 
         while let Some(entry) = stream.next().await {
+            if job.is_cancelled() {
+                break;
+            }
             let entry = entry.map_err(|e| e.to_string())?;
             let path = entry.path();
 
             if path.is_dir() {
                 if let Ok(mut sub_files) =
-                    scan_directory_recursive(path.to_str().unwrap().to_string()).await
+                    scan_directory_recursive(path.to_str().unwrap().to_string(), job.clone()).await
                 {
                     files.append(&mut sub_files);
                 }
@@ -179,19 +592,26 @@ fn get_library_file_path(app: &AppHandle) -> Result<PathBuf, String> {
 }
 
 // 保存音乐库到文件
-fn save_library_to_file(app: &AppHandle, library: &MusicLibrary) -> Result<(), String> {
+pub(crate) fn save_library_to_file(app: &AppHandle, library: &MusicLibrary) -> Result<(), String> {
     let file_path = get_library_file_path(app)?;
 
     let content = serde_json::to_string_pretty(library)
         .map_err(|e| format!("Failed to serialize library: {}", e))?;
+    // 内容没有变化时不写入，也不触发智能播放列表刷新
+    if fs::read_to_string(&file_path).is_ok_and(|saved| saved == content) {
+        return Ok(());
+    }
 
     fs::write(&file_path, content).map_err(|e| format!("Failed to write library file: {}", e))?;
+    query::invalidate_query_cache(app);
+    // 智能播放列表依赖音乐库内容，保存后在后台重新计算
+    crate::playlist::schedule_smart_playlist_refresh(app);
 
     Ok(())
 }
 
 // 从文件加载音乐库
-fn load_library_from_file(app: &AppHandle) -> Result<MusicLibrary, String> {
+pub(crate) fn load_library_from_file(app: &AppHandle) -> Result<MusicLibrary, String> {
     let file_path = get_library_file_path(app)?;
 
     if !file_path.exists() {
@@ -201,10 +621,22 @@ fn load_library_from_file(app: &AppHandle) -> Result<MusicLibrary, String> {
     let content = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read library file: {}", e))?;
 
-    let library: MusicLibrary = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse library file: {}", e))?;
+    // 旧版本的文件在启动时由 upgrade_library 升级，这里只读取
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse library file: {}", e))
+}
 
-    Ok(library)
+/// 音乐库和视频库文件的写锁
+///
+/// 监听器、扫描任务和命令都会“读取-修改-保存”库文件，必须持有此锁，否则并发的写入会互相覆盖
+pub type LibraryLockState = Arc<tokio::sync::Mutex<()>>;
+
+/// 获取库文件写锁，持有期间其他写入者等待
+pub(crate) async fn lock_library(app: &AppHandle) -> tokio::sync::OwnedMutexGuard<()> {
+    app.state::<LibraryLockState>()
+        .inner()
+        .clone()
+        .lock_owned()
+        .await
 }
 
 // 新增：获取保存的音乐库
@@ -220,13 +652,39 @@ pub async fn save_library(
     tracks: Vec<LibraryTrack>,
     scanned_paths: Vec<String>,
 ) -> Result<(), String> {
-    let library = MusicLibrary {
+    let _lock = lock_library(&app).await;
+    let previous = load_library_from_file(&app).ok();
+    let mut library = MusicLibrary {
+        version: LIBRARY_VERSION,
         tracks,
         last_scanned_paths: scanned_paths,
         last_updated: chrono::Utc::now().to_rfc3339(),
     };
+    library.ensure_track_ids(previous.as_ref());
+    rescan::complete_submitted_tracks(&mut library, previous.as_ref()).await?;
+    // 前端提交的曲目可能不含评分和播放统计
+    if let Some(previous) = &previous {
+        library.keep_user_data(previous);
+    }
+    // 只有新加入的曲目记录加入时间
+    let known: HashSet<&str> = previous
+        .iter()
+        .flat_map(|previous| previous.tracks.iter().map(|t| t.id.as_str()))
+        .collect();
+    let now = chrono::Utc::now().to_rfc3339();
+    for track in library
+        .tracks
+        .iter_mut()
+        .filter(|t| !known.contains(t.id.as_str()))
+    {
+        track.added_at.get_or_insert_with(|| now.clone());
+    }
 
-    save_library_to_file(&app, &library)
+    save_library_to_file(&app, &library)?;
+    search::rebuild_search_index(&app, &library);
+    watcher::refresh_watching(&app);
+
+    Ok(())
 }
 
 // 新增：清除音乐库
@@ -234,9 +692,23 @@ pub async fn save_library(
 pub async fn clear_library(app: AppHandle) -> Result<(), String> {
     let file_path = get_library_file_path(&app)?;
 
+    let _lock = lock_library(&app).await;
     if file_path.exists() {
+        // 清空前写入快照，可以撤销
+        let library = load_library_from_file(&app)?;
+        let snapshot = crate::history::write_snapshot(&app, &library)?;
         fs::remove_file(&file_path).map_err(|e| format!("Failed to remove library file: {}", e))?;
+        let track_ids = library.tracks.iter().map(|t| t.id.clone()).collect();
+        crate::history::record_operation(
+            &app,
+            "Clear library",
+            vec![crate::history::HistoryChange::LibraryRemoval { track_ids }],
+            Some(snapshot),
+        )?;
     }
+    query::invalidate_query_cache(&app);
+    search::rebuild_search_index(&app, &MusicLibrary::new());
+    crate::playlist::schedule_smart_playlist_refresh(&app);
 
     Ok(())
 }
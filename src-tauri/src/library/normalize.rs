@@ -0,0 +1,91 @@
+use super::*;
+use std::sync::OnceLock;
+
+/// 内置繁简对照表，每行为 `繁体简体` 两个字符
+const TRADITIONAL_TO_SIMPLIFIED: &str = include_str!("t2s.txt");
+
+fn simplified_table() -> &'static HashMap<char, char> {
+    static TABLE: OnceLock<HashMap<char, char>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        TRADITIONAL_TO_SIMPLIFIED
+            .lines()
+            .filter_map(|line| {
+                let mut chars = line.chars();
+                Some((chars.next()?, chars.next()?))
+            })
+            .collect()
+    })
+}
+
+/// 繁体字转简体，没有对应简体的字符原样返回
+pub fn to_simplified(c: char) -> char {
+    simplified_table().get(&c).copied().unwrap_or(c)
+}
+
+/// 全角字符转半角，并统一常见的中文标点
+pub fn to_half_width(c: char) -> char {
+    match c {
+        // 全角ASCII区：！到～
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        '、' => ',',
+        '。' => '.',
+        '「' | '」' | '『' | '』' | '“' | '”' => '"',
+        '‘' | '’' => '\'',
+        '【' | '〔' => '[',
+        '】' | '〕' => ']',
+        '《' | '〈' => '<',
+        '》' | '〉' => '>',
+        '・' | '•' => '·',
+        _ => c,
+    }
+}
+
+/// 比较用的规范化文本：全角转半角、繁体转简体、小写化并合并空白
+///
+/// 只用于搜索、分组和查重，显示时仍使用原始标签文本
+pub fn normalize_text(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    let mut pending_space = false;
+
+    for c in text.chars().map(to_half_width).map(to_simplified) {
+        if c.is_whitespace() {
+            pending_space = !normalized.is_empty();
+            continue;
+        }
+        if pending_space {
+            normalized.push(' ');
+            pending_space = false;
+        }
+        normalized.extend(c.to_lowercase());
+    }
+
+    normalized
+}
+
+/// 规范化后是否相同，如 "張學友" 与 "张学友"、"Ｊａｙ" 与 "jay"
+pub fn normalized_eq(a: &str, b: &str) -> bool {
+    normalize_text(a) == normalize_text(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traditional_to_simplified() {
+        assert_eq!(normalize_text("張學友"), "张学友");
+        assert_eq!(normalize_text("劉德華 - 忘情水"), "刘德华 - 忘情水");
+        assert!(normalized_eq("陳奕迅", "陈奕迅"));
+    }
+
+    #[test]
+    fn test_full_width_and_whitespace() {
+        assert_eq!(normalize_text("Ｊａｙ"), "jay");
+        assert_eq!(
+            normalize_text("  Ｈｅｌｌｏ，\u{3000}World！ "),
+            "hello, world!"
+        );
+        assert_eq!(normalize_text("《七里香》"), "<七里香>");
+    }
+}
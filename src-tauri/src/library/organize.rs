@@ -0,0 +1,834 @@
+use super::*;
+use std::collections::HashSet;
+
+/// 随音频文件一起移动的同名附属文件
+const SIDECAR_EXTENSIONS: [&str; 2] = ["lrc", "cue"];
+
+/// 模板中可用的占位符
+const TEMPLATE_FIELDS: [&str; 10] = [
+    "title",
+    "artist",
+    "album",
+    "albumartist",
+    "genre",
+    "year",
+    "track",
+    "disc",
+    "composer",
+    "ext",
+];
+
+/// 单个路径组件的最大字节数，留出冲突后缀的空间
+const MAX_COMPONENT_BYTES: usize = 200;
+
+/// 撤销日志最多保留的记录数
+const MAX_JOURNAL_ENTRIES: usize = 50;
+
+/// Windows保留的设备名，不能用作文件名
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// 整理模板，如 `{albumartist}/{year} - {album}/{disc}-{track} {title}.{ext}`
+///
+/// `/` 分隔目录层级，文件名未包含 `{ext}` 时自动保留原扩展名
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrganizeTemplate {
+    directories: Vec<String>,
+    file_stem: String,
+}
+
+impl OrganizeTemplate {
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let normalized = template.trim().replace('\\', "/");
+        let mut components: Vec<String> = normalized
+            .split('/')
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty())
+            .collect();
+
+        for component in &components {
+            for name in placeholders(component)? {
+                if !TEMPLATE_FIELDS.contains(&name.as_str()) {
+                    return Err(format!("Unknown placeholder: {{{}}}", name));
+                }
+            }
+        }
+
+        let file_name = components.pop().ok_or("Template is empty")?;
+        let file_stem = file_name
+            .strip_suffix(".{ext}")
+            .unwrap_or(&file_name)
+            .to_string();
+        if file_stem.contains("{ext}") {
+            return Err("{ext} must be at the end of the file name".to_string());
+        }
+        if !file_stem.contains("{title}") && !file_stem.contains("{track}") {
+            return Err("File name must contain {title} or {track}".to_string());
+        }
+
+        Ok(Self {
+            directories: components,
+            file_stem,
+        })
+    }
+
+    /// 按曲目标签生成相对路径
+    pub fn render(&self, track: &LibraryTrack) -> PathBuf {
+        let mut path: PathBuf = self
+            .directories
+            .iter()
+            .map(|component| finish_component(&substitute(component, track)))
+            .collect();
+
+        let stem = finish_component(&substitute(&self.file_stem, track));
+        let ext = track.format();
+        if ext.is_empty() {
+            path.push(stem);
+        } else {
+            path.push(format!("{}.{}", stem, ext));
+        }
+        path
+    }
+}
+
+// 提取组件中的占位符名称
+fn placeholders(component: &str) -> Result<Vec<String>, String> {
+    let mut names = Vec::new();
+    let mut rest = component;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in template: {}", component))?;
+        names.push(after[..end].to_lowercase());
+        rest = &after[end + 1..];
+    }
+    Ok(names)
+}
+
+fn field_value(name: &str, track: &LibraryTrack) -> String {
+    match name {
+        "title" => track.title.clone(),
+        "artist" => track.artist.clone(),
+        "album" => track.album.clone(),
+        "albumartist" => browse::album_artist_of(track).to_string(),
+        "genre" => track.genre.clone().unwrap_or_default(),
+        "year" => track.year.map(|y| y.to_string()).unwrap_or_default(),
+        "track" => track
+            .track_number
+            .map(|n| format!("{:02}", n))
+            .unwrap_or_default(),
+        "disc" => track.disc_number.map(|n| n.to_string()).unwrap_or_default(),
+        "composer" => track.composer.clone().unwrap_or_default(),
+        "ext" => track.format(),
+        _ => String::new(),
+    }
+}
+
+// 替换占位符，标签值中的路径分隔符等非法字符会被替换
+fn substitute(component: &str, track: &LibraryTrack) -> String {
+    let mut result = String::new();
+    let mut rest = component;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        result.push_str(&sanitize_value(&field_value(
+            &after[..end].to_lowercase(),
+            track,
+        )));
+        rest = &after[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 替换在Windows、macOS或Linux上不能出现在文件名中的字符
+pub fn sanitize_value(value: &str) -> String {
+    value
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+// 整理路径组件：合并空白，去掉缺失字段留下的首尾分隔符，避开保留名并限制长度
+fn finish_component(component: &str) -> String {
+    let trim = |s: &str| {
+        s.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '_' | '.'))
+            .to_string()
+    };
+    let collapsed = component.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut result = trim(&collapsed);
+
+    if result.len() > MAX_COMPONENT_BYTES {
+        let mut end = MAX_COMPONENT_BYTES;
+        while !result.is_char_boundary(end) {
+            end -= 1;
+        }
+        result = trim(&result[..end]);
+    }
+    if result.is_empty() {
+        return "Unknown".to_string();
+    }
+
+    let base = result.split('.').next().unwrap_or("").to_uppercase();
+    if RESERVED_NAMES.contains(&base.as_str()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+// 文件名后追加序号，如 "晴天 (2).mp3"
+fn with_counter(path: &Path, counter: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{} ({}).{}", stem, counter, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, counter),
+    };
+    path.with_file_name(name)
+}
+
+// 路径比较忽略大小写，兼容不区分大小写的文件系统
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// 文件操作，用于执行计划和撤销日志
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum FileOperation {
+    Move { from: String, to: String },
+    Copy { from: String, to: String },
+}
+
+/// 单个曲目的移动计划
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedMove {
+    pub track_id: String,
+    pub from: String,
+    pub to: String,
+    /// 同名的 .lrc、.cue 文件
+    pub sidecars: Vec<FileOperation>,
+}
+
+/// 整理计划
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrganizePlan {
+    pub moves: Vec<PlannedMove>,
+    /// 目录中的封面文件
+    pub artwork: Vec<FileOperation>,
+    /// 已位于目标位置的曲目数
+    pub unchanged: usize,
+}
+
+// 同目录下与音频文件同名的附属文件
+fn find_sidecars(audio: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem)) = (audio.parent(), audio.file_stem()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut sidecars: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_stem() == Some(stem)
+                && path
+                    .extension()
+                    .and_then(|s| s.to_str())
+                    .is_some_and(|ext| {
+                        SIDECAR_EXTENSIONS
+                            .iter()
+                            .any(|e| ext.eq_ignore_ascii_case(e))
+                    })
+        })
+        .collect();
+    sidecars.sort();
+    sidecars
+}
+
+// 选择不冲突的目标路径，已被其他曲目占用或磁盘上已存在时追加序号
+fn resolve_target(from: &Path, target: PathBuf, claimed: &mut HashSet<String>) -> PathBuf {
+    let mut candidate = target.clone();
+    let mut counter = 1;
+    loop {
+        let key = path_key(&candidate);
+        if !claimed.contains(&key) && (key == path_key(from) || !candidate.exists()) {
+            claimed.insert(key);
+            return candidate;
+        }
+        counter += 1;
+        candidate = with_counter(&target, counter);
+    }
+}
+
+// 封面随目录移动：源目录的音频全部移到同一目录时移动，否则复制到每个目标目录
+fn plan_artwork(moves: &[PlannedMove]) -> Vec<FileOperation> {
+    let moving: HashSet<String> = moves.iter().map(|m| path_key(Path::new(&m.from))).collect();
+    let mut targets: HashMap<PathBuf, HashSet<PathBuf>> = HashMap::new();
+    for planned in moves {
+        let (Some(source), Some(target)) = (
+            Path::new(&planned.from).parent(),
+            Path::new(&planned.to).parent(),
+        ) else {
+            continue;
+        };
+        targets
+            .entry(source.to_path_buf())
+            .or_default()
+            .insert(target.to_path_buf());
+    }
+
+    let mut operations = Vec::new();
+    for (source, target_dirs) in targets {
+        let Ok(entries) = fs::read_dir(&source) else {
+            continue;
+        };
+        let files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file())
+            .collect();
+        let all_moved = files
+            .iter()
+            .filter(|path| is_audio_file(path))
+            .all(|path| moving.contains(&path_key(path)));
+        let relocate = all_moved && target_dirs.len() == 1 && !target_dirs.contains(&source);
+
+        for cover in files.iter().filter(|path| artwork::is_folder_artwork(path)) {
+            let Some(name) = cover.file_name() else {
+                continue;
+            };
+            for target_dir in target_dirs.iter().filter(|dir| **dir != source) {
+                let to = target_dir.join(name);
+                if to.exists() {
+                    continue;
+                }
+                let from = cover.to_string_lossy().to_string();
+                let to = to.to_string_lossy().to_string();
+                operations.push(if relocate {
+                    FileOperation::Move { from, to }
+                } else {
+                    FileOperation::Copy { from, to }
+                });
+            }
+        }
+    }
+
+    operations.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+    operations
+}
+
+/// 根据模板计算整理计划，只读取文件系统，不做任何修改
+pub fn plan_organize(
+    tracks: &[LibraryTrack],
+    template: &OrganizeTemplate,
+    destination: &Path,
+) -> OrganizePlan {
+    let mut plan = OrganizePlan::default();
+    let mut claimed = HashSet::new();
+
+    for track in tracks {
+        let from = PathBuf::from(&track.file_path);
+        let target = resolve_target(
+            &from,
+            destination.join(template.render(track)),
+            &mut claimed,
+        );
+        if target == from {
+            plan.unchanged += 1;
+            continue;
+        }
+
+        let target_stem = target
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let sidecars = find_sidecars(&from)
+            .into_iter()
+            .map(|sidecar| {
+                let ext = sidecar
+                    .extension()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                FileOperation::Move {
+                    from: sidecar.to_string_lossy().to_string(),
+                    to: target
+                        .with_file_name(format!("{}.{}", target_stem, ext))
+                        .to_string_lossy()
+                        .to_string(),
+                }
+            })
+            .collect();
+
+        plan.moves.push(PlannedMove {
+            track_id: track.id.clone(),
+            from: track.file_path.clone(),
+            to: target.to_string_lossy().to_string(),
+            sidecars,
+        });
+    }
+
+    plan.artwork = plan_artwork(&plan.moves);
+    plan
+}
+
+// 移动文件，跨磁盘时改为复制后删除；只改大小写的重命名不视为冲突
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if to.exists() && path_key(from) != path_key(to) {
+        return Err(format!("Target already exists: {}", to.to_string_lossy()));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    fs::copy(from, to).map_err(|e| format!("Failed to move file: {}", e))?;
+    fs::remove_file(from).map_err(|e| {
+        let _ = fs::remove_file(to);
+        format!("Failed to move file: {}", e)
+    })
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
+    if to.exists() {
+        return Err(format!("Target already exists: {}", to.to_string_lossy()));
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::copy(from, to)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to copy file: {}", e))
+}
+
+fn apply_operation(operation: &FileOperation) -> Result<(), String> {
+    match operation {
+        FileOperation::Move { from, to } => move_file(Path::new(from), Path::new(to)),
+        FileOperation::Copy { from, to } => copy_file(Path::new(from), Path::new(to)),
+    }
+}
+
+// 撤销单个操作，已经撤销过的操作直接跳过
+fn revert_operation(operation: &FileOperation) -> Result<(), String> {
+    match operation {
+        FileOperation::Move { from, to } => {
+            let (from, to) = (Path::new(from), Path::new(to));
+            if !to.exists() && from.exists() {
+                return Ok(());
+            }
+            move_file(to, from)
+        }
+        FileOperation::Copy { to, .. } => {
+            let to = Path::new(to);
+            if !to.exists() {
+                return Ok(());
+            }
+            fs::remove_file(to).map_err(|e| format!("Failed to remove file: {}", e))
+        }
+    }
+}
+
+// 删除变空的目录，只处理文件所在的那一层
+fn remove_empty_dirs<'a>(paths: impl Iterator<Item = &'a str>) {
+    let dirs: HashSet<PathBuf> = paths
+        .filter_map(|path| Path::new(path).parent().map(Path::to_path_buf))
+        .collect();
+    for dir in dirs {
+        // 目录非空时删除失败，忽略即可
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+/// 执行结果：已完成的操作、已移动的曲目和错误
+#[derive(Debug, Clone, Default)]
+pub struct OrganizeOutcome {
+    pub operations: Vec<FileOperation>,
+    pub moved: Vec<PlannedMove>,
+    pub errors: Vec<String>,
+}
+
+// 执行操作并记录结果
+fn record(operation: &FileOperation, outcome: &mut OrganizeOutcome) -> bool {
+    match apply_operation(operation) {
+        Ok(()) => {
+            outcome.operations.push(operation.clone());
+            true
+        }
+        Err(e) => {
+            outcome.errors.push(e);
+            false
+        }
+    }
+}
+
+/// 按计划移动文件，单个文件失败不影响其他文件
+pub fn execute_plan(plan: &OrganizePlan) -> OrganizeOutcome {
+    let mut outcome = OrganizeOutcome::default();
+
+    for planned in &plan.moves {
+        let operation = FileOperation::Move {
+            from: planned.from.clone(),
+            to: planned.to.clone(),
+        };
+        if !record(&operation, &mut outcome) {
+            continue;
+        }
+        for sidecar in &planned.sidecars {
+            record(sidecar, &mut outcome);
+        }
+        outcome.moved.push(planned.clone());
+    }
+    for operation in &plan.artwork {
+        record(operation, &mut outcome);
+    }
+
+    remove_empty_dirs(outcome.operations.iter().filter_map(|op| match op {
+        FileOperation::Move { from, .. } => Some(from.as_str()),
+        FileOperation::Copy { .. } => None,
+    }));
+    outcome
+}
+
+/// 按相反顺序撤销操作，返回错误列表
+pub fn revert_operations(operations: &[FileOperation]) -> Vec<String> {
+    let errors = operations
+        .iter()
+        .rev()
+        .filter_map(|operation| revert_operation(operation).err())
+        .collect();
+    remove_empty_dirs(operations.iter().map(|op| match op {
+        FileOperation::Move { to, .. } | FileOperation::Copy { to, .. } => to.as_str(),
+    }));
+    errors
+}
+
+/// 一次整理的撤销记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrganizeJournal {
+    pub id: String,
+    pub created_at: String,
+    pub template: String,
+    pub operations: Vec<FileOperation>,
+    pub tracks: Vec<PlannedMove>,
+}
+
+/// 整理结果，`dry_run` 时只包含计划
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrganizeReport {
+    pub dry_run: bool,
+    pub moves: Vec<PlannedMove>,
+    pub artwork: Vec<FileOperation>,
+    pub unchanged: usize,
+    pub errors: Vec<String>,
+    pub journal_id: Option<String>,
+}
+
+fn get_journal_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(app_data_dir.join("organize_journal.json"))
+}
+
+fn load_journals(app: &AppHandle) -> Result<Vec<OrganizeJournal>, String> {
+    let file_path = get_journal_file_path(app)?;
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read organize journal: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse organize journal: {}", e))
+}
+
+fn save_journals(app: &AppHandle, journals: &[OrganizeJournal]) -> Result<(), String> {
+    let file_path = get_journal_file_path(app)?;
+    let content = serde_json::to_string_pretty(journals)
+        .map_err(|e| format!("Failed to serialize organize journal: {}", e))?;
+    fs::write(&file_path, content).map_err(|e| format!("Failed to write organize journal: {}", e))
+}
+
+// 更新音乐库和播放列表中的文件路径
+async fn relink_paths(app: &AppHandle, moves: HashMap<String, String>) -> Result<(), String> {
+    if moves.is_empty() {
+        return Ok(());
+    }
+
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    let mut changed = Vec::new();
+    for track in library.tracks.iter_mut() {
+        if let Some(new_path) = moves.get(&track.file_path) {
+            track.file_path = new_path.clone();
+            changed.push(track.id.clone());
+        }
+    }
+    library.last_updated = chrono::Utc::now().to_rfc3339();
+    save_library_to_file(app, &library)?;
+    search::update_search_index(app, &library, &changed, &[]);
+
+    crate::playlist::update_playlist_paths(app, &moves).await?;
+    Ok(())
+}
+
+/// 按模板移动和重命名音乐库中的文件，未指定曲目时整理整个音乐库
+///
+/// `dry_run` 为 true 时只返回计划；执行后写入撤销日志，并同步更新音乐库和播放列表
+#[tauri::command]
+pub async fn organize_library(
+    app: AppHandle,
+    track_ids: Option<Vec<String>>,
+    template: String,
+    destination: String,
+    dry_run: bool,
+) -> Result<OrganizeReport, String> {
+    let parsed = OrganizeTemplate::parse(&template)?;
+    let destination = PathBuf::from(&destination);
+    if !destination.is_absolute() || !destination.is_dir() {
+        return Err(format!(
+            "Destination is not a directory: {}",
+            destination.to_string_lossy()
+        ));
+    }
+
+    let library = load_library_from_file(&app)?;
+    let tracks: Vec<LibraryTrack> = match &track_ids {
+        Some(ids) => editor::track_indices(&library, ids)?
+            .into_iter()
+            .map(|index| library.tracks[index].clone())
+            .collect(),
+        None => library.tracks.clone(),
+    };
+
+    let plan = tokio::task::spawn_blocking(move || plan_organize(&tracks, &parsed, &destination))
+        .await
+        .map_err(|e| format!("Organize task failed: {}", e))?;
+    if dry_run {
+        return Ok(OrganizeReport {
+            dry_run,
+            moves: plan.moves,
+            artwork: plan.artwork,
+            unchanged: plan.unchanged,
+            ..Default::default()
+        });
+    }
+
+    let executed = plan.clone();
+    let outcome = tokio::task::spawn_blocking(move || execute_plan(&executed))
+        .await
+        .map_err(|e| format!("Organize task failed: {}", e))?;
+
+    // 文件已经移动，先保存撤销记录，更新音乐库失败时仍可撤销
+    let mut journal_id = None;
+    if !outcome.operations.is_empty() {
+        let journal = OrganizeJournal {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            template,
+            operations: outcome.operations,
+            tracks: outcome.moved.clone(),
+        };
+        journal_id = Some(journal.id.clone());
+
+        let mut journals = load_journals(&app)?;
+        journals.push(journal);
+        let excess = journals.len().saturating_sub(MAX_JOURNAL_ENTRIES);
+        journals.drain(..excess);
+        save_journals(&app, &journals)?;
+    }
+
+    let moves: HashMap<String, String> = outcome
+        .moved
+        .iter()
+        .map(|m| (m.from.clone(), m.to.clone()))
+        .collect();
+    relink_paths(&app, moves).await?;
+
+    Ok(OrganizeReport {
+        dry_run,
+        moves: outcome.moved,
+        artwork: plan.artwork,
+        unchanged: plan.unchanged,
+        errors: outcome.errors,
+        journal_id,
+    })
+}
+
+/// 获取整理的撤销记录，最新的在最后
+#[tauri::command]
+pub async fn get_organize_journal(app: AppHandle) -> Result<Vec<OrganizeJournal>, String> {
+    load_journals(&app)
+}
+
+/// 撤销一次整理，把文件移回原位置并恢复音乐库和播放列表中的路径
+///
+/// 全部撤销成功后删除该记录，部分失败时保留以便重试
+#[tauri::command]
+pub async fn undo_organize(app: AppHandle, journal_id: String) -> Result<Vec<String>, String> {
+    let mut journals = load_journals(&app)?;
+    let journal = journals
+        .iter()
+        .find(|j| j.id == journal_id)
+        .cloned()
+        .ok_or_else(|| format!("Organize journal not found: {}", journal_id))?;
+
+    let operations = journal.operations.clone();
+    let errors = tokio::task::spawn_blocking(move || revert_operations(&operations))
+        .await
+        .map_err(|e| format!("Organize task failed: {}", e))?;
+
+    // 只恢复已经移回原位置的曲目
+    let moves: HashMap<String, String> = journal
+        .tracks
+        .iter()
+        .filter(|m| Path::new(&m.from).exists())
+        .map(|m| (m.to.clone(), m.from.clone()))
+        .collect();
+    relink_paths(&app, moves).await?;
+
+    if errors.is_empty() {
+        journals.retain(|j| j.id != journal_id);
+        save_journals(&app, &journals)?;
+    }
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support::{self, temp_dir};
+
+    fn track(id: &str, path: &Path, title: &str, track_number: u32) -> LibraryTrack {
+        test_support::track(id)
+            .title(title)
+            .artist("AC/DC")
+            .album("Back in Black")
+            .year(1980)
+            .track_number(track_number)
+            .path(path)
+            .build()
+    }
+
+    #[test]
+    fn test_parse_template() {
+        assert!(OrganizeTemplate::parse("{albumartist}/{year} - {album}/{title}.{ext}").is_ok());
+        assert!(OrganizeTemplate::parse("{artist}/{album}").is_err());
+        assert!(OrganizeTemplate::parse("{artist}/{singer} {title}").is_err());
+        assert!(OrganizeTemplate::parse("{artist}/{title").is_err());
+        assert!(OrganizeTemplate::parse("{ext}/{title}.{ext}").is_ok());
+        assert!(OrganizeTemplate::parse("{title}.{ext}.bak").is_err());
+    }
+
+    #[test]
+    fn test_render_sanitizes_and_cleans_missing_fields() {
+        let template =
+            OrganizeTemplate::parse("{albumartist}/{year} - {album}/{disc}-{track} {title}.{ext}")
+                .unwrap();
+        let mut song = track("1", Path::new("/music/a.MP3"), "Hells Bells?", 1);
+        assert_eq!(
+            template.render(&song),
+            PathBuf::from("AC_DC/1980 - Back in Black/01 Hells Bells.mp3")
+        );
+
+        song.year = None;
+        song.disc_number = Some(2);
+        song.title = "CON".to_string();
+        song.track_number = None;
+        let template = OrganizeTemplate::parse("{artist}/{year} - {album}/{title}").unwrap();
+        assert_eq!(
+            template.render(&song),
+            PathBuf::from("AC_DC/Back in Black/_CON.mp3")
+        );
+    }
+
+    #[test]
+    fn test_plan_resolves_collisions_and_carries_sidecars() {
+        let source = temp_dir("source");
+        let destination = temp_dir("destination");
+        let first = source.join("a.mp3");
+        let second = source.join("b.mp3");
+        for file in [&first, &second] {
+            fs::write(file, b"audio").unwrap();
+        }
+        fs::write(source.join("a.lrc"), b"lyrics").unwrap();
+        fs::write(source.join("cover.jpg"), b"image").unwrap();
+
+        let template = OrganizeTemplate::parse("{artist}/{album}/{title}").unwrap();
+        let tracks = vec![
+            track("1", &first, "Shoot to Thrill", 2),
+            track("2", &second, "Shoot to Thrill", 2),
+        ];
+        let plan = plan_organize(&tracks, &template, &destination);
+        let album = destination.join("AC_DC").join("Back in Black");
+
+        assert_eq!(plan.moves.len(), 2);
+        assert_eq!(
+            PathBuf::from(&plan.moves[1].to),
+            album.join("Shoot to Thrill (2).mp3")
+        );
+        assert_eq!(
+            plan.moves[0].sidecars,
+            vec![FileOperation::Move {
+                from: source.join("a.lrc").to_string_lossy().to_string(),
+                to: album
+                    .join("Shoot to Thrill.lrc")
+                    .to_string_lossy()
+                    .to_string(),
+            }]
+        );
+        assert!(matches!(
+            plan.artwork.as_slice(),
+            [FileOperation::Move { .. }]
+        ));
+
+        fs::remove_dir_all(&source).unwrap();
+        fs::remove_dir_all(&destination).unwrap();
+    }
+
+    #[test]
+    fn test_execute_and_revert() {
+        let source = temp_dir("execute");
+        let destination = temp_dir("execute-destination");
+        let file = source.join("a.flac");
+        fs::write(&file, b"audio").unwrap();
+        fs::write(source.join("folder.png"), b"image").unwrap();
+
+        let template = OrganizeTemplate::parse("{artist}/{track} {title}").unwrap();
+        let plan = plan_organize(
+            &[track("1", &file, "Hells Bells", 1)],
+            &template,
+            &destination,
+        );
+        let outcome = execute_plan(&plan);
+        assert!(outcome.errors.is_empty());
+        assert_eq!(outcome.moved.len(), 1);
+
+        let moved = destination.join("AC_DC").join("01 Hells Bells.flac");
+        assert!(moved.exists());
+        assert!(destination.join("AC_DC").join("folder.png").exists());
+        // 源目录已清空并删除
+        assert!(!source.exists());
+
+        assert!(revert_operations(&outcome.operations).is_empty());
+        assert!(file.exists());
+        assert!(source.join("folder.png").exists());
+        assert!(!destination.join("AC_DC").exists());
+
+        fs::remove_dir_all(&source).unwrap();
+        fs::remove_dir_all(&destination).unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use super::*;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+/// 内置拼音字典，每行为 `拼音 汉字...`，多音字只收录最常用读音
+const PINYIN_DATA: &str = include_str!("pinyin.txt");
+
+fn dictionary() -> &'static HashMap<char, &'static str> {
+    static DICTIONARY: OnceLock<HashMap<char, &'static str>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        let mut dictionary = HashMap::new();
+        for line in PINYIN_DATA.lines() {
+            if let Some((syllable, chars)) = line.split_once(' ') {
+                for c in chars.chars() {
+                    dictionary.insert(c, syllable);
+                }
+            }
+        }
+        dictionary
+    })
+}
+
+/// 单个汉字的拼音（不带声调）
+pub fn char_pinyin(c: char) -> Option<&'static str> {
+    dictionary().get(&c).copied()
+}
+
+/// 用于搜索索引的拼音词：每个音节、连续汉字的全拼和首字母
+///
+/// 例如 "周杰伦" 生成 `zhou`、`jie`、`lun`、`zhoujielun`、`zjl`
+pub fn pinyin_terms(text: &str) -> Vec<String> {
+    fn flush(syllables: &mut Vec<&str>, terms: &mut Vec<String>) {
+        if syllables.len() > 1 {
+            terms.push(syllables.concat());
+            terms.push(syllables.iter().filter_map(|s| s.get(..1)).collect());
+        }
+        syllables.clear();
+    }
+
+    let mut terms = Vec::new();
+    let mut syllables: Vec<&str> = Vec::new();
+
+    for c in text.chars() {
+        match char_pinyin(c) {
+            Some(syllable) => {
+                terms.push(syllable.to_string());
+                syllables.push(syllable);
+            }
+            None => flush(&mut syllables, &mut terms),
+        }
+    }
+    flush(&mut syllables, &mut terms);
+
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// A–Z 索引字母，汉字取拼音首字母，其它非字母开头的归入 `#`
+pub fn index_letter(text: &str) -> char {
+    let Some(first) = normalize::normalize_text(text).chars().next() else {
+        return '#';
+    };
+    let letter = match char_pinyin(first) {
+        Some(syllable) => syllable.chars().next().unwrap_or('#'),
+        None => first,
+    };
+    // 带重音的拉丁字母按基本字母归类
+    let letter = base_letter(letter);
+
+    if letter.is_ascii_alphabetic() {
+        letter.to_ascii_uppercase()
+    } else {
+        '#'
+    }
+}
+
+fn base_letter(c: char) -> char {
+    match c {
+        'À'..='Å' | 'à'..='å' => 'a',
+        'Ç' | 'ç' => 'c',
+        'È'..='Ë' | 'è'..='ë' => 'e',
+        'Ì'..='Ï' | 'ì'..='ï' => 'i',
+        'Ñ' | 'ñ' => 'n',
+        'Ò'..='Ö' | 'ò'..='ö' => 'o',
+        'Ù'..='Ü' | 'ù'..='ü' => 'u',
+        'Ý' | 'ý' | 'ÿ' => 'y',
+        _ => c,
+    }
+}
+
+/// 排序键：先按索引字母分组，组内汉字按拼音、其它字符按规范化文本比较
+pub fn collation_key(text: &str) -> String {
+    let text = normalize::normalize_text(text);
+    let mut key = String::with_capacity(text.len() * 2);
+    key.push(index_letter(&text).to_ascii_lowercase());
+
+    for c in text.chars() {
+        match char_pinyin(c) {
+            Some(syllable) => {
+                key.push_str(syllable);
+                // 音节分隔，使 "西安" 排在 "先" 之前
+                key.push(' ');
+            }
+            None => key.push(c),
+        }
+    }
+
+    key
+}
+
+/// 按拼音排序规则比较两个字符串，拼音相同时按原文区分
+///
+/// 每次比较都会生成排序键，排序列表时应使用 `sort_by_cached_key(|x| collated(..))`
+pub fn compare_collated(a: &str, b: &str) -> Ordering {
+    collated(a).cmp(&collated(b))
+}
+
+/// 与 `compare_collated` 顺序一致的排序键
+pub fn collated(text: &str) -> (String, String) {
+    (collation_key(text), text.to_string())
+}
+
+/// A–Z 索引分组，`offset` 为该组在排序结果中的起始位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexGroup {
+    pub letter: char,
+    pub offset: usize,
+    pub count: usize,
+}
+
+/// 对已排序的名称列表生成索引分组，相邻的同字母项合并为一组
+pub fn index_groups<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<IndexGroup> {
+    let mut groups: Vec<IndexGroup> = Vec::new();
+
+    for (offset, name) in names.into_iter().enumerate() {
+        let letter = index_letter(name);
+        match groups.last_mut() {
+            Some(group) if group.letter == letter => group.count += 1,
+            _ => groups.push(IndexGroup {
+                letter,
+                offset,
+                count: 1,
+            }),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pinyin_terms() {
+        let terms = pinyin_terms("周杰伦");
+        for expected in ["zhou", "jie", "lun", "zhoujielun", "zjl"] {
+            assert!(terms.contains(&expected.to_string()), "{}", expected);
+        }
+        assert!(pinyin_terms("Yesterday").is_empty());
+    }
+
+    #[test]
+    fn test_common_polyphones_use_common_reading() {
+        assert_eq!(char_pinyin('了'), Some("le"));
+        assert_eq!(char_pinyin('还'), Some("hai"));
+        assert_eq!(char_pinyin('都'), Some("dou"));
+        assert!(pinyin_terms("说了再见").contains(&"shuolezaijian".to_string()));
+    }
+
+    #[test]
+    fn test_index_letter() {
+        assert_eq!(index_letter("周杰伦"), 'Z');
+        assert_eq!(index_letter("陳奕迅"), 'C');
+        assert_eq!(index_letter("abba"), 'A');
+        assert_eq!(index_letter("Ｊａｙ"), 'J');
+        assert_eq!(index_letter("Édith Piaf"), 'E');
+        assert_eq!(index_letter("2Pac"), '#');
+        assert_eq!(index_letter(""), '#');
+    }
+
+    #[test]
+    fn test_collation_mixes_chinese_and_latin() {
+        let mut names = vec![
+            "周杰伦",
+            "Beyond",
+            "陈奕迅",
+            "Adele",
+            "张学友",
+            "蔡依林",
+            "1975",
+        ];
+        names.sort_by_cached_key(|name| collated(name));
+        assert_eq!(
+            names,
+            vec![
+                "1975",
+                "Adele",
+                "Beyond",
+                "蔡依林",
+                "陈奕迅",
+                "张学友",
+                "周杰伦"
+            ]
+        );
+
+        let groups = index_groups(names.iter().copied());
+        let letters: Vec<char> = groups.iter().map(|g| g.letter).collect();
+        assert_eq!(letters, vec!['#', 'A', 'B', 'C', 'Z']);
+        assert_eq!(groups[3].offset, 3);
+        assert_eq!(groups[3].count, 2);
+    }
+}
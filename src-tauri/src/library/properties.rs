@@ -0,0 +1,107 @@
+use super::*;
+use lofty::config::ParseOptions;
+use lofty::file::{FileType, TaggedFile};
+use lofty::mp4::{Mp4Codec, Mp4File, Mp4Properties};
+
+/// 音频技术参数
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioProperties {
+    /// 编码格式，如 "MP3"、"FLAC"、"AAC"、"ALAC"
+    pub codec: Option<String>,
+    pub lossless: bool,
+    /// 总比特率（kbps）
+    pub overall_bitrate: Option<u32>,
+    /// 音频流比特率（kbps）
+    pub audio_bitrate: Option<u32>,
+    /// 采样率（Hz）
+    pub sample_rate: Option<u32>,
+    pub bit_depth: Option<u8>,
+    pub channels: Option<u8>,
+    pub duration_ms: u64,
+}
+
+impl AudioProperties {
+    /// 用于过滤的比特率，优先使用音频流比特率
+    pub fn bitrate(&self) -> Option<u32> {
+        self.audio_bitrate.or(self.overall_bitrate)
+    }
+}
+
+// 无损编码
+fn is_lossless_codec(codec: &str) -> bool {
+    matches!(
+        codec,
+        "FLAC" | "ALAC" | "WAV" | "AIFF" | "APE" | "WavPack" | "TTA"
+    )
+}
+
+// MP4容器的具体编码（AAC或ALAC等）
+fn mp4_codec(properties: &Mp4Properties) -> Option<String> {
+    let codec = match properties.codec() {
+        Mp4Codec::AAC => "AAC",
+        Mp4Codec::ALAC => "ALAC",
+        Mp4Codec::MP3 => "MP3",
+        Mp4Codec::FLAC => "FLAC",
+        _ => return None,
+    };
+    Some(codec.to_string())
+}
+
+fn codec_name(file_type: &FileType) -> String {
+    match file_type {
+        FileType::Mpeg => "MP3".to_string(),
+        FileType::Flac => "FLAC".to_string(),
+        FileType::Mp4 => "MP4".to_string(),
+        FileType::Aac => "AAC".to_string(),
+        FileType::Aiff => "AIFF".to_string(),
+        FileType::Ape => "APE".to_string(),
+        FileType::Mpc => "MPC".to_string(),
+        FileType::Opus => "Opus".to_string(),
+        FileType::Vorbis => "Vorbis".to_string(),
+        FileType::Speex => "Speex".to_string(),
+        FileType::Wav => "WAV".to_string(),
+        FileType::WavPack => "WavPack".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// 读取文件标签和音频技术参数
+///
+/// MP4容器直接按MP4解析，在同一次读取中取得具体编码，不必再次打开文件
+pub fn read_tagged_file(path: &Path) -> Result<(TaggedFile, AudioProperties), String> {
+    let probe = Probe::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+
+    let (tagged_file, codec) = if probe.file_type() == Some(FileType::Mp4) {
+        let mut reader = probe.into_inner();
+        let mp4 = Mp4File::read_from(&mut reader, ParseOptions::new())
+            .map_err(|e| format!("Failed to read metadata: {}", e))?;
+        let codec = mp4_codec(mp4.properties());
+        (TaggedFile::from(mp4), codec)
+    } else {
+        let tagged_file = probe
+            .read()
+            .map_err(|e| format!("Failed to read metadata: {}", e))?;
+        (tagged_file, None)
+    };
+
+    let audio = read_audio_properties(&tagged_file, codec);
+    Ok((tagged_file, audio))
+}
+
+// 读取音频技术参数，`codec` 为空时按文件类型命名
+fn read_audio_properties(tagged_file: &TaggedFile, codec: Option<String>) -> AudioProperties {
+    let properties = tagged_file.properties();
+    let codec = codec.unwrap_or_else(|| codec_name(&tagged_file.file_type()));
+
+    AudioProperties {
+        lossless: is_lossless_codec(&codec),
+        codec: Some(codec),
+        overall_bitrate: properties.overall_bitrate().filter(|b| *b > 0),
+        audio_bitrate: properties.audio_bitrate().filter(|b| *b > 0),
+        sample_rate: properties.sample_rate().filter(|r| *r > 0),
+        bit_depth: properties.bit_depth().filter(|d| *d > 0),
+        channels: properties.channels().filter(|c| *c > 0),
+        duration_ms: properties.duration().as_millis() as u64,
+    }
+}
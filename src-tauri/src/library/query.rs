@@ -0,0 +1,799 @@
+use super::*;
+use crate::video::SortDirection;
+use std::cmp::Ordering;
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPage<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl<T> QueryPage<T> {
+    /// 从已过滤排序的完整结果中截取一页
+    pub fn from_sorted(items: Vec<T>, offset: usize, limit: Option<usize>) -> Self {
+        let total = items.len();
+        let items = items
+            .into_iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+
+        Self {
+            items,
+            total,
+            offset,
+            limit,
+        }
+    }
+}
+
+/// 曲目过滤条件，所有条件同时满足才会匹配
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackFilter {
+    /// 在标题、艺术家、专辑和路径中模糊匹配
+    pub text: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+    pub min_year: Option<u32>,
+    pub max_year: Option<u32>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    /// 文件扩展名，如 "flac"、"mp3"
+    pub formats: Vec<String>,
+    /// 编码格式，如 "AAC"、"ALAC"
+    pub codecs: Vec<String>,
+    pub lossless: Option<bool>,
+    /// 比特率范围（kbps）
+    pub min_bitrate: Option<u32>,
+    pub max_bitrate: Option<u32>,
+    /// 采样率范围（Hz）
+    pub min_sample_rate: Option<u32>,
+    pub max_sample_rate: Option<u32>,
+    pub min_bit_depth: Option<u8>,
+    pub max_bit_depth: Option<u8>,
+    pub channels: Option<u8>,
+    /// 评分范围（半星为单位，0-10），未评分的曲目按0处理
+    pub min_rating: Option<u8>,
+    pub max_rating: Option<u8>,
+    pub loved: Option<bool>,
+    pub min_play_count: Option<u32>,
+    pub max_play_count: Option<u32>,
+}
+
+impl TrackFilter {
+    pub fn matches(&self, track: &LibraryTrack) -> bool {
+        if let Some(ref text) = self.text {
+            let text = normalize::normalize_text(text);
+            let found = [&track.title, &track.artist, &track.album, &track.file_path]
+                .iter()
+                .any(|field| normalize::normalize_text(field).contains(&text));
+            if !found {
+                return false;
+            }
+        }
+
+        let mut artists = track.artist_names();
+        artists.push(&track.artist);
+        let mut genres = track.genre_names();
+        genres.extend(track.genre.as_deref());
+        if !matches_any(self.artist.as_deref(), &artists)
+            || !matches_exact(self.album.as_deref(), Some(&track.album))
+            || !matches_any(self.genre.as_deref(), &genres)
+        {
+            return false;
+        }
+
+        if !in_range(track.year, self.min_year, self.max_year) {
+            return false;
+        }
+
+        if self.min_duration.is_some_and(|min| track.duration < min)
+            || self.max_duration.is_some_and(|max| track.duration > max)
+        {
+            return false;
+        }
+
+        if !self.formats.is_empty() {
+            let format = track.format();
+            if !self.formats.iter().any(|f| f.eq_ignore_ascii_case(&format)) {
+                return false;
+            }
+        }
+
+        let rating = track.rating.unwrap_or(0);
+        if !in_range(Some(rating), self.min_rating, self.max_rating)
+            || self.loved.is_some_and(|loved| track.loved != loved)
+            || !in_range(
+                Some(track.play_count),
+                self.min_play_count,
+                self.max_play_count,
+            )
+        {
+            return false;
+        }
+
+        let audio = &track.audio;
+        if !self.codecs.is_empty() {
+            let Some(codec) = audio.codec.as_deref() else {
+                return false;
+            };
+            if !self.codecs.iter().any(|c| c.eq_ignore_ascii_case(codec)) {
+                return false;
+            }
+        }
+
+        if self
+            .lossless
+            .is_some_and(|lossless| audio.lossless != lossless)
+            || self
+                .channels
+                .is_some_and(|channels| audio.channels != Some(channels))
+        {
+            return false;
+        }
+
+        in_range(audio.bitrate(), self.min_bitrate, self.max_bitrate)
+            && in_range(
+                audio.sample_rate,
+                self.min_sample_rate,
+                self.max_sample_rate,
+            )
+            && in_range(audio.bit_depth, self.min_bit_depth, self.max_bit_depth)
+    }
+}
+
+// 范围过滤（含边界），设置了范围但值缺失时不匹配
+fn in_range<T: PartialOrd>(value: Option<T>, min: Option<T>, max: Option<T>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    value.is_some_and(|value| {
+        !min.is_some_and(|min| value < min) && !max.is_some_and(|max| value > max)
+    })
+}
+
+// 规范化后的精确匹配（忽略大小写、繁简和全半角），未设置条件时总是匹配
+fn matches_exact(expected: Option<&str>, actual: Option<&str>) -> bool {
+    match expected {
+        Some(expected) => actual.is_some_and(|actual| normalize::normalized_eq(actual, expected)),
+        None => true,
+    }
+}
+
+// 多值字段与其中任一值（或合并后的完整值）相同即匹配
+fn matches_any(expected: Option<&str>, values: &[&str]) -> bool {
+    match expected {
+        Some(expected) => values
+            .iter()
+            .any(|value| normalize::normalized_eq(value, expected)),
+        None => true,
+    }
+}
+
+/// 曲目排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TrackSortField {
+    Title,
+    Artist,
+    Album,
+    Genre,
+    Year,
+    Duration,
+    Format,
+    FilePath,
+    FileSize,
+    ModifiedAt,
+    Codec,
+    Bitrate,
+    SampleRate,
+    BitDepth,
+    Rating,
+    Loved,
+    PlayCount,
+    SkipCount,
+    LastPlayed,
+    DateAdded,
+}
+
+/// 排序键，多个键按顺序依次比较
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackSortKey {
+    pub field: TrackSortField,
+    pub direction: SortDirection,
+}
+
+// 文本排序字段的比较值，有排序名标签时优先使用
+fn sort_text(track: &LibraryTrack, field: TrackSortField) -> &str {
+    let sort_names = &track.sort_names;
+    match field {
+        TrackSortField::Artist => sort_names.artist.as_deref().unwrap_or(&track.artist),
+        TrackSortField::Album => sort_names.album.as_deref().unwrap_or(&track.album),
+        TrackSortField::Genre => track.genre.as_deref().unwrap_or(""),
+        _ => sort_names.title.as_deref().unwrap_or(&track.title),
+    }
+}
+
+pub fn compare_tracks(a: &LibraryTrack, b: &LibraryTrack, field: TrackSortField) -> Ordering {
+    match field {
+        TrackSortField::Title
+        | TrackSortField::Artist
+        | TrackSortField::Album
+        | TrackSortField::Genre => {
+            pinyin::compare_collated(sort_text(a, field), sort_text(b, field))
+        }
+        TrackSortField::Year => a.year.cmp(&b.year),
+        TrackSortField::Duration => a
+            .duration
+            .partial_cmp(&b.duration)
+            .unwrap_or(Ordering::Equal),
+        TrackSortField::Format => a.format().cmp(&b.format()),
+        TrackSortField::FilePath => a.file_path.cmp(&b.file_path),
+        TrackSortField::FileSize => a.file_size.cmp(&b.file_size),
+        TrackSortField::ModifiedAt => a.modified_at.cmp(&b.modified_at),
+        TrackSortField::Codec => a.audio.codec.cmp(&b.audio.codec),
+        TrackSortField::Bitrate => a.audio.bitrate().cmp(&b.audio.bitrate()),
+        TrackSortField::SampleRate => a.audio.sample_rate.cmp(&b.audio.sample_rate),
+        TrackSortField::BitDepth => a.audio.bit_depth.cmp(&b.audio.bit_depth),
+        TrackSortField::Rating => a.rating.cmp(&b.rating),
+        TrackSortField::Loved => a.loved.cmp(&b.loved),
+        TrackSortField::PlayCount => a.play_count.cmp(&b.play_count),
+        TrackSortField::SkipCount => a.skip_count.cmp(&b.skip_count),
+        TrackSortField::LastPlayed => a.last_played.cmp(&b.last_played),
+        TrackSortField::DateAdded => a.added_time().cmp(&b.added_time()),
+    }
+}
+
+fn is_text_field(field: TrackSortField) -> bool {
+    matches!(
+        field,
+        TrackSortField::Title
+            | TrackSortField::Artist
+            | TrackSortField::Album
+            | TrackSortField::Genre
+    )
+}
+
+/// 按多个排序键排序，`track_of` 取出元素对应的曲目
+///
+/// 文本字段的拼音排序键每个元素只生成一次，不在每次比较时重新计算
+pub fn sort_by_track_keys<T>(
+    items: &mut Vec<T>,
+    keys: &[TrackSortKey],
+    track_of: impl Fn(&T) -> &LibraryTrack,
+) {
+    let mut keyed: Vec<_> = items
+        .drain(..)
+        .map(|item| {
+            let track = track_of(&item);
+            let text_keys: Vec<_> = keys
+                .iter()
+                .map(|key| {
+                    is_text_field(key.field).then(|| pinyin::collated(sort_text(track, key.field)))
+                })
+                .collect();
+            (text_keys, item)
+        })
+        .collect();
+
+    keyed.sort_by(|(a_keys, a), (b_keys, b)| {
+        let (a, b) = (track_of(a), track_of(b));
+        keys.iter()
+            .zip(a_keys.iter().zip(b_keys))
+            .map(|(key, text_keys)| {
+                let ordering = match text_keys {
+                    (Some(a_key), Some(b_key)) => a_key.cmp(b_key),
+                    _ => compare_tracks(a, b, key.field),
+                };
+                match key.direction {
+                    SortDirection::Ascending => ordering,
+                    SortDirection::Descending => ordering.reverse(),
+                }
+            })
+            .find(|ordering| *ordering != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
+
+/// 按多个排序键排序曲目
+pub fn sort_tracks(tracks: &mut Vec<LibraryTrack>, keys: &[TrackSortKey]) {
+    sort_by_track_keys(tracks, keys, |track| track);
+}
+
+/// 曲目查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackQuery {
+    pub filter: TrackFilter,
+    pub sort: Vec<TrackSortKey>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+// 过滤并排序，返回匹配曲目在 `tracks` 中的下标
+fn matched_indices(
+    tracks: &[LibraryTrack],
+    filter: &TrackFilter,
+    sort: &[TrackSortKey],
+) -> Vec<usize> {
+    let mut matched: Vec<(usize, &LibraryTrack)> = tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, track)| filter.matches(track))
+        .collect();
+    sort_by_track_keys(&mut matched, sort, |(_, track)| track);
+    matched.into_iter().map(|(index, _)| index).collect()
+}
+
+// 按下标截取一页曲目
+fn page_of(
+    tracks: &[LibraryTrack],
+    matched: &[usize],
+    offset: usize,
+    limit: Option<usize>,
+) -> QueryPage<LibraryTrack> {
+    QueryPage {
+        items: matched
+            .iter()
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|&index| tracks[index].clone())
+            .collect(),
+        total: matched.len(),
+        offset,
+        limit,
+    }
+}
+
+// 索引分组默认按标题
+fn index_sort(query: &TrackQuery) -> Vec<TrackSortKey> {
+    let mut sort = query.sort.clone();
+    if sort.is_empty() {
+        sort.push(TrackSortKey {
+            field: TrackSortField::Title,
+            direction: SortDirection::Ascending,
+        });
+    }
+    sort
+}
+
+fn groups_of(
+    tracks: &[LibraryTrack],
+    matched: &[usize],
+    sort: &[TrackSortKey],
+) -> Vec<pinyin::IndexGroup> {
+    let field = sort[0].field;
+    pinyin::index_groups(
+        matched
+            .iter()
+            .map(|&index| sort_text(&tracks[index], field)),
+    )
+}
+
+/// 分页查询的内存缓存：音乐库快照和最近一次查询的过滤排序结果
+///
+/// 翻页和获取索引分组时不再重新读取、过滤和排序整个音乐库，保存音乐库时清空
+#[derive(Default)]
+pub struct QueryCache {
+    library: Option<Arc<MusicLibrary>>,
+    // 最近一次查询的条件（不含分页）和匹配曲目的下标
+    last: Option<(String, Arc<Vec<usize>>)>,
+}
+
+pub type QueryCacheState = Arc<std::sync::Mutex<QueryCache>>;
+
+impl QueryCache {
+    // 没有快照时用 `load` 读取音乐库，条件与上次相同时直接复用上次的结果
+    fn matched(
+        &mut self,
+        load: impl FnOnce() -> Result<MusicLibrary, String>,
+        filter: &TrackFilter,
+        sort: &[TrackSortKey],
+    ) -> Result<(Arc<MusicLibrary>, Arc<Vec<usize>>), String> {
+        let library = match &self.library {
+            Some(library) => library.clone(),
+            None => {
+                let library = Arc::new(load()?);
+                self.library = Some(library.clone());
+                library
+            }
+        };
+
+        let key = serde_json::to_string(&(filter, sort)).map_err(|e| e.to_string())?;
+        if let Some((last_key, matched)) = &self.last {
+            if *last_key == key {
+                return Ok((library, matched.clone()));
+            }
+        }
+        let matched = Arc::new(matched_indices(&library.tracks, filter, sort));
+        self.last = Some((key, matched.clone()));
+        Ok((library, matched))
+    }
+}
+
+/// 音乐库文件变化后清空查询缓存
+pub fn invalidate_query_cache(app: &AppHandle) {
+    if let Ok(mut cache) = app.state::<QueryCacheState>().lock() {
+        *cache = QueryCache::default();
+    }
+}
+
+/// 分页查询音乐库
+#[tauri::command]
+pub async fn query_tracks(
+    app: AppHandle,
+    cache: tauri::State<'_, QueryCacheState>,
+    query: TrackQuery,
+) -> Result<QueryPage<LibraryTrack>, String> {
+    let mut cache = cache.lock().map_err(|e| e.to_string())?;
+    let (library, matched) =
+        cache.matched(|| load_library_from_file(&app), &query.filter, &query.sort)?;
+    Ok(page_of(
+        &library.tracks,
+        &matched,
+        query.offset,
+        query.limit,
+    ))
+}
+
+/// 获取查询结果的 A–Z 索引分组，用于列表侧边栏快速跳转
+#[tauri::command]
+pub async fn get_track_index_groups(
+    app: AppHandle,
+    cache: tauri::State<'_, QueryCacheState>,
+    query: TrackQuery,
+) -> Result<Vec<pinyin::IndexGroup>, String> {
+    let sort = index_sort(&query);
+    let mut cache = cache.lock().map_err(|e| e.to_string())?;
+    let (library, matched) =
+        cache.matched(|| load_library_from_file(&app), &query.filter, &sort)?;
+    Ok(groups_of(&library.tracks, &matched, &sort))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn query_library_tracks(
+        tracks: &[LibraryTrack],
+        query: &TrackQuery,
+    ) -> QueryPage<LibraryTrack> {
+        let matched = matched_indices(tracks, &query.filter, &query.sort);
+        page_of(tracks, &matched, query.offset, query.limit)
+    }
+
+    fn query_index_groups(tracks: &[LibraryTrack], query: &TrackQuery) -> Vec<pinyin::IndexGroup> {
+        let sort = index_sort(query);
+        let matched = matched_indices(tracks, &query.filter, &sort);
+        groups_of(tracks, &matched, &sort)
+    }
+
+    fn track(
+        title: &str,
+        artist: &str,
+        year: Option<u32>,
+        duration: f64,
+        path: &str,
+    ) -> LibraryTrack {
+        test_support::track(title)
+            .artist(artist)
+            .album("Album")
+            .genre("Rock")
+            .year(year)
+            .duration(duration)
+            .path(path)
+            .build()
+    }
+
+    fn sample_tracks() -> Vec<LibraryTrack> {
+        vec![
+            track(
+                "Yesterday",
+                "The Beatles",
+                Some(1965),
+                125.0,
+                "/music/a.mp3",
+            ),
+            track("Help", "The Beatles", Some(1965), 140.0, "/music/b.flac"),
+            track(
+                "Hey Jude",
+                "The Beatles",
+                Some(1968),
+                431.0,
+                "/music/c.flac",
+            ),
+            track("Angie", "The Rolling Stones", None, 272.0, "/music/d.mp3"),
+        ]
+    }
+
+    #[test]
+    fn test_filter_tracks() {
+        let tracks = sample_tracks();
+
+        let query = TrackQuery {
+            filter: TrackFilter {
+                artist: Some("the beatles".to_string()),
+                min_year: Some(1966),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].title, "Hey Jude");
+
+        let query = TrackQuery {
+            filter: TrackFilter {
+                artist: Some("ＴＨＥ ＢＥＡＴＬＥＳ".to_string()),
+                formats: vec!["FLAC".to_string()],
+                max_duration: Some(200.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].title, "Help");
+    }
+
+    #[test]
+    fn test_filter_multi_value_artist_and_genre() {
+        let mut duet = track("Duet", "Jay Chou; Lara", None, 200.0, "/music/e.mp3");
+        duet.artists = vec!["Jay Chou".to_string(), "Lara".to_string()];
+        duet.genres = vec!["Pop".to_string(), "Mandopop".to_string()];
+        let tracks = vec![duet];
+
+        for filter in [
+            TrackFilter {
+                artist: Some("lara".to_string()),
+                ..Default::default()
+            },
+            TrackFilter {
+                artist: Some("Jay Chou; Lara".to_string()),
+                ..Default::default()
+            },
+            TrackFilter {
+                genre: Some("mandopop".to_string()),
+                ..Default::default()
+            },
+        ] {
+            let query = TrackQuery {
+                filter,
+                ..Default::default()
+            };
+            assert_eq!(query_library_tracks(&tracks, &query).total, 1);
+        }
+    }
+
+    #[test]
+    fn test_multi_key_sort_and_pagination() {
+        let tracks = sample_tracks();
+
+        let query = TrackQuery {
+            sort: vec![
+                TrackSortKey {
+                    field: TrackSortField::Year,
+                    direction: SortDirection::Descending,
+                },
+                TrackSortKey {
+                    field: TrackSortField::Title,
+                    direction: SortDirection::Ascending,
+                },
+            ],
+            offset: 1,
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 4);
+        let titles: Vec<&str> = page.items.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Help", "Yesterday"]);
+    }
+
+    #[test]
+    fn test_pinyin_sort_and_index_groups() {
+        let mut tracks = sample_tracks();
+        tracks.push(track("晴天", "周杰伦", Some(2003), 269.0, "/music/e.flac"));
+        tracks.push(track("十年", "陈奕迅", Some(2003), 205.0, "/music/f.flac"));
+
+        let query = TrackQuery {
+            sort: vec![TrackSortKey {
+                field: TrackSortField::Title,
+                direction: SortDirection::Ascending,
+            }],
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        let titles: Vec<&str> = page.items.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Angie", "Help", "Hey Jude", "晴天", "十年", "Yesterday"]
+        );
+
+        let groups = query_index_groups(&tracks, &query);
+        let letters: Vec<char> = groups.iter().map(|g| g.letter).collect();
+        assert_eq!(letters, vec!['A', 'H', 'Q', 'S', 'Y']);
+        assert_eq!(groups[1].count, 2);
+    }
+
+    #[test]
+    fn test_filter_audio_properties() {
+        let mut tracks = sample_tracks();
+        let properties = [
+            ("MP3", false, 128, 44100, None),
+            ("MP3", false, 320, 44100, None),
+            ("FLAC", true, 2300, 96000, Some(24)),
+            ("FLAC", true, 900, 44100, Some(16)),
+        ];
+        for (track, (codec, lossless, bitrate, sample_rate, bit_depth)) in
+            tracks.iter_mut().zip(properties)
+        {
+            track.audio = AudioProperties {
+                codec: Some(codec.to_string()),
+                lossless,
+                audio_bitrate: Some(bitrate),
+                sample_rate: Some(sample_rate),
+                bit_depth,
+                ..Default::default()
+            };
+        }
+
+        // 192kbps以下的有损文件
+        let query = TrackQuery {
+            filter: TrackFilter {
+                lossless: Some(false),
+                max_bitrate: Some(191),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].title, "Yesterday");
+
+        // 24bit/96kHz 高解析度
+        let query = TrackQuery {
+            filter: TrackFilter {
+                codecs: vec!["flac".to_string()],
+                min_bit_depth: Some(24),
+                min_sample_rate: Some(96000),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].title, "Hey Jude");
+    }
+
+    #[test]
+    fn test_sort_names_take_precedence() {
+        let mut tracks = sample_tracks();
+        tracks[0].sort_names.artist = Some("Beatles, The".to_string());
+        tracks[1].sort_names.artist = Some("Beatles, The".to_string());
+        tracks[2].sort_names.artist = Some("Beatles, The".to_string());
+        tracks[3].sort_names.artist = Some("Rolling Stones, The".to_string());
+
+        let query = TrackQuery {
+            sort: vec![TrackSortKey {
+                field: TrackSortField::Artist,
+                direction: SortDirection::Ascending,
+            }],
+            ..Default::default()
+        };
+        let groups = query_index_groups(&tracks, &query);
+        let letters: Vec<char> = groups.iter().map(|g| g.letter).collect();
+        assert_eq!(letters, vec!['B', 'R']);
+    }
+
+    #[test]
+    fn test_filter_and_sort_by_rating() {
+        let mut tracks = sample_tracks();
+        tracks[0].rating = Some(9);
+        tracks[0].play_count = 3;
+        tracks[1].rating = Some(6);
+        tracks[1].loved = true;
+        tracks[1].play_count = 20;
+        tracks[2].play_count = 8;
+
+        // 4星及以上
+        let query = TrackQuery {
+            filter: TrackFilter {
+                min_rating: Some(8),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].title, "Yesterday");
+
+        // 未评分的曲目
+        let query = TrackQuery {
+            filter: TrackFilter {
+                max_rating: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(query_library_tracks(&tracks, &query).total, 2);
+
+        let query = TrackQuery {
+            filter: TrackFilter {
+                loved: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(query_library_tracks(&tracks, &query).items[0].title, "Help");
+
+        let query = TrackQuery {
+            filter: TrackFilter {
+                min_play_count: Some(5),
+                ..Default::default()
+            },
+            sort: vec![TrackSortKey {
+                field: TrackSortField::PlayCount,
+                direction: SortDirection::Descending,
+            }],
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        let titles: Vec<&str> = page.items.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Help", "Hey Jude"]);
+
+        let query = TrackQuery {
+            sort: vec![TrackSortKey {
+                field: TrackSortField::Rating,
+                direction: SortDirection::Descending,
+            }],
+            ..Default::default()
+        };
+        let page = query_library_tracks(&tracks, &query);
+        assert_eq!(page.items[0].title, "Yesterday");
+        assert_eq!(page.items[1].title, "Help");
+    }
+
+    #[test]
+    fn test_query_cache_reuses_snapshot_and_results() {
+        let mut library = MusicLibrary::new();
+        library.tracks = sample_tracks();
+        let mut cache = QueryCache::default();
+        let sort = vec![TrackSortKey {
+            field: TrackSortField::Title,
+            direction: SortDirection::Ascending,
+        }];
+
+        let (_, first) = cache
+            .matched(|| Ok(library.clone()), &TrackFilter::default(), &sort)
+            .unwrap();
+        assert_eq!(first.len(), 4);
+
+        // 快照已缓存，不再读取音乐库；相同条件复用排序结果
+        let (snapshot, second) = cache
+            .matched(
+                || Err("not loaded".to_string()),
+                &TrackFilter::default(),
+                &sort,
+            )
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(snapshot.tracks[second[0]].title, "Angie");
+
+        let filter = TrackFilter {
+            min_year: Some(1966),
+            ..Default::default()
+        };
+        let (_, filtered) = cache
+            .matched(|| Err("not loaded".to_string()), &filter, &sort)
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+}
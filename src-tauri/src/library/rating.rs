@@ -0,0 +1,490 @@
+use super::*;
+use lofty::id3::v2::{Frame, Id3v2Tag, PopularimeterFrame};
+use lofty::tag::{ItemValue, Tag, TagItem, TagType};
+
+/// 最高评分（半星为单位，即5星）
+pub const MAX_RATING: u8 = 10;
+
+// POPM 帧使用的邮箱标识，与 Windows Media Player 一致以便其他播放器读取
+const POPM_EMAIL: &str = "Windows Media Player 9 Series";
+// 半星评分对应的 POPM 数值（0.5星到5星），与 MediaMonkey/WMP 的约定一致
+const POPM_VALUES: [u8; 10] = [13, 1, 54, 64, 118, 128, 186, 196, 242, 255];
+
+const FMPS_RATING: &str = "FMPS_RATING";
+const FMPS_PLAYCOUNT: &str = "FMPS_PLAYCOUNT";
+const VORBIS_RATING: &str = "RATING";
+const MP4_RATING: &str = "rate";
+
+/// 将星级（0-5，步长0.5）转换为半星评分，0星或 `None` 表示未评分
+pub fn stars_to_rating(stars: Option<f32>) -> Result<Option<u8>, String> {
+    let Some(stars) = stars else {
+        return Ok(None);
+    };
+    let half_stars = stars * 2.0;
+    if !(0.0..=MAX_RATING as f32).contains(&half_stars) || half_stars.fract() != 0.0 {
+        return Err(format!(
+            "Invalid rating: {} (expected 0-5 in steps of 0.5)",
+            stars
+        ));
+    }
+    Ok(Some(half_stars as u8).filter(|rating| *rating > 0))
+}
+
+// POPM 数值转换为半星评分，非标准数值按比例换算
+fn rating_from_popm(value: u8) -> Option<u8> {
+    if value == 0 {
+        return None;
+    }
+    let rating = match POPM_VALUES.iter().position(|v| *v == value) {
+        Some(index) => index as u8 + 1,
+        None => ((value as f32 / 255.0 * MAX_RATING as f32).round() as u8).max(1),
+    };
+    Some(rating)
+}
+
+fn popm_from_rating(rating: Option<u8>) -> u8 {
+    match rating {
+        Some(rating) if rating > 0 => POPM_VALUES[(rating.min(MAX_RATING) - 1) as usize],
+        _ => 0,
+    }
+}
+
+// lofty 把 POPM 帧保存在通用标签的附属标签中，无法通过 ItemKey 读写，
+// 需转换为 Id3v2Tag 处理；有多个 POPM 帧时优先使用本程序写入的
+fn read_popm(tag: &Tag) -> Option<(u8, u32)> {
+    let id3v2 = Id3v2Tag::from(tag.clone());
+    let frames: Vec<&PopularimeterFrame> = (&id3v2)
+        .into_iter()
+        .filter_map(|frame| match frame {
+            Frame::Popularimeter(popm) => Some(popm),
+            _ => None,
+        })
+        .collect();
+    let popm = frames
+        .iter()
+        .find(|popm| popm.email == POPM_EMAIL)
+        .or_else(|| frames.first())?;
+    Some((popm.rating, popm.counter.min(u32::MAX as u64) as u32))
+}
+
+// 替换本程序写入的 POPM 帧，其他播放器的帧保持不变
+fn write_popm(tag: &mut Tag, rating: u8, counter: u32) {
+    let mut id3v2 = Id3v2Tag::from(std::mem::replace(tag, Tag::new(TagType::Id3v2)));
+    id3v2.retain(|frame| !matches!(frame, Frame::Popularimeter(popm) if popm.email == POPM_EMAIL));
+    if rating != 0 || counter != 0 {
+        id3v2.insert(Frame::Popularimeter(PopularimeterFrame::new(
+            POPM_EMAIL.to_string(),
+            rating,
+            counter as u64,
+        )));
+    }
+    *tag = Tag::from(id3v2);
+}
+
+// 按标签格式映射键名：Vorbis 的 RATING 和 MP4 的 rate 由 lofty 映射为 Popularimeter，
+// 没有映射的键（如 FMPS_RATING）保持为 Unknown
+fn item_key(tag: &Tag, key: &str) -> ItemKey {
+    ItemKey::from_key(tag.tag_type(), key)
+}
+
+fn read_number(tag: &Tag, key: &str) -> Option<f64> {
+    read_string(tag, item_key(tag, key))
+        .and_then(|s| s.replace(',', ".").parse::<f64>().ok())
+        .filter(|value| value.is_finite() && *value >= 0.0)
+}
+
+// 百分制评分（0-100）转换为半星评分
+fn rating_from_percent(value: f64) -> Option<u8> {
+    let rating = (value.min(100.0) / 10.0).round() as u8;
+    Some(rating).filter(|rating| *rating > 0)
+}
+
+/// 从标签读取评分（半星为单位）
+///
+/// ID3v2 读取 POPM，Vorbis 优先读取 FMPS_RATING（0-1），其次 RATING（0-5 或 0-100），
+/// MP4 读取 rate 原子（0-100），APE 读取 RATING（0-100）
+pub fn read_rating(tag: &Tag) -> Option<u8> {
+    match tag.tag_type() {
+        TagType::Id3v2 => read_popm(tag).and_then(|(rating, _)| rating_from_popm(rating)),
+        TagType::VorbisComments => {
+            if let Some(value) = read_number(tag, FMPS_RATING) {
+                return rating_from_percent(value.min(1.0) * 100.0);
+            }
+            read_number(tag, VORBIS_RATING).and_then(|value| {
+                if value <= 5.0 {
+                    rating_from_percent(value * 20.0)
+                } else {
+                    rating_from_percent(value)
+                }
+            })
+        }
+        TagType::Mp4Ilst => read_number(tag, MP4_RATING).and_then(rating_from_percent),
+        TagType::Ape => read_number(tag, VORBIS_RATING).and_then(rating_from_percent),
+        _ => None,
+    }
+}
+
+/// 从标签读取播放次数：ID3v2 的 POPM 计数，Vorbis 和 APE 的 FMPS_PLAYCOUNT
+pub fn read_play_count(tag: &Tag) -> Option<u32> {
+    let count = match tag.tag_type() {
+        TagType::Id3v2 => read_popm(tag).map(|(_, counter)| counter),
+        TagType::VorbisComments | TagType::Ape => {
+            read_number(tag, FMPS_PLAYCOUNT).map(|count| count.min(u32::MAX as f64) as u32)
+        }
+        _ => None,
+    };
+    count.filter(|count| *count > 0)
+}
+
+// 部分键没有对应的通用 ItemKey，需跳过键名映射检查直接写入
+fn set_or_remove(tag: &mut Tag, key: &str, value: Option<String>) {
+    let key = item_key(tag, key);
+    match value {
+        Some(value) => {
+            tag.insert_unchecked(TagItem::new(key, ItemValue::Text(value)));
+        }
+        None => {
+            tag.remove_key(&key);
+        }
+    }
+}
+
+/// 将评分写入标签，`Clear` 时移除评分
+pub fn apply_rating(tag: &mut Tag, edit: &FieldEdit<u8>) {
+    let rating = match edit {
+        FieldEdit::Keep => return,
+        FieldEdit::Set(rating) => Some(*rating).filter(|rating| *rating > 0),
+        FieldEdit::Clear => None,
+    };
+    let percent = rating.map(|rating| rating.min(MAX_RATING) as u32 * 10);
+
+    match tag.tag_type() {
+        TagType::Id3v2 => {
+            let counter = read_popm(tag).map(|(_, counter)| counter).unwrap_or(0);
+            write_popm(tag, popm_from_rating(rating), counter);
+        }
+        TagType::VorbisComments => {
+            set_or_remove(
+                tag,
+                FMPS_RATING,
+                percent.map(|p| format!("{}", p as f32 / 100.0)),
+            );
+            set_or_remove(tag, VORBIS_RATING, percent.map(|p| p.to_string()));
+        }
+        TagType::Mp4Ilst => set_or_remove(tag, MP4_RATING, percent.map(|p| p.to_string())),
+        TagType::Ape => set_or_remove(tag, VORBIS_RATING, percent.map(|p| p.to_string())),
+        _ => {}
+    }
+}
+
+/// 将播放次数写入标签，MP4 没有通用的播放次数字段，不做修改
+pub fn apply_play_count(tag: &mut Tag, edit: &FieldEdit<u32>) {
+    let count = match edit {
+        FieldEdit::Keep => return,
+        FieldEdit::Set(count) => Some(*count).filter(|count| *count > 0),
+        FieldEdit::Clear => None,
+    };
+
+    match tag.tag_type() {
+        TagType::Id3v2 => {
+            let rating = read_popm(tag).map(|(rating, _)| rating).unwrap_or(0);
+            write_popm(tag, rating, count.unwrap_or(0));
+        }
+        TagType::VorbisComments | TagType::Ape => {
+            set_or_remove(tag, FMPS_PLAYCOUNT, count.map(|c| c.to_string()));
+        }
+        _ => {}
+    }
+}
+
+// 保存音乐库并更新搜索索引中对应的曲目
+fn save_track_changes(
+    app: &AppHandle,
+    library: &mut MusicLibrary,
+    indices: &[usize],
+) -> Result<Vec<LibraryTrack>, String> {
+    library.last_updated = chrono::Utc::now().to_rfc3339();
+    save_library_to_file(app, library)?;
+    let changed: Vec<String> = indices
+        .iter()
+        .map(|&index| library.tracks[index].id.clone())
+        .collect();
+    search::update_search_index(app, library, &changed, &[]);
+    Ok(indices
+        .iter()
+        .map(|&index| library.tracks[index].clone())
+        .collect())
+}
+
+/// 设置曲目评分（0-5星，步长0.5，`None` 或0清除评分）
+///
+/// `write_to_file` 为 true 时同时写入文件标签，写入前先检查所有文件
+#[tauri::command]
+pub async fn set_track_rating(
+    app: AppHandle,
+    track_ids: Vec<String>,
+    rating: Option<f32>,
+    write_to_file: bool,
+) -> Result<editor::TagEditReport, String> {
+    let rating = stars_to_rating(rating)?;
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let indices = editor::track_indices(&library, &track_ids)?;
+    if indices.is_empty() {
+        return Ok(editor::TagEditReport::default());
+    }
+
+    // 修改前的条目，写入文件时用于生成撤销记录
+    let previous: HashMap<String, LibraryTrack> = indices
+        .iter()
+        .map(|&index| {
+            (
+                library.tracks[index].id.clone(),
+                library.tracks[index].clone(),
+            )
+        })
+        .collect();
+    for &index in &indices {
+        library.tracks[index].rating = rating;
+    }
+
+    let mut report = editor::TagEditReport::default();
+    let edit = TagEdit {
+        rating: rating.map(FieldEdit::Set).unwrap_or(FieldEdit::Clear),
+        ..Default::default()
+    };
+    let mut written = Vec::new();
+    if write_to_file {
+        let edits = indices.iter().map(|&index| (index, edit.clone())).collect();
+        let result = editor::write_tag_edits(&mut library, edits, None).await?;
+        report.errors = result.errors;
+        written = result.updated;
+    }
+    report.updated = save_track_changes(&app, &mut library, &indices)?;
+
+    // 只更新音乐库时不记录，撤销会写入文件
+    let edits = written
+        .iter()
+        .filter_map(|track| previous.get(&track.id))
+        .map(|track| (track, &edit))
+        .collect();
+    editor::record_tag_edits(&app, "Rate tracks", edits)?;
+
+    Ok(report)
+}
+
+/// 标记或取消标记喜爱的曲目
+#[tauri::command]
+pub async fn set_track_loved(
+    app: AppHandle,
+    track_ids: Vec<String>,
+    loved: bool,
+) -> Result<Vec<LibraryTrack>, String> {
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let indices = editor::track_indices(&library, &track_ids)?;
+    if indices.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    for &index in &indices {
+        library.tracks[index].loved = loved;
+    }
+    save_track_changes(&app, &mut library, &indices)
+}
+
+/// 记录一次播放，`skipped` 为 true 时计为跳过
+///
+/// 只更新音乐库，需要写入文件时使用 `sync_track_stats`
+#[tauri::command]
+pub async fn record_playback(
+    app: AppHandle,
+    track_id: String,
+    skipped: bool,
+) -> Result<LibraryTrack, String> {
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let indices = editor::track_indices(&library, std::slice::from_ref(&track_id))?;
+
+    let track = &mut library.tracks[indices[0]];
+    if skipped {
+        track.skip_count = track.skip_count.saturating_add(1);
+    } else {
+        track.play_count = track.play_count.saturating_add(1);
+        track.last_played = Some(chrono::Utc::now().to_rfc3339());
+    }
+
+    let mut updated = save_track_changes(&app, &mut library, &indices)?;
+    Ok(updated.remove(0))
+}
+
+/// 将评分和播放次数写入文件标签，未指定曲目时同步整个音乐库中有评分或播放记录的曲目
+#[tauri::command]
+pub async fn sync_track_stats(
+    app: AppHandle,
+    track_ids: Option<Vec<String>>,
+) -> Result<editor::TagEditReport, String> {
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let indices = match track_ids {
+        Some(ids) => editor::track_indices(&library, &ids)?,
+        None => (0..library.tracks.len())
+            .filter(|&index| {
+                let track = &library.tracks[index];
+                track.rating.is_some() || track.play_count > 0
+            })
+            .collect(),
+    };
+
+    let edits = indices
+        .iter()
+        .map(|&index| {
+            let track = &library.tracks[index];
+            let edit = TagEdit {
+                rating: track.rating.map(FieldEdit::Set).unwrap_or(FieldEdit::Clear),
+                play_count: Some(track.play_count)
+                    .filter(|count| *count > 0)
+                    .map(FieldEdit::Set)
+                    .unwrap_or(FieldEdit::Clear),
+                ..Default::default()
+            };
+            (index, edit)
+        })
+        .collect();
+    let report = editor::write_tag_edits(&mut library, edits, None).await?;
+
+    if !report.updated.is_empty() {
+        save_library_to_file(&app, &library)?;
+        let changed: Vec<String> = report.updated.iter().map(|t| t.id.clone()).collect();
+        search::update_search_index(&app, &library, &changed, &[]);
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+    use lofty::config::WriteOptions;
+    use lofty::mp4::Ilst;
+    use lofty::ogg::VorbisComments;
+
+    #[test]
+    fn test_stars_to_rating() {
+        assert_eq!(stars_to_rating(Some(3.5)), Ok(Some(7)));
+        assert_eq!(stars_to_rating(Some(5.0)), Ok(Some(MAX_RATING)));
+        assert_eq!(stars_to_rating(Some(0.0)), Ok(None));
+        assert_eq!(stars_to_rating(None), Ok(None));
+        assert!(stars_to_rating(Some(3.3)).is_err());
+        assert!(stars_to_rating(Some(5.5)).is_err());
+        assert!(stars_to_rating(Some(-1.0)).is_err());
+    }
+
+    #[test]
+    fn test_popm_round_trip() {
+        for rating in 1..=MAX_RATING {
+            assert_eq!(
+                rating_from_popm(popm_from_rating(Some(rating))),
+                Some(rating)
+            );
+        }
+        // 其他播放器写入的非标准数值按比例换算
+        assert_eq!(rating_from_popm(0), None);
+        assert_eq!(rating_from_popm(100), Some(4));
+
+        // 评分和播放次数保存在同一个 POPM 帧中，修改一个不影响另一个
+        let mut tag = Tag::new(TagType::Id3v2);
+        apply_rating(&mut tag, &FieldEdit::Set(8));
+        apply_play_count(&mut tag, &FieldEdit::Set(42));
+        assert_eq!(read_rating(&tag), Some(8));
+        assert_eq!(read_play_count(&tag), Some(42));
+        apply_rating(&mut tag, &FieldEdit::Clear);
+        assert_eq!(read_play_count(&tag), Some(42));
+    }
+
+    #[test]
+    fn test_vorbis_rating_and_play_count() {
+        let mut tag = Tag::new(TagType::VorbisComments);
+        apply_rating(&mut tag, &FieldEdit::Set(7));
+        apply_play_count(&mut tag, &FieldEdit::Set(12));
+        assert_eq!(read_rating(&tag), Some(7));
+        assert_eq!(read_play_count(&tag), Some(12));
+
+        // 只有0-5星 RATING 的文件
+        let mut tag = Tag::new(TagType::VorbisComments);
+        set_or_remove(&mut tag, VORBIS_RATING, Some("4".to_string()));
+        assert_eq!(read_rating(&tag), Some(8));
+
+        apply_rating(&mut tag, &FieldEdit::Clear);
+        assert_eq!(read_rating(&tag), None);
+    }
+
+    #[test]
+    fn test_rating_survives_real_tag_conversion() {
+        let mut tag = Tag::new(TagType::VorbisComments);
+        apply_rating(&mut tag, &FieldEdit::Set(7));
+        apply_play_count(&mut tag, &FieldEdit::Set(12));
+
+        // 写入文件时转换为具体的标签格式，读取时再转换回来
+        let comments = VorbisComments::from(tag);
+        assert_eq!(comments.get("RATING"), Some("70"));
+        assert_eq!(comments.get("FMPS_RATING"), Some("0.7"));
+        let mut tag = Tag::from(comments);
+        assert_eq!(read_rating(&tag), Some(7));
+        assert_eq!(read_play_count(&tag), Some(12));
+
+        apply_rating(&mut tag, &FieldEdit::Clear);
+        let comments = VorbisComments::from(tag);
+        assert_eq!(comments.get("RATING"), None);
+        assert_eq!(comments.get("FMPS_RATING"), None);
+
+        let mut tag = Tag::new(TagType::Mp4Ilst);
+        apply_rating(&mut tag, &FieldEdit::Set(4));
+        let tag = Tag::from(Ilst::from(tag));
+        assert_eq!(read_rating(&tag), Some(4));
+    }
+
+    #[test]
+    fn test_mp3_rating_and_play_count_round_trip() {
+        let dir = test_support::temp_dir("popm");
+        let path = dir.join("a.mp3");
+        test_support::silent_mp3(&path);
+
+        let write = |edit: TagEdit| {
+            let tagged_file = Probe::open(&path).unwrap().read().unwrap();
+            let mut tag = tagged_file
+                .primary_tag()
+                .cloned()
+                .unwrap_or_else(|| Tag::new(TagType::Id3v2));
+            apply_rating(&mut tag, &edit.rating);
+            apply_play_count(&mut tag, &edit.play_count);
+            tag.save_to_path(&path, WriteOptions::default()).unwrap();
+        };
+        write(TagEdit {
+            rating: FieldEdit::Set(7),
+            play_count: FieldEdit::Set(12),
+            ..Default::default()
+        });
+
+        // 扫描时读取的评分和播放次数
+        let track = extract_metadata(&path.to_string_lossy()).unwrap();
+        assert_eq!(track.rating, Some(7));
+        assert_eq!(track.play_count, Some(12));
+
+        // 只修改评分时保留播放次数，反之亦然
+        write(TagEdit {
+            rating: FieldEdit::Set(10),
+            ..Default::default()
+        });
+        write(TagEdit {
+            play_count: FieldEdit::Set(13),
+            ..Default::default()
+        });
+        let track = extract_metadata(&path.to_string_lossy()).unwrap();
+        assert_eq!(track.rating, Some(10));
+        assert_eq!(track.play_count, Some(13));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
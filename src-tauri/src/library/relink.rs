@@ -0,0 +1,540 @@
+use super::*;
+use crate::history::{playlist_changes, record_operation, write_snapshot, HistoryChange};
+use std::collections::HashSet;
+
+/// 候选文件的匹配方式，按可信度从低到高
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum RelinkMatch {
+    /// 文件名相同
+    Name,
+    /// 文件大小相同
+    Size,
+    /// 文件名和大小都相同
+    NameAndSize,
+    /// 声纹相同
+    Fingerprint,
+}
+
+/// 丢失文件可能的新位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelinkCandidate {
+    pub path: String,
+    pub matched: RelinkMatch,
+    pub similarity: Option<f32>,
+}
+
+/// 文件已不存在的音乐库曲目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingTrack {
+    pub track_id: String,
+    pub path: String,
+    pub candidates: Vec<RelinkCandidate>,
+    /// 唯一的最佳候选，可直接批量重新关联
+    pub suggested: Option<String>,
+}
+
+/// 文件已不存在的播放列表条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingPlaylistEntry {
+    pub playlist_id: String,
+    pub playlist_name: String,
+    pub path: String,
+    pub candidates: Vec<RelinkCandidate>,
+    pub suggested: Option<String>,
+}
+
+/// 音乐库健康检查结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryHealthReport {
+    pub missing_tracks: Vec<MissingTrack>,
+    pub missing_playlist_entries: Vec<MissingPlaylistEntry>,
+    /// 搜索候选时扫描的文件数
+    pub scanned_files: usize,
+    pub errors: Vec<String>,
+}
+
+/// 重新关联：把指向 `from` 的条目改为 `to`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Relink {
+    pub from: String,
+    pub to: String,
+}
+
+/// 重新关联或移除的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RelinkReport {
+    pub tracks: usize,
+    pub playlist_entries: usize,
+    pub errors: Vec<String>,
+}
+
+fn lowercase_file_name(path: &Path) -> Option<String> {
+    path.file_name().map(|s| s.to_string_lossy().to_lowercase())
+}
+
+/// 按文件名和大小在候选文件中查找丢失文件，结果按可信度排序
+pub fn find_relink_candidates(
+    missing: &Path,
+    size: Option<u64>,
+    files: &[(PathBuf, u64)],
+) -> Vec<RelinkCandidate> {
+    let name = lowercase_file_name(missing);
+    let size = size.filter(|s| *s > 0);
+
+    let mut candidates: Vec<RelinkCandidate> = files
+        .iter()
+        .filter_map(|(path, file_size)| {
+            let same_name = name.is_some() && lowercase_file_name(path) == name;
+            let same_size = size == Some(*file_size);
+            let matched = match (same_name, same_size) {
+                (true, true) => RelinkMatch::NameAndSize,
+                (true, false) => RelinkMatch::Name,
+                (false, true) => RelinkMatch::Size,
+                (false, false) => return None,
+            };
+            Some(RelinkCandidate {
+                path: path.to_string_lossy().to_string(),
+                matched,
+                similarity: None,
+            })
+        })
+        .collect();
+    sort_candidates(&mut candidates);
+    candidates
+}
+
+fn sort_candidates(candidates: &mut [RelinkCandidate]) {
+    candidates.sort_by(|a, b| {
+        b.matched
+            .cmp(&a.matched)
+            .then_with(|| {
+                b.similarity
+                    .unwrap_or(0.0)
+                    .total_cmp(&a.similarity.unwrap_or(0.0))
+            })
+            .then_with(|| a.path.cmp(&b.path))
+    });
+}
+
+/// 最高可信度的候选唯一时作为建议，只有大小相同的候选不作为建议
+pub fn suggest_candidate(candidates: &[RelinkCandidate]) -> Option<String> {
+    let best = candidates
+        .first()
+        .filter(|c| c.matched != RelinkMatch::Size)?;
+    let ties = candidates
+        .iter()
+        .filter(|c| c.matched == best.matched)
+        .count();
+    (ties == 1).then(|| best.path.clone())
+}
+
+// 列出搜索目录下的所有音频文件及其大小
+async fn collect_candidate_files(
+    roots: &[String],
+    errors: &mut Vec<String>,
+) -> Vec<(PathBuf, u64)> {
+    let mut seen = HashSet::new();
+    let mut files = Vec::new();
+    for root in roots {
+        match rescan::collect_audio_files(root, &ScanJob::new()).await {
+            Ok(found) => {
+                for path in found {
+                    if !seen.insert(path.clone()) {
+                        continue;
+                    }
+                    let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    files.push((path, size));
+                }
+            }
+            Err(e) => errors.push(format!("{}: {}", root, e)),
+        }
+    }
+    files
+}
+
+// 为有缓存声纹的丢失曲目计算未入库文件的声纹并比较
+async fn add_fingerprint_candidates(
+    app: &AppHandle,
+    missing: &mut [MissingTrack],
+    unreferenced: Vec<PathBuf>,
+    errors: &mut Vec<String>,
+) {
+    let cached = duplicates::cached_fingerprints(app);
+    if unreferenced.is_empty() || !missing.iter().any(|m| cached.contains_key(&m.track_id)) {
+        return;
+    }
+
+    let mut fingerprints = Vec::new();
+    let mut results =
+        probe_in_parallel(&ScanJob::new(), unreferenced, fingerprint::fingerprint_file);
+    while let Some((path, result)) = results.next().await {
+        match result {
+            Ok(fingerprint) => fingerprints.push((path, fingerprint)),
+            Err(e) => errors.push(format!("{}: {}", path.to_string_lossy(), e)),
+        }
+    }
+
+    for entry in missing.iter_mut() {
+        let Some(original) = cached.get(&entry.track_id) else {
+            continue;
+        };
+        for (path, fingerprint) in &fingerprints {
+            let Some(similarity) = fingerprint::fingerprint_similarity(original, fingerprint)
+            else {
+                continue;
+            };
+            let path = path.to_string_lossy().to_string();
+            entry.candidates.retain(|c| c.path != path);
+            entry.candidates.push(RelinkCandidate {
+                path,
+                matched: RelinkMatch::Fingerprint,
+                similarity: Some(similarity),
+            });
+        }
+        sort_candidates(&mut entry.candidates);
+    }
+}
+
+/// 检查音乐库和播放列表中文件已不存在的条目，并在音乐库目录中查找可能的新位置
+///
+/// 未指定 `search_paths` 时搜索音乐库的扫描目录；`fingerprint` 为 true 时
+/// 对未入库的文件计算声纹，与重复检测时缓存的声纹比较
+#[tauri::command]
+pub async fn check_library_health(
+    app: AppHandle,
+    search_paths: Option<Vec<String>>,
+    fingerprint: bool,
+) -> Result<LibraryHealthReport, String> {
+    let library = load_library_from_file(&app)?;
+    let mut playlists = crate::playlist::load_playlists_from_file(&app).await?;
+    crate::playlist::TrackLinker::new(&library.tracks).link_playlists(&mut playlists);
+    let mut report = LibraryHealthReport::default();
+
+    let missing_tracks: Vec<&LibraryTrack> = library
+        .tracks
+        .iter()
+        .filter(|t| !Path::new(&t.file_path).exists())
+        .collect();
+    let mut missing_entries: Vec<(String, String, String)> = Vec::new();
+    for playlist in &playlists {
+        let mut seen = HashSet::new();
+        for track in &playlist.tracks {
+            if !Path::new(&track.file_path).exists() && seen.insert(track.file_path.clone()) {
+                missing_entries.push((
+                    playlist.id.clone(),
+                    playlist.name.clone(),
+                    track.file_path.clone(),
+                ));
+            }
+        }
+    }
+    if missing_tracks.is_empty() && missing_entries.is_empty() {
+        return Ok(report);
+    }
+
+    let roots = search_paths.unwrap_or_else(|| library.last_scanned_paths.clone());
+    let files = collect_candidate_files(&roots, &mut report.errors).await;
+    report.scanned_files = files.len();
+
+    // 播放列表条目若对应音乐库曲目，使用曲目记录的文件大小
+    let sizes: HashMap<&str, u64> = library
+        .tracks
+        .iter()
+        .map(|t| (t.file_path.as_str(), t.file_size))
+        .collect();
+
+    report.missing_tracks = missing_tracks
+        .iter()
+        .map(|track| {
+            let candidates =
+                find_relink_candidates(Path::new(&track.file_path), Some(track.file_size), &files);
+            MissingTrack {
+                track_id: track.id.clone(),
+                path: track.file_path.clone(),
+                suggested: suggest_candidate(&candidates),
+                candidates,
+            }
+        })
+        .collect();
+
+    if fingerprint {
+        let library_paths: HashSet<&str> = library
+            .tracks
+            .iter()
+            .map(|t| t.file_path.as_str())
+            .collect();
+        let unreferenced = files
+            .iter()
+            .filter(|(path, _)| !library_paths.contains(path.to_string_lossy().as_ref()))
+            .map(|(path, _)| path.clone())
+            .collect();
+        add_fingerprint_candidates(
+            &app,
+            &mut report.missing_tracks,
+            unreferenced,
+            &mut report.errors,
+        )
+        .await;
+        for entry in report.missing_tracks.iter_mut() {
+            entry.suggested = suggest_candidate(&entry.candidates);
+        }
+    }
+
+    report.missing_playlist_entries = missing_entries
+        .into_iter()
+        .map(|(playlist_id, playlist_name, path)| {
+            // 已为对应的音乐库曲目找到候选时直接沿用
+            let candidates = report
+                .missing_tracks
+                .iter()
+                .find(|m| m.path == path)
+                .map(|m| m.candidates.clone())
+                .unwrap_or_else(|| {
+                    find_relink_candidates(
+                        Path::new(&path),
+                        sizes.get(path.as_str()).copied(),
+                        &files,
+                    )
+                });
+            MissingPlaylistEntry {
+                playlist_id,
+                playlist_name,
+                suggested: suggest_candidate(&candidates),
+                candidates,
+                path,
+            }
+        })
+        .collect();
+
+    Ok(report)
+}
+
+// 音乐库中指向 `from` 的曲目改为指向 `to`，返回被关联的曲目ID和被移除的重复条目ID
+//
+// `to` 已作为另一个曲目入库时移除该条目，它的评分和播放统计合并到被关联的曲目
+fn relink_library_track(
+    library: &mut MusicLibrary,
+    from: &str,
+    to: &str,
+    fingerprint: &FileFingerprint,
+) -> (Vec<String>, Vec<String>) {
+    if !library.tracks.iter().any(|t| t.file_path == from) {
+        return (Vec::new(), Vec::new());
+    }
+
+    let mut duplicates = Vec::new();
+    library.tracks.retain(|t| {
+        if t.file_path == to {
+            duplicates.push(t.clone());
+            false
+        } else {
+            true
+        }
+    });
+
+    let mut relinked = Vec::new();
+    for track in library.tracks.iter_mut().filter(|t| t.file_path == from) {
+        let added_at = track.added_at.clone();
+        for duplicate in &duplicates {
+            track.keep_user_data(duplicate);
+        }
+        // 加入时间以原曲目为准
+        track.added_at = added_at.or(track.added_at.take());
+        track.file_path = to.to_string();
+        track.file_size = fingerprint.size;
+        track.modified_at = fingerprint.modified_at;
+        relinked.push(track.id.clone());
+    }
+
+    let removed = duplicates.into_iter().map(|t| t.id).collect();
+    (relinked, removed)
+}
+
+/// 批量重新关联丢失的文件，音乐库和所有播放列表中的路径一并更新
+///
+/// 曲目保留原ID，播放次数、评分和播放列表引用都不受影响；若新位置已作为
+/// 另一个曲目入库，该重复条目会被移除，其评分和播放统计合并到原曲目
+#[tauri::command]
+pub async fn relink_missing(app: AppHandle, relinks: Vec<Relink>) -> Result<RelinkReport, String> {
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    let mut report = RelinkReport::default();
+    let mut moves: HashMap<String, String> = HashMap::new();
+    let mut changed = Vec::new();
+    let mut removed = Vec::new();
+
+    for relink in relinks {
+        let (from, to) = (Path::new(&relink.from), Path::new(&relink.to));
+        if from.exists() {
+            report
+                .errors
+                .push(format!("{}: File still exists", relink.from));
+            continue;
+        }
+        let fingerprint = match FileFingerprint::read(to) {
+            Ok(fingerprint) if to.is_file() => fingerprint,
+            Ok(_) => {
+                report.errors.push(format!("{}: Not a file", relink.to));
+                continue;
+            }
+            Err(e) => {
+                report.errors.push(format!("{}: {}", relink.to, e));
+                continue;
+            }
+        };
+
+        let (relinked, duplicates) =
+            relink_library_track(&mut library, &relink.from, &relink.to, &fingerprint);
+        report.tracks += relinked.len();
+        changed.extend(relinked);
+        removed.extend(duplicates);
+        moves.insert(relink.from, relink.to);
+    }
+
+    if !changed.is_empty() {
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+        save_library_to_file(&app, &library)?;
+        search::update_search_index(&app, &library, &changed, &removed);
+    }
+    report.playlist_entries = crate::playlist::update_playlist_paths(&app, &moves).await?;
+
+    Ok(report)
+}
+
+/// 从音乐库和所有播放列表中移除指向这些路径的条目，仍存在的文件会被跳过
+#[tauri::command]
+pub async fn remove_missing_entries(
+    app: AppHandle,
+    paths: Vec<String>,
+) -> Result<RelinkReport, String> {
+    let mut report = RelinkReport::default();
+    let mut missing = HashSet::new();
+    for path in paths {
+        if Path::new(&path).exists() {
+            report.errors.push(format!("{}: File still exists", path));
+        } else {
+            missing.insert(path);
+        }
+    }
+
+    let _lock = lock_library(&app).await;
+    let mut library = load_library_from_file(&app)?;
+    // 移除前写入快照，音乐库和播放列表的修改作为一次操作记录，可以撤销
+    let snapshot = write_snapshot(&app, &library)?;
+    let playlists_before = crate::playlist::load_playlists_from_file(&app).await?;
+    let mut removed = Vec::new();
+    library.tracks.retain(|t| {
+        if missing.contains(&t.file_path) {
+            removed.push(t.id.clone());
+            false
+        } else {
+            true
+        }
+    });
+    if !removed.is_empty() {
+        report.tracks = removed.len();
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+        save_library_to_file(&app, &library)?;
+        search::update_search_index(&app, &library, &[], &removed);
+    }
+    report.playlist_entries = crate::playlist::remove_playlist_paths(&app, &missing).await?;
+
+    let playlists_after = crate::playlist::load_playlists_from_file(&app).await?;
+    let mut changes = Vec::new();
+    if !removed.is_empty() {
+        changes.push(HistoryChange::LibraryRemoval { track_ids: removed });
+    }
+    let edited = playlist_changes(&playlists_before, &playlists_after);
+    if !edited.is_empty() {
+        changes.push(HistoryChange::Playlists { changes: edited });
+    }
+    record_operation(&app, "Remove missing tracks", changes, Some(snapshot))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn files() -> Vec<(PathBuf, u64)> {
+        vec![
+            (PathBuf::from("/music/new/01 - Song.mp3"), 5000),
+            (PathBuf::from("/music/other/01 - SONG.mp3"), 4200),
+            (PathBuf::from("/music/other/renamed.mp3"), 5000),
+            (PathBuf::from("/music/other/unrelated.mp3"), 1234),
+        ]
+    }
+
+    #[test]
+    fn test_candidates_ranked_by_name_and_size() {
+        let candidates =
+            find_relink_candidates(Path::new("/old/01 - Song.mp3"), Some(5000), &files());
+        let matched: Vec<RelinkMatch> = candidates.iter().map(|c| c.matched).collect();
+        assert_eq!(
+            matched,
+            vec![
+                RelinkMatch::NameAndSize,
+                RelinkMatch::Size,
+                RelinkMatch::Name
+            ]
+        );
+        assert_eq!(
+            suggest_candidate(&candidates).as_deref(),
+            Some("/music/new/01 - Song.mp3")
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_candidates_not_suggested() {
+        // 播放列表条目没有文件大小，两个同名文件无法区分
+        let candidates = find_relink_candidates(Path::new("/old/01 - song.MP3"), None, &files());
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.iter().all(|c| c.matched == RelinkMatch::Name));
+        assert_eq!(suggest_candidate(&candidates), None);
+
+        assert!(find_relink_candidates(Path::new("/old/gone.mp3"), Some(0), &files()).is_empty());
+    }
+
+    #[test]
+    fn test_size_only_match_not_suggested() {
+        let candidates =
+            find_relink_candidates(Path::new("/old/another.mp3"), Some(1234), &files());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].matched, RelinkMatch::Size);
+        assert_eq!(suggest_candidate(&candidates), None);
+    }
+
+    #[test]
+    fn test_relink_onto_library_track_merges_user_data() {
+        let mut original = test_support::track("old").path("/old/song.mp3").build();
+        original.rating = Some(8);
+        original.play_count = 3;
+        original.added_at = Some("2020-01-01T00:00:00+00:00".to_string());
+        let mut duplicate = test_support::track("new").path("/new/song.mp3").build();
+        duplicate.play_count = 5;
+        duplicate.loved = true;
+        duplicate.added_at = Some("2024-01-01T00:00:00+00:00".to_string());
+        let mut library = MusicLibrary::new();
+        library.tracks = vec![original, duplicate];
+
+        let fingerprint = FileFingerprint {
+            size: 42,
+            modified_at: 1,
+        };
+        let (relinked, removed) =
+            relink_library_track(&mut library, "/old/song.mp3", "/new/song.mp3", &fingerprint);
+
+        assert_eq!(relinked, vec!["old"]);
+        assert_eq!(removed, vec!["new"]);
+        assert_eq!(library.tracks.len(), 1);
+        let track = &library.tracks[0];
+        assert_eq!(track.file_path, "/new/song.mp3");
+        assert_eq!(track.file_size, 42);
+        assert_eq!(track.rating, Some(8));
+        assert_eq!(track.play_count, 5);
+        assert!(track.loved);
+        assert_eq!(track.added_at.as_deref(), Some("2020-01-01T00:00:00+00:00"));
+    }
+}
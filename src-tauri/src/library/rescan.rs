@@ -0,0 +1,604 @@
+use super::*;
+use crate::scan::{register_job, unregister_job, ScanEvent, ScanJobsState, ScanReporter};
+use std::collections::HashSet;
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// 文件指纹：大小 + 修改时间（毫秒），两者都未变化则认为文件未修改
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified_at: i64,
+}
+
+impl FileFingerprint {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let metadata =
+            fs::metadata(path).map_err(|e| format!("Failed to read file metadata: {}", e))?;
+        let modified_at = metadata
+            .modified()
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis())
+            .unwrap_or(0);
+
+        Ok(Self {
+            size: metadata.len(),
+            modified_at,
+        })
+    }
+
+    pub fn matches(&self, track: &LibraryTrack) -> bool {
+        self.size == track.file_size && self.modified_at == track.modified_at
+    }
+}
+
+/// 增量扫描结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryRescanDiff {
+    pub added: Vec<LibraryTrack>,
+    pub updated: Vec<LibraryTrack>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    pub errors: Vec<String>,
+}
+
+impl LibraryRescanDiff {
+    /// 新增和更新的曲目ID
+    pub fn changed_ids(&self) -> Vec<String> {
+        self.added
+            .iter()
+            .chain(self.updated.iter())
+            .map(|track| track.id.clone())
+            .collect()
+    }
+
+    /// 把在快照上得到的扫描结果合并到最新的音乐库
+    ///
+    /// 扫描期间其他写入者（监听器、评分、播放统计）做的修改会保留
+    pub fn apply_to(&self, library: &mut MusicLibrary) {
+        let removed: HashSet<&String> = self.removed.iter().collect();
+        library.tracks.retain(|track| !removed.contains(&track.id));
+
+        for track in &self.updated {
+            // 扫描期间已被移除的曲目不再加回
+            if let Some(current) = library.tracks.iter_mut().find(|t| t.id == track.id) {
+                let mut updated = track.clone();
+                updated.keep_user_data(current);
+                *current = updated;
+            }
+        }
+
+        for track in &self.added {
+            if !library
+                .tracks
+                .iter()
+                .any(|t| t.file_path == track.file_path)
+            {
+                library.tracks.push(track.clone());
+            }
+        }
+    }
+}
+
+/// 对比已保存的音乐库增量更新，只重新读取新增或已修改的文件
+///
+/// 需要读取的文件在线程池中并行处理，进度通过 `reporter` 推送
+pub async fn rescan_library_tracks(
+    library: &mut MusicLibrary,
+    roots: &[String],
+    reporter: &mut ScanReporter<LibraryTrack>,
+) -> Result<LibraryRescanDiff, String> {
+    let mut diff = LibraryRescanDiff::default();
+    let mut found = HashSet::new();
+    let mut scanned_roots = Vec::new();
+    let mut pending: HashMap<PathBuf, (FileFingerprint, Option<usize>)> = HashMap::new();
+
+    let existing: HashMap<String, usize> = library
+        .tracks
+        .iter()
+        .enumerate()
+        .map(|(index, track)| (track.file_path.clone(), index))
+        .collect();
+
+    for root in roots {
+        let files = match collect_audio_files(root, reporter.job()).await {
+            Ok(files) => files,
+            Err(e) => {
+                diff.errors.push(format!("{}: {}", root, e));
+                continue;
+            }
+        };
+        // 遍历被取消时文件列表不完整，该目录不参与移除判断
+        if reporter.job().is_cancelled() {
+            break;
+        }
+        scanned_roots.push(Path::new(root));
+        reporter.found(files.len());
+
+        for file in files {
+            let path_str = file.to_string_lossy().to_string();
+            if !found.insert(path_str.clone()) {
+                continue;
+            }
+
+            let fingerprint = match FileFingerprint::read(&file) {
+                Ok(fingerprint) => fingerprint,
+                Err(e) => {
+                    reporter.failed(&file, &e);
+                    diff.errors.push(format!("{}: {}", path_str, e));
+                    continue;
+                }
+            };
+
+            let existing_index = existing.get(&path_str).copied();
+            if let Some(index) = existing_index {
+                if fingerprint.matches(&library.tracks[index]) {
+                    diff.unchanged += 1;
+                    reporter.skipped(&file);
+                    continue;
+                }
+            }
+
+            pending.insert(file, (fingerprint, existing_index));
+        }
+    }
+
+    let files = pending.keys().cloned().collect();
+    let mut results = probe_in_parallel(reporter.job(), files, |path| {
+        extract_metadata(&path.to_string_lossy())
+    });
+
+    while let Some((file, result)) = results.next().await {
+        let path_str = file.to_string_lossy().to_string();
+        let Some((fingerprint, existing_index)) = pending.get(&file).copied() else {
+            continue;
+        };
+
+        let track = match result {
+            Ok(track) => track,
+            Err(e) => {
+                reporter.failed(&file, &e);
+                diff.errors.push(format!("{}: {}", path_str, e));
+                fallback_track(&path_str)
+            }
+        };
+
+        match existing_index {
+            Some(index) => {
+                let previous = &library.tracks[index];
+                let mut updated =
+                    LibraryTrack::from_track(track, previous.id.clone(), &fingerprint);
+                updated.keep_user_data(previous);
+                reporter.item(&file, &updated);
+                library.tracks[index] = updated.clone();
+                diff.updated.push(updated);
+            }
+            None => {
+                let added = LibraryTrack::from_track(track, new_track_id(), &fingerprint);
+                reporter.item(&file, &added);
+                library.tracks.push(added.clone());
+                diff.added.push(added);
+            }
+        }
+    }
+
+    // 只移除位于本次成功扫描的目录下、但已不存在的文件，
+    // 无法访问的目录（如未挂载的磁盘）不会导致曲目被删除
+    library.tracks.retain(|track| {
+        let path = Path::new(&track.file_path);
+        let in_roots = scanned_roots.iter().any(|root| path.starts_with(root));
+        if in_roots && !found.contains(&track.file_path) {
+            diff.removed.push(track.id.clone());
+            false
+        } else {
+            true
+        }
+    });
+    reporter.removed(&diff.removed);
+
+    library.last_updated = chrono::Utc::now().to_rfc3339();
+
+    Ok(diff)
+}
+
+/// 补全前端提交的曲目的标签字段
+///
+/// 文件浏览器只提交标题、艺术家、专辑、时长和路径：文件未变化时沿用旧库中的完整条目，
+/// 其余文件重新读取标签，避免保存后丢失流派、音轨号等字段
+pub async fn complete_submitted_tracks(
+    library: &mut MusicLibrary,
+    previous: Option<&MusicLibrary>,
+) -> Result<LibraryRescanDiff, String> {
+    if let Some(previous) = previous {
+        let previous: HashMap<&str, &LibraryTrack> = previous
+            .tracks
+            .iter()
+            .map(|track| (track.file_path.as_str(), track))
+            .collect();
+        for track in library.tracks.iter_mut() {
+            let Some(old) = previous.get(track.file_path.as_str()) else {
+                continue;
+            };
+            let unchanged = FileFingerprint::read(Path::new(&track.file_path))
+                .is_ok_and(|fingerprint| fingerprint.matches(old));
+            if unchanged {
+                *track = LibraryTrack {
+                    id: track.id.clone(),
+                    ..(*old).clone()
+                };
+            }
+        }
+    }
+
+    // 指纹与磁盘不一致的曲目（包括只有基本字段的新曲目）重新读取
+    let paths: Vec<String> = library
+        .tracks
+        .iter()
+        .map(|track| track.file_path.clone())
+        .collect();
+    rescan_library_tracks(library, &paths, &mut ScanReporter::silent()).await
+}
+
+// 收集扫描根路径下的音频文件，根路径本身也可以是单个文件
+pub(crate) async fn collect_audio_files(root: &str, job: &ScanJob) -> Result<Vec<PathBuf>, String> {
+    let path = Path::new(root);
+    if !path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    if path.is_file() {
+        if is_audio_file(path) {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        return Ok(Vec::new());
+    }
+
+    scan_directory_recursive(root.to_string(), job.clone()).await
+}
+
+// 扫描在快照上进行，完成后持写锁合并到最新的音乐库并保存
+async fn commit_rescan(
+    app: &AppHandle,
+    diff: &LibraryRescanDiff,
+    roots: Vec<String>,
+) -> Result<MusicLibrary, String> {
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    diff.apply_to(&mut library);
+    for root in roots {
+        if !library.last_scanned_paths.contains(&root) {
+            library.last_scanned_paths.push(root);
+        }
+    }
+    library.last_updated = chrono::Utc::now().to_rfc3339();
+    save_library_to_file(app, &library)?;
+
+    Ok(library)
+}
+
+/// 启动时升级旧版本的音乐库文件，并在后台重新读取标签补全新增字段
+///
+/// 缺少ID的曲目在这里分配ID并写回，否则每次加载都会分配新的ID，播放列表无法引用
+pub fn upgrade_library(app: &AppHandle) -> Result<(), String> {
+    let file_path = get_library_file_path(app)?;
+    if !file_path.exists() {
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read library file: {}", e))?;
+    let mut library: MusicLibrary = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse library file: {}", e))?;
+    let migrated = library.migrate();
+    if library.ensure_track_ids(None) || migrated {
+        save_library_to_file(app, &library)?;
+    }
+    if !migrated {
+        return Ok(());
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let roots = library.last_scanned_paths.clone();
+        let mut reporter = ScanReporter::silent();
+        match rescan_library_tracks(&mut library, &roots, &mut reporter).await {
+            Ok(diff) => match commit_rescan(&app, &diff, Vec::new()).await {
+                Ok(library) => search::rebuild_search_index(&app, &library),
+                Err(e) => eprintln!("Failed to save upgraded library: {}", e),
+            },
+            Err(e) => eprintln!("Failed to rescan upgraded library: {}", e),
+        }
+    });
+
+    Ok(())
+}
+
+/// 增量重新扫描音乐库，未指定路径时使用上次扫描的目录
+#[tauri::command]
+pub async fn rescan_library(
+    app: AppHandle,
+    paths: Option<Vec<String>>,
+) -> Result<LibraryRescanDiff, String> {
+    let mut library = load_library_from_file(&app)?;
+    let roots = paths.unwrap_or_else(|| library.last_scanned_paths.clone());
+
+    let mut reporter = ScanReporter::silent();
+    let diff = rescan_library_tracks(&mut library, &roots, &mut reporter).await?;
+    let library = commit_rescan(&app, &diff, roots).await?;
+    search::update_search_index(&app, &library, &diff.changed_ids(), &diff.removed);
+    watcher::refresh_watching(&app);
+
+    Ok(diff)
+}
+
+/// 启动可取消的后台音乐库扫描，立即返回任务ID，进度和结果通过 `on_event` 推送
+#[tauri::command]
+pub async fn start_library_scan(
+    app: AppHandle,
+    paths: Option<Vec<String>>,
+    on_event: Channel<ScanEvent<LibraryTrack>>,
+    jobs: State<'_, ScanJobsState>,
+) -> Result<String, String> {
+    let mut library = load_library_from_file(&app)?;
+    let roots = paths.unwrap_or_else(|| library.last_scanned_paths.clone());
+
+    let jobs = jobs.inner().clone();
+    let job = register_job(&jobs)?;
+    let job_id = job.id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut reporter = ScanReporter::new(job.clone(), Some(on_event));
+
+        match rescan_library_tracks(&mut library, &roots, &mut reporter).await {
+            Ok(diff) => {
+                // 即使被取消，已完成的部分也会保存
+                match commit_rescan(&app, &diff, roots).await {
+                    Ok(library) => {
+                        search::update_search_index(
+                            &app,
+                            &library,
+                            &diff.changed_ids(),
+                            &diff.removed,
+                        );
+                        watcher::refresh_watching(&app);
+                    }
+                    Err(e) => eprintln!("Failed to save library after scan: {}", e),
+                }
+            }
+            Err(e) => eprintln!("Library scan failed: {}", e),
+        }
+
+        reporter.finish();
+        unregister_job(&jobs, &job.id);
+    });
+
+    Ok(job_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support::{self, temp_dir};
+
+    #[tokio::test]
+    async fn test_rescan_skips_unchanged_and_updates_modified_files() {
+        let dir = temp_dir("fingerprint");
+        fs::write(dir.join("a.mp3"), b"aaaa").unwrap();
+        fs::write(dir.join("b.mp3"), b"bbbb").unwrap();
+        fs::write(dir.join("c.mp3"), b"cccc").unwrap();
+        let roots = vec![dir.to_string_lossy().to_string()];
+
+        let mut library = MusicLibrary::new();
+        let first = rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        assert_eq!(first.added.len(), 3);
+        let id_of = |library: &MusicLibrary, name: &str| {
+            library
+                .tracks
+                .iter()
+                .find(|t| t.file_path.ends_with(name))
+                .map(|t| t.id.clone())
+                .unwrap()
+        };
+        let b_id = id_of(&library, "b.mp3");
+
+        fs::write(dir.join("b.mp3"), b"bbbbbbbb").unwrap();
+        fs::remove_file(dir.join("c.mp3")).unwrap();
+        let second = rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+
+        assert_eq!(second.unchanged, 1);
+        assert!(second.added.is_empty());
+        assert_eq!(second.updated.len(), 1);
+        assert_eq!(second.updated[0].id, b_id);
+        assert_eq!(second.updated[0].file_size, 8);
+        assert_eq!(second.removed.len(), 1);
+        assert_eq!(library.tracks.len(), 2);
+        assert_eq!(id_of(&library, "b.mp3"), b_id);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_submitted_tracks_keep_tag_fields() {
+        let dir = temp_dir("submitted");
+        fs::write(dir.join("a.mp3"), b"aaaa").unwrap();
+        fs::write(dir.join("b.mp3"), b"bbbb").unwrap();
+        let path_of = |name: &str| dir.join(name).to_string_lossy().to_string();
+
+        let mut previous = MusicLibrary::new();
+        let roots = vec![path_of("a.mp3")];
+        rescan_library_tracks(&mut previous, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        previous.tracks[0].genre = Some("Rock".to_string());
+        previous.tracks[0].track_number = Some(3);
+
+        // 文件浏览器提交的曲目只有基本字段
+        let thin = |name: &str| LibraryTrack {
+            title: name.to_string(),
+            artist: "Unknown Artist".to_string(),
+            album: "Unknown Album".to_string(),
+            file_path: path_of(name),
+            ..Default::default()
+        };
+        let mut library = MusicLibrary::new();
+        library.tracks = vec![thin("a.mp3"), thin("b.mp3")];
+        library.ensure_track_ids(Some(&previous));
+
+        let diff = complete_submitted_tracks(&mut library, Some(&previous))
+            .await
+            .unwrap();
+
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(library.tracks[0].id, previous.tracks[0].id);
+        assert_eq!(library.tracks[0].genre.as_deref(), Some("Rock"));
+        assert_eq!(library.tracks[0].track_number, Some(3));
+        // 新文件重新读取后记录了指纹，之后的增量扫描可以跳过
+        assert_eq!(library.tracks[1].file_size, 4);
+        assert!(library.tracks[1].modified_at > 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rescan_sets_added_at_only_for_new_tracks() {
+        let dir = temp_dir("added-at");
+        fs::write(dir.join("a.mp3"), b"aaaa").unwrap();
+        let roots = vec![dir.to_string_lossy().to_string()];
+        let mut library = MusicLibrary::new();
+        rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        assert!(library.tracks[0].added_at.is_some());
+
+        // 旧版本的条目没有加入时间，重新读取后不能当作刚加入
+        library.tracks[0].added_at = None;
+        library.tracks[0].file_size = 0;
+        fs::write(dir.join("b.mp3"), b"bbbb").unwrap();
+        let diff = rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+
+        assert_eq!(diff.updated.len(), 1);
+        assert_eq!(diff.updated[0].added_at, None);
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.added[0].added_at.is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rescan_keeps_tracks_under_unreadable_root() {
+        let dir = temp_dir("unmounted");
+        fs::write(dir.join("a.mp3"), b"aaaa").unwrap();
+        let roots = vec![dir.to_string_lossy().to_string()];
+
+        let mut library = MusicLibrary::new();
+        rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        assert_eq!(library.tracks.len(), 1);
+
+        // 模拟磁盘被卸载：扫描目录无法访问
+        fs::remove_dir_all(&dir).unwrap();
+        let diff = rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+
+        assert_eq!(diff.errors.len(), 1);
+        assert!(diff.removed.is_empty());
+        assert_eq!(library.tracks.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rescan_diff_merges_into_current_library() {
+        let dir = temp_dir("merge");
+        fs::write(dir.join("a.mp3"), b"aaaa").unwrap();
+        let roots = vec![dir.to_string_lossy().to_string()];
+        let mut current = MusicLibrary::new();
+        rescan_library_tracks(&mut current, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+
+        // 扫描在快照上进行，期间当前音乐库的评分被修改
+        let mut snapshot = current.clone();
+        fs::write(dir.join("a.mp3"), b"aaaaaaaa").unwrap();
+        fs::write(dir.join("b.mp3"), b"bbbb").unwrap();
+        let diff = rescan_library_tracks(&mut snapshot, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        current.tracks[0].rating = Some(8);
+        diff.apply_to(&mut current);
+
+        assert_eq!(current.tracks.len(), 2);
+        assert_eq!(current.tracks[0].file_size, 8);
+        assert_eq!(current.tracks[0].rating, Some(8));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_rescan_keeps_library_unchanged() {
+        let dir = temp_dir("cancel");
+        for i in 0..20 {
+            let sub = dir.join(format!("album-{}", i));
+            fs::create_dir_all(&sub).unwrap();
+            fs::write(sub.join("01.mp3"), b"track").unwrap();
+        }
+        let roots = vec![dir.to_string_lossy().to_string()];
+        let mut library = MusicLibrary::new();
+        rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        assert_eq!(library.tracks.len(), 20);
+
+        fs::write(dir.join("new.mp3"), b"new").unwrap();
+        let job = ScanJob::new();
+        job.cancel();
+        let mut reporter = ScanReporter::new(job, None);
+        let diff = rescan_library_tracks(&mut library, &roots, &mut reporter)
+            .await
+            .unwrap();
+
+        // 遍历中途停止，不完整的文件列表不会导致曲目被移除
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert_eq!(library.tracks.len(), 20);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_old_library() {
+        let mut old_track = test_support::track("")
+            .artist("Jay Chou; Lara")
+            .genre("Pop;Mandopop")
+            .path("/music/a.mp3")
+            .build();
+        old_track.file_size = 100;
+        let mut library = MusicLibrary {
+            version: 0,
+            tracks: vec![old_track, test_support::track("kept").build()],
+            last_scanned_paths: Vec::new(),
+            last_updated: String::new(),
+        };
+
+        assert!(library.migrate());
+        assert_eq!(library.version, LIBRARY_VERSION);
+        assert_eq!(library.tracks[0].artists, vec!["Jay Chou", "Lara"]);
+        assert_eq!(library.tracks[0].genres, vec!["Pop", "Mandopop"]);
+        // 清空文件指纹，下次扫描时重新读取标签
+        assert_eq!(library.tracks[0].file_size, 0);
+        assert!(!library.migrate());
+
+        // 沿用旧库中相同路径的ID，已有ID不变
+        let mut previous = MusicLibrary::new();
+        previous.tracks = vec![test_support::track("old-id").path("/music/a.mp3").build()];
+        assert!(library.ensure_track_ids(Some(&previous)));
+        assert_eq!(library.tracks[0].id, "old-id");
+        assert_eq!(library.tracks[1].id, "kept");
+        assert!(!library.ensure_track_ids(None));
+    }
+}
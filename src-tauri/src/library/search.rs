@@ -0,0 +1,667 @@
+use super::*;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Arc, Mutex};
+use tauri::State;
+
+/// 可搜索的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Composer,
+    Comment,
+    Lyrics,
+    Path,
+}
+
+impl SearchField {
+    const ALL: [SearchField; 9] = [
+        SearchField::Title,
+        SearchField::Artist,
+        SearchField::Album,
+        SearchField::AlbumArtist,
+        SearchField::Genre,
+        SearchField::Composer,
+        SearchField::Comment,
+        SearchField::Lyrics,
+        SearchField::Path,
+    ];
+
+    fn bit(self) -> u16 {
+        1 << (self as u16)
+    }
+
+    /// 字段权重，越重要的字段命中得分越高
+    fn weight(self) -> f32 {
+        match self {
+            SearchField::Title => 3.0,
+            SearchField::Artist => 2.5,
+            SearchField::AlbumArtist => 2.0,
+            SearchField::Album => 2.0,
+            SearchField::Composer => 1.5,
+            SearchField::Genre => 1.2,
+            SearchField::Comment => 0.5,
+            SearchField::Lyrics => 0.5,
+            SearchField::Path => 0.3,
+        }
+    }
+
+    // 名称类字段额外索引拼音全拼和首字母
+    fn has_pinyin(self) -> bool {
+        !matches!(
+            self,
+            SearchField::Comment | SearchField::Lyrics | SearchField::Path
+        )
+    }
+
+    /// 查询语法中的字段名，如 `artist:beatles`
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "title" => Some(SearchField::Title),
+            "artist" => Some(SearchField::Artist),
+            "album" => Some(SearchField::Album),
+            "albumartist" | "album_artist" => Some(SearchField::AlbumArtist),
+            "genre" => Some(SearchField::Genre),
+            "composer" => Some(SearchField::Composer),
+            "comment" => Some(SearchField::Comment),
+            "lyrics" => Some(SearchField::Lyrics),
+            "path" | "file" => Some(SearchField::Path),
+            _ => None,
+        }
+    }
+
+    // 字段的所有取值，艺术家和流派包含多值标签中的每一项
+    fn values(self, track: &LibraryTrack) -> Vec<&str> {
+        let mut values = match self {
+            SearchField::Title => vec![track.title.as_str()],
+            SearchField::Artist => {
+                let mut names = track.artist_names();
+                names.push(&track.artist);
+                names
+            }
+            SearchField::Album => vec![track.album.as_str()],
+            SearchField::AlbumArtist => track.album_artist.as_deref().into_iter().collect(),
+            SearchField::Genre => {
+                let mut names = track.genre_names();
+                names.extend(track.genre.as_deref());
+                names
+            }
+            SearchField::Composer => track.composer.as_deref().into_iter().collect(),
+            SearchField::Comment => track.comment.as_deref().into_iter().collect(),
+            SearchField::Lyrics => track.lyrics.as_deref().into_iter().collect(),
+            SearchField::Path => vec![track.file_path.as_str()],
+        };
+        values.dedup();
+        values
+    }
+}
+
+// 中日韩文字没有空格分词，按单字索引
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF)
+}
+
+/// 分词：小写化，按非字母数字字符切分，中日韩文字逐字切分
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if is_cjk(c) {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            tokens.push(c.to_string());
+        } else if c.is_alphanumeric() {
+            current.push(c);
+        } else if !current.is_empty() {
+            tokens.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// 编辑距离（含相邻字符交换），超过 `max` 时提前返回
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev_prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        let mut row_min = current[0];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (prev[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(prev_prev[j - 2] + 1);
+            }
+            row_min = row_min.min(current[j]);
+        }
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut current);
+    }
+
+    let distance = prev[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+// 根据词长允许的拼写错误数
+fn max_typos(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// 查询子句
+#[derive(Debug, Clone, PartialEq)]
+enum Clause {
+    Term {
+        text: String,
+        field: Option<SearchField>,
+    },
+    Year {
+        min: Option<u32>,
+        max: Option<u32>,
+    },
+}
+
+// 拆分查询字符串，引号内的内容视为一个整体
+fn split_query(query: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+
+    parts
+}
+
+fn parse_year_range(value: &str) -> Option<(Option<u32>, Option<u32>)> {
+    let parse = |s: &str| -> Option<Option<u32>> {
+        if s.is_empty() {
+            Some(None)
+        } else {
+            s.parse().ok().map(Some)
+        }
+    };
+
+    match value.split_once("..") {
+        Some((min, max)) => Some((parse(min)?, parse(max)?)),
+        None => {
+            let year = value.parse().ok()?;
+            Some((Some(year), Some(year)))
+        }
+    }
+}
+
+/// 解析查询语法：普通词、`field:value`、`field:"多个 词"`、`year:1965..1970`
+fn parse_query(query: &str) -> Vec<Clause> {
+    let mut clauses = Vec::new();
+
+    for part in split_query(query) {
+        if let Some((name, value)) = part.split_once(':') {
+            if name.eq_ignore_ascii_case("year") {
+                if let Some((min, max)) = parse_year_range(value) {
+                    clauses.push(Clause::Year { min, max });
+                    continue;
+                }
+            } else if let Some(field) = SearchField::from_name(name) {
+                clauses.extend(tokenize(value).into_iter().map(|text| Clause::Term {
+                    text,
+                    field: Some(field),
+                }));
+                continue;
+            }
+        }
+
+        clauses.extend(
+            tokenize(&part)
+                .into_iter()
+                .map(|text| Clause::Term { text, field: None }),
+        );
+    }
+
+    clauses
+}
+
+#[derive(Debug, Clone)]
+struct IndexedTrack {
+    track: LibraryTrack,
+    terms: Vec<String>,
+}
+
+/// 搜索命中结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub track: LibraryTrack,
+    pub score: f32,
+}
+
+/// 内存中的曲目全文索引，支持增量更新
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    loaded: bool,
+    docs: Vec<Option<IndexedTrack>>,
+    free_slots: Vec<usize>,
+    slots: HashMap<String, usize>,
+    // 词 -> 文档槽位 -> 命中字段位掩码
+    postings: HashMap<String, HashMap<usize, u16>>,
+    // 有序词表，用于前缀匹配
+    terms: BTreeSet<String>,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 用完整曲目列表重建索引
+    pub fn rebuild(&mut self, tracks: &[LibraryTrack]) {
+        *self = Self::default();
+        self.loaded = true;
+        for track in tracks {
+            self.upsert(track.clone());
+        }
+    }
+
+    /// 新增或更新一首曲目
+    pub fn upsert(&mut self, track: LibraryTrack) {
+        self.remove(&track.id);
+
+        let slot = self.free_slots.pop().unwrap_or_else(|| {
+            self.docs.push(None);
+            self.docs.len() - 1
+        });
+
+        let mut fields_by_term: HashMap<String, u16> = HashMap::new();
+        for field in SearchField::ALL {
+            for value in field.values(&track) {
+                let mut terms = tokenize(&normalize::normalize_text(value));
+                if field.has_pinyin() {
+                    terms.extend(pinyin::pinyin_terms(value));
+                }
+                for term in terms {
+                    *fields_by_term.entry(term).or_default() |= field.bit();
+                }
+            }
+        }
+
+        for (term, fields) in &fields_by_term {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(slot, *fields);
+            self.terms.insert(term.clone());
+        }
+
+        self.slots.insert(track.id.clone(), slot);
+        self.docs[slot] = Some(IndexedTrack {
+            track,
+            terms: fields_by_term.into_keys().collect(),
+        });
+    }
+
+    /// 移除一首曲目
+    pub fn remove(&mut self, id: &str) {
+        let Some(slot) = self.slots.remove(id) else {
+            return;
+        };
+        let Some(doc) = self.docs[slot].take() else {
+            return;
+        };
+
+        for term in doc.terms {
+            if let Some(posting) = self.postings.get_mut(&term) {
+                posting.remove(&slot);
+                if posting.is_empty() {
+                    self.postings.remove(&term);
+                    self.terms.remove(&term);
+                }
+            }
+        }
+        self.free_slots.push(slot);
+    }
+
+    // 找出与查询词匹配的索引词：精确匹配、前缀匹配和拼写容错匹配
+    fn expand_term(&self, term: &str) -> Vec<(&str, f32)> {
+        let mut matches: Vec<(&str, f32)> = Vec::new();
+
+        if let Some((exact, _)) = self.postings.get_key_value(term) {
+            matches.push((exact.as_str(), 1.0));
+        }
+
+        for candidate in self
+            .terms
+            .range::<str, _>((std::ops::Bound::Excluded(term), std::ops::Bound::Unbounded))
+            .take_while(|candidate| candidate.starts_with(term))
+        {
+            matches.push((candidate.as_str(), 0.8));
+        }
+
+        // 拼写纠错只比较首字符相同、长度相差不超过允许错误数的词
+        let max = max_typos(term);
+        if let (true, Some(first)) = (max > 0, term.chars().next()) {
+            let len = term.chars().count();
+            let start = first.to_string();
+            for candidate in self
+                .terms
+                .range::<str, _>((
+                    std::ops::Bound::Included(start.as_str()),
+                    std::ops::Bound::Unbounded,
+                ))
+                .take_while(|candidate| candidate.starts_with(first))
+            {
+                if candidate.starts_with(term) || candidate.chars().count().abs_diff(len) > max {
+                    continue;
+                }
+                if let Some(distance) = edit_distance(term, candidate, max) {
+                    matches.push((candidate.as_str(), 0.6 - 0.15 * (distance - 1) as f32));
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// 搜索并按相关度排序
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let normalized_query = normalize::normalize_text(query);
+        let clauses = parse_query(&normalized_query);
+        let mut scores: Option<HashMap<usize, f32>> = None;
+
+        for clause in &clauses {
+            let Clause::Term { text, field } = clause else {
+                continue;
+            };
+            let mask = field.map(|f| f.bit()).unwrap_or(u16::MAX);
+
+            // 同一个子句取每个文档的最佳命中
+            let mut clause_scores: HashMap<usize, f32> = HashMap::new();
+            for (term, match_score) in self.expand_term(text) {
+                let Some(posting) = self.postings.get(term) else {
+                    continue;
+                };
+                for (&slot, &fields) in posting {
+                    let fields = fields & mask;
+                    if fields == 0 {
+                        continue;
+                    }
+                    let weight = SearchField::ALL
+                        .iter()
+                        .filter(|f| fields & f.bit() != 0)
+                        .map(|f| f.weight())
+                        .fold(0.0, f32::max);
+                    let score = match_score * weight;
+                    let best = clause_scores.entry(slot).or_default();
+                    *best = best.max(score);
+                }
+            }
+
+            // 所有子句都必须命中
+            scores = Some(match scores {
+                None => clause_scores,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(slot, score)| {
+                        clause_scores.get(&slot).map(|extra| (slot, score + extra))
+                    })
+                    .collect(),
+            });
+        }
+
+        // 只有过滤条件时匹配全部曲目
+        let scores =
+            scores.unwrap_or_else(|| self.slots.values().map(|&slot| (slot, 0.0)).collect());
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .filter_map(|(slot, score)| {
+                let doc = self.docs.get(slot)?.as_ref()?;
+                let track = &doc.track;
+                let year_ok = clauses.iter().all(|clause| match clause {
+                    Clause::Year { min, max } => track.year.is_some_and(|year| {
+                        !min.is_some_and(|min| year < min) && !max.is_some_and(|max| year > max)
+                    }),
+                    _ => true,
+                });
+                if !year_ok {
+                    return None;
+                }
+
+                // 标题完全一致时额外加分
+                let bonus = if normalize::normalize_text(&track.title) == normalized_query {
+                    2.0
+                } else {
+                    0.0
+                };
+                Some(SearchHit {
+                    track: track.clone(),
+                    score: score + bonus,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.track.title.cmp(&b.track.title))
+        });
+
+        hits
+    }
+}
+
+pub type SearchIndexState = Arc<Mutex<SearchIndex>>;
+
+/// 首次使用时从音乐库文件加载索引
+fn ensure_loaded(app: &AppHandle, index: &mut SearchIndex) -> Result<(), String> {
+    if !index.loaded {
+        let library = load_library_from_file(app)?;
+        index.rebuild(&library.tracks);
+    }
+    Ok(())
+}
+
+/// 音乐库变化后增量更新索引，索引尚未加载时不做任何事
+pub fn update_search_index(
+    app: &AppHandle,
+    library: &MusicLibrary,
+    changed_ids: &[String],
+    removed_ids: &[String],
+) {
+    let state = app.state::<SearchIndexState>();
+    let Ok(mut index) = state.lock() else {
+        return;
+    };
+    if !index.loaded {
+        return;
+    }
+
+    for id in removed_ids {
+        index.remove(id);
+    }
+    let changed: HashSet<&String> = changed_ids.iter().collect();
+    for track in library
+        .tracks
+        .iter()
+        .filter(|track| changed.contains(&track.id))
+    {
+        index.upsert(track.clone());
+    }
+}
+
+/// 整个音乐库被替换或清空后重建索引
+pub fn rebuild_search_index(app: &AppHandle, library: &MusicLibrary) {
+    let state = app.state::<SearchIndexState>();
+    let Ok(mut index) = state.lock() else {
+        return;
+    };
+    index.rebuild(&library.tracks);
+}
+
+/// 全文搜索音乐库
+#[tauri::command]
+pub async fn search_tracks(
+    app: AppHandle,
+    index: State<'_, SearchIndexState>,
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<QueryPage<SearchHit>, String> {
+    let mut index = index.lock().map_err(|e| e.to_string())?;
+    ensure_loaded(&app, &mut index)?;
+
+    let hits = index.search(&query);
+    Ok(QueryPage::from_sorted(hits, offset.unwrap_or(0), limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn track(id: &str, title: &str, artist: &str, year: Option<u32>) -> LibraryTrack {
+        test_support::track(id)
+            .title(title)
+            .artist(artist)
+            .album("Help!")
+            .year(year)
+            .build()
+    }
+
+    fn sample_index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.rebuild(&[
+            track("1", "Yesterday", "The Beatles", Some(1965)),
+            track("2", "Hey Jude", "The Beatles", Some(1968)),
+            track("3", "Let It Be", "The Beatles", Some(1970)),
+            track("4", "晴天", "周杰伦", Some(2003)),
+            track("5", "Beatles Tribute", "Cover Band", Some(2010)),
+        ]);
+        index
+    }
+
+    fn ids(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.track.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Hey Jude (Remastered)"),
+            vec!["hey", "jude", "remastered"]
+        );
+        assert_eq!(tokenize("周杰伦 Jay"), vec!["周", "杰", "伦", "jay"]);
+    }
+
+    #[test]
+    fn test_prefix_and_typo_matching() {
+        let index = sample_index();
+
+        assert_eq!(ids(&index.search("yester")), vec!["1"]);
+        assert_eq!(ids(&index.search("yesterdy")), vec!["1"]);
+        assert_eq!(ids(&index.search("杰伦")), vec!["4"]);
+    }
+
+    #[test]
+    fn test_normalized_matching() {
+        let index = sample_index();
+
+        assert_eq!(ids(&index.search("週杰倫")), vec!["4"]);
+        assert_eq!(ids(&index.search("ＹＥＳＴＥＲＤＡＹ")), vec!["1"]);
+    }
+
+    #[test]
+    fn test_pinyin_matching() {
+        let index = sample_index();
+
+        assert_eq!(ids(&index.search("zjl")), vec!["4"]);
+        assert_eq!(ids(&index.search("zhoujielun")), vec!["4"]);
+        assert_eq!(ids(&index.search("qingtian")), vec!["4"]);
+    }
+
+    #[test]
+    fn test_field_scoped_query() {
+        let index = sample_index();
+
+        let hits = index.search("artist:beatles year:1965..1968");
+        let mut found = ids(&hits);
+        found.sort();
+        assert_eq!(found, vec!["1", "2"]);
+
+        let hits = index.search("title:\"let it\"");
+        assert_eq!(ids(&hits), vec!["3"]);
+    }
+
+    #[test]
+    fn test_ranking_prefers_important_fields() {
+        let index = sample_index();
+
+        // 标题命中的权重高于艺术家命中
+        let hits = index.search("beatles");
+        assert_eq!(hits[0].track.id, "5");
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let mut index = sample_index();
+
+        index.remove("1");
+        assert!(index.search("yesterday").is_empty());
+
+        index.upsert(track("2", "Hey Jude (Live)", "The Beatles", Some(1968)));
+        assert_eq!(ids(&index.search("live")), vec!["2"]);
+        assert_eq!(index.slots.len(), 4);
+    }
+
+    #[test]
+    fn test_multi_value_artists_and_genres() {
+        let mut index = SearchIndex::new();
+        let mut duet = track("1", "Under Pressure", "Queen", Some(1981));
+        duet.artists = vec!["Queen".to_string(), "David Bowie".to_string()];
+        duet.genres = vec!["Rock".to_string(), "Synthpop".to_string()];
+        index.upsert(duet);
+
+        assert_eq!(ids(&index.search("artist:bowie")), vec!["1"]);
+        assert_eq!(ids(&index.search("genre:synthpop")), vec!["1"]);
+        // 首字符不同的词不参与拼写纠错
+        assert!(index.search("bynthpop").is_empty());
+        assert_eq!(ids(&index.search("synthpp")), vec!["1"]);
+    }
+}
@@ -0,0 +1,82 @@
+use super::LibraryTrack;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 在系统临时目录下创建一个独立的测试目录
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("music-play-test-{}-{}", name, uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 写入一个只有静音 MPEG 帧、没有标签的 MP3 文件，供标签读写测试使用
+pub fn silent_mp3(path: &Path) {
+    // MPEG-1 Layer III，128kbps，44.1kHz，每帧417字节
+    let mut frame = vec![0u8; 417];
+    frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+    fs::write(path, frame.repeat(40)).unwrap();
+}
+
+/// 测试用的音乐库曲目，标题默认为ID，路径默认为 `/music/<id>.mp3`
+pub fn track(id: &str) -> TrackBuilder {
+    TrackBuilder(LibraryTrack {
+        id: id.to_string(),
+        title: id.to_string(),
+        file_path: format!("/music/{}.mp3", id),
+        ..Default::default()
+    })
+}
+
+pub struct TrackBuilder(LibraryTrack);
+
+impl TrackBuilder {
+    pub fn title(mut self, title: &str) -> Self {
+        self.0.title = title.to_string();
+        self
+    }
+
+    pub fn artist(mut self, artist: &str) -> Self {
+        self.0.artist = artist.to_string();
+        self
+    }
+
+    pub fn album(mut self, album: &str) -> Self {
+        self.0.album = album.to_string();
+        self
+    }
+
+    pub fn genre(mut self, genre: &str) -> Self {
+        self.0.genre = Some(genre.to_string());
+        self
+    }
+
+    pub fn year(mut self, year: impl Into<Option<u32>>) -> Self {
+        self.0.year = year.into();
+        self
+    }
+
+    pub fn track_number(mut self, track_number: u32) -> Self {
+        self.0.track_number = Some(track_number);
+        self
+    }
+
+    pub fn duration(mut self, duration: f64) -> Self {
+        self.0.duration = duration;
+        self
+    }
+
+    pub fn rating(mut self, rating: Option<u8>) -> Self {
+        self.0.rating = rating;
+        self
+    }
+
+    pub fn path(mut self, path: impl AsRef<Path>) -> Self {
+        self.0.file_path = path.as_ref().to_string_lossy().to_string();
+        self
+    }
+
+    pub fn build(self) -> LibraryTrack {
+        self.0
+    }
+}
@@ -0,0 +1,572 @@
+use super::*;
+use crate::scan::ScanReporter;
+use crate::video::{self, MediaType, VideoLibraryConfig, VideoProcessor};
+use notify_debouncer_full::notify::event::{ModifyKind, RenameMode};
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{
+    new_debouncer, DebounceEventResult, DebouncedEvent, Debouncer, RecommendedCache,
+};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{Emitter, State};
+use tokio::sync::mpsc;
+
+/// 文件变化合并的时间窗口
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 音乐库/视频库目录监听器
+pub struct LibraryWatcher {
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+    pub roots: Vec<String>,
+    audio_roots: Vec<String>,
+    video_roots: Vec<String>,
+}
+
+pub type LibraryWatcherState = Arc<Mutex<Option<LibraryWatcher>>>;
+
+/// `library://changed` 事件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryChangedEvent {
+    pub media_type: MediaType,
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl LibraryChangedEvent {
+    fn new(media_type: MediaType) -> Self {
+        Self {
+            media_type,
+            added: Vec::new(),
+            updated: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// 一批去抖后的文件变化
+#[derive(Debug, Default)]
+struct ChangeSet {
+    renames: Vec<(PathBuf, PathBuf)>,
+    paths: Vec<PathBuf>,
+}
+
+impl ChangeSet {
+    fn from_events(events: Vec<DebouncedEvent>) -> Self {
+        let mut changes = Self::default();
+
+        for event in events {
+            match event.kind {
+                EventKind::Access(_) => {}
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                    changes
+                        .renames
+                        .push((event.paths[0].clone(), event.paths[1].clone()));
+                }
+                _ => {
+                    for path in &event.paths {
+                        if !changes.paths.contains(path) {
+                            changes.paths.push(path.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        changes
+    }
+
+    // 过滤出位于指定根目录下的变化
+    // 只有一端在根目录下的重命名按移出（删除）或移入（新增）处理
+    fn within(&self, roots: &[String]) -> Self {
+        let under = |path: &Path| roots.iter().any(|root| path.starts_with(root));
+        let mut changes = Self {
+            renames: Vec::new(),
+            paths: self.paths.iter().filter(|p| under(p)).cloned().collect(),
+        };
+
+        for (from, to) in &self.renames {
+            let path = match (under(from), under(to)) {
+                (true, true) => {
+                    changes.renames.push((from.clone(), to.clone()));
+                    continue;
+                }
+                (true, false) => from,
+                (false, true) => to,
+                (false, false) => continue,
+            };
+            if !changes.paths.contains(path) {
+                changes.paths.push(path.clone());
+            }
+        }
+
+        changes
+    }
+
+    fn is_empty(&self) -> bool {
+        self.renames.is_empty() && self.paths.is_empty()
+    }
+}
+
+// 重命名后的新路径，`path` 必须位于 `from` 之下
+fn renamed_path(path: &Path, from: &Path, to: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(from).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(to.to_path_buf())
+    } else {
+        Some(to.join(rest))
+    }
+}
+
+// 音乐库和视频库当前的扫描目录
+fn library_roots(app: &AppHandle) -> Result<(Vec<String>, Vec<String>), String> {
+    let audio_roots = load_library_from_file(app)?.last_scanned_paths;
+    let video_roots = video::load_video_library_from_file(app)?.last_scanned_paths;
+    Ok((audio_roots, video_roots))
+}
+
+/// 开始监听音乐库和视频库的扫描目录，已有的监听器会被替换
+pub fn start_watching(app: &AppHandle) -> Result<Vec<String>, String> {
+    let (audio_roots, video_roots) = library_roots(app)?;
+    watch_roots(app, audio_roots, video_roots)
+}
+
+fn watch_roots(
+    app: &AppHandle,
+    audio_roots: Vec<String>,
+    video_roots: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Vec<DebouncedEvent>>();
+    let mut debouncer = new_debouncer(
+        DEBOUNCE_TIMEOUT,
+        None,
+        move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let _ = tx.send(events);
+            }
+            Err(errors) => eprintln!("File watcher errors: {:?}", errors),
+        },
+    )
+    .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+    let mut roots: Vec<String> = Vec::new();
+    for root in audio_roots.iter().chain(video_roots.iter()) {
+        if roots.contains(root) {
+            continue;
+        }
+        match debouncer.watch(Path::new(root), RecursiveMode::Recursive) {
+            Ok(()) => roots.push(root.clone()),
+            Err(e) => eprintln!("Failed to watch {}: {}", root, e),
+        }
+    }
+
+    // 监听器被替换或停止时发送端随之释放，处理任务自动结束
+    let handle = app.clone();
+    let (task_audio_roots, task_video_roots) = (audio_roots.clone(), video_roots.clone());
+    tauri::async_runtime::spawn(async move {
+        while let Some(events) = rx.recv().await {
+            let changes = ChangeSet::from_events(events);
+            apply_changes(&handle, &changes, &task_audio_roots, &task_video_roots).await;
+        }
+    });
+
+    let state = app.state::<LibraryWatcherState>();
+    let mut watcher = state.lock().map_err(|e| e.to_string())?;
+    *watcher = Some(LibraryWatcher {
+        _debouncer: debouncer,
+        roots: roots.clone(),
+        audio_roots,
+        video_roots,
+    });
+
+    Ok(roots)
+}
+
+/// 库目录变化后在后台重新建立监听，目录未变时保留现有监听器，失败只记录日志
+pub fn refresh_watching(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = rewatch_if_changed(&app) {
+            eprintln!("Failed to refresh library watcher: {}", e);
+        }
+    });
+}
+
+fn rewatch_if_changed(app: &AppHandle) -> Result<(), String> {
+    let (audio_roots, video_roots) = library_roots(app)?;
+
+    let state = app.state::<LibraryWatcherState>();
+    let unchanged = state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .is_some_and(|w| w.audio_roots == audio_roots && w.video_roots == video_roots);
+    if unchanged {
+        return Ok(());
+    }
+
+    watch_roots(app, audio_roots, video_roots).map(|_| ())
+}
+
+async fn apply_changes(
+    app: &AppHandle,
+    changes: &ChangeSet,
+    audio_roots: &[String],
+    video_roots: &[String],
+) {
+    let audio_changes = changes.within(audio_roots);
+    if !audio_changes.is_empty() {
+        match apply_audio_changes(app, &audio_changes).await {
+            Ok(event) => emit_changed(app, event),
+            Err(e) => eprintln!("Failed to update music library: {}", e),
+        }
+    }
+
+    let video_changes = changes.within(video_roots);
+    if !video_changes.is_empty() {
+        match apply_video_changes(app, &video_changes).await {
+            Ok(event) => emit_changed(app, event),
+            Err(e) => eprintln!("Failed to update video library: {}", e),
+        }
+    }
+}
+
+fn emit_changed(app: &AppHandle, event: LibraryChangedEvent) {
+    if event.is_empty() {
+        return;
+    }
+    if let Err(e) = app.emit("library://changed", &event) {
+        eprintln!("Failed to emit library change event: {}", e);
+    }
+}
+
+async fn apply_audio_changes(
+    app: &AppHandle,
+    changes: &ChangeSet,
+) -> Result<LibraryChangedEvent, String> {
+    let _lock = lock_library(app).await;
+    let mut library = load_library_from_file(app)?;
+    let event = apply_audio_changes_to(&mut library, changes).await?;
+
+    if !event.is_empty() {
+        save_library_to_file(app, &library)?;
+
+        let changed: Vec<String> = event
+            .added
+            .iter()
+            .chain(event.updated.iter())
+            .cloned()
+            .collect();
+        search::update_search_index(app, &library, &changed, &event.removed);
+    }
+
+    Ok(event)
+}
+
+// 把一批文件变化应用到音乐库，返回变化的曲目ID
+async fn apply_audio_changes_to(
+    library: &mut MusicLibrary,
+    changes: &ChangeSet,
+) -> Result<LibraryChangedEvent, String> {
+    let mut event = LibraryChangedEvent::new(MediaType::Audio);
+    let mut paths = changes.paths.clone();
+
+    // 重命名/移动只更新路径，保留曲目ID
+    for (from, to) in &changes.renames {
+        for track in library.tracks.iter_mut() {
+            if let Some(new_path) = renamed_path(Path::new(&track.file_path), from, to) {
+                track.file_path = new_path.to_string_lossy().to_string();
+                event.updated.push(track.id.clone());
+            }
+        }
+        paths.push(to.clone());
+    }
+
+    for path in &paths {
+        if !path.exists() {
+            library.tracks.retain(|track| {
+                if Path::new(&track.file_path).starts_with(path) {
+                    event.removed.push(track.id.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            continue;
+        }
+
+        let mut reporter = ScanReporter::silent();
+        let roots = [path.to_string_lossy().to_string()];
+        let diff = rescan::rescan_library_tracks(library, &roots, &mut reporter).await?;
+        event.added.extend(diff.added.into_iter().map(|t| t.id));
+        event.updated.extend(diff.updated.into_iter().map(|t| t.id));
+        event.removed.extend(diff.removed);
+    }
+
+    if !event.is_empty() {
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+    }
+
+    Ok(event)
+}
+
+async fn apply_video_changes(
+    app: &AppHandle,
+    changes: &ChangeSet,
+) -> Result<LibraryChangedEvent, String> {
+    let processor = VideoProcessor::new(VideoLibraryConfig::default())
+        .map_err(|e| format!("创建视频处理器失败: {}", e))?;
+    let _lock = lock_library(app).await;
+    let mut library = video::load_video_library_from_file(app)?;
+    let event = apply_video_changes_to(&processor, &mut library, changes).await?;
+
+    if !event.is_empty() {
+        video::save_video_library_to_file(app, &library)?;
+    }
+
+    Ok(event)
+}
+
+// 把一批文件变化应用到视频库，返回变化的视频ID
+async fn apply_video_changes_to(
+    processor: &VideoProcessor,
+    library: &mut video::VideoLibrary,
+    changes: &ChangeSet,
+) -> Result<LibraryChangedEvent, String> {
+    let mut event = LibraryChangedEvent::new(MediaType::Video);
+    let mut paths = changes.paths.clone();
+
+    for (from, to) in &changes.renames {
+        for video in library.videos.iter_mut() {
+            if let Some(new_path) = renamed_path(&video.file_path, from, to) {
+                video.file_path = new_path;
+                event.updated.push(video.id.clone());
+            }
+        }
+        paths.push(to.clone());
+    }
+
+    for path in &paths {
+        if !path.exists() {
+            library.videos.retain(|video| {
+                if video.file_path.starts_with(path) {
+                    event.removed.push(video.id.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            continue;
+        }
+
+        let mut reporter = ScanReporter::silent();
+        let (videos, diff) = processor
+            .rescan_and_process(&library.videos, std::slice::from_ref(path), &mut reporter)
+            .await
+            .map_err(|e| format!("扫描视频文件失败: {}", e))?;
+        library.videos = videos;
+        event.added.extend(diff.added.into_iter().map(|v| v.id));
+        event.updated.extend(diff.updated.into_iter().map(|v| v.id));
+        event.removed.extend(diff.removed);
+    }
+
+    if !event.is_empty() {
+        library.last_updated = chrono::Utc::now().to_rfc3339();
+    }
+
+    Ok(event)
+}
+
+/// 开始（或重新开始）监听库目录，返回实际监听的路径
+#[tauri::command]
+pub fn start_library_watcher(app: AppHandle) -> Result<Vec<String>, String> {
+    start_watching(&app)
+}
+
+/// 停止监听库目录
+#[tauri::command]
+pub fn stop_library_watcher(state: State<'_, LibraryWatcherState>) -> Result<(), String> {
+    let mut watcher = state.lock().map_err(|e| e.to_string())?;
+    *watcher = None;
+    Ok(())
+}
+
+/// 获取当前监听的路径
+#[tauri::command]
+pub fn get_watched_library_paths(
+    state: State<'_, LibraryWatcherState>,
+) -> Result<Vec<String>, String> {
+    let watcher = state.lock().map_err(|e| e.to_string())?;
+    Ok(watcher
+        .as_ref()
+        .map(|w| w.roots.clone())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support::temp_dir;
+    use crate::video::{VideoCodec, VideoFile, VideoFormat, VideoLibrary, VideoResolution};
+    use notify_debouncer_full::notify::event::{AccessKind, CreateKind, RemoveKind};
+    use notify_debouncer_full::notify::Event;
+    use std::time::Instant;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> DebouncedEvent {
+        let event = paths.iter().fold(Event::new(kind), |event, path| {
+            event.add_path(path.to_path_buf())
+        });
+        DebouncedEvent::new(event, Instant::now())
+    }
+
+    fn video(path: &Path) -> VideoFile {
+        let metadata = fs::metadata(path).unwrap();
+        VideoFile {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: "clip".to_string(),
+            file_path: path.to_path_buf(),
+            duration: 10.0,
+            resolution: VideoResolution::new(1920, 1080),
+            format: VideoFormat::MP4,
+            codec: VideoCodec::H264,
+            file_size: metadata.len(),
+            thumbnail_path: None,
+            created_at: chrono::Utc::now(),
+            modified_at: metadata.modified().unwrap().into(),
+            bitrate: None,
+            frame_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_change_set_from_events() {
+        let a = Path::new("/music/a.mp3");
+        let b = Path::new("/music/b.mp3");
+        let c = Path::new("/other/c.mp3");
+        let changes = ChangeSet::from_events(vec![
+            event(EventKind::Access(AccessKind::Any), &[a]),
+            event(EventKind::Create(CreateKind::File), &[a]),
+            event(EventKind::Modify(ModifyKind::Any), &[a]),
+            event(
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &[b, c],
+            ),
+            event(EventKind::Remove(RemoveKind::File), &[c]),
+        ]);
+
+        assert_eq!(changes.paths, vec![a.to_path_buf(), c.to_path_buf()]);
+        assert_eq!(changes.renames, vec![(b.to_path_buf(), c.to_path_buf())]);
+
+        // 移出根目录的重命名按删除源路径处理
+        let within = changes.within(&["/music".to_string()]);
+        assert_eq!(within.paths, vec![a.to_path_buf(), b.to_path_buf()]);
+        assert!(within.renames.is_empty());
+        assert!(changes.within(&["/videos".to_string()]).is_empty());
+
+        // 移入根目录的重命名按新增处理，根目录内的重命名保持不变
+        let within = changes.within(&["/other".to_string()]);
+        assert_eq!(within.paths, vec![c.to_path_buf()]);
+        assert!(within.renames.is_empty());
+        let within = changes.within(&["/".to_string()]);
+        assert_eq!(within.renames, vec![(b.to_path_buf(), c.to_path_buf())]);
+    }
+
+    #[test]
+    fn test_renamed_path() {
+        let from = Path::new("/music/Old");
+        let to = Path::new("/music/New");
+        assert_eq!(
+            renamed_path(Path::new("/music/Old/01.mp3"), from, to),
+            Some(PathBuf::from("/music/New/01.mp3"))
+        );
+        assert_eq!(renamed_path(from, from, to), Some(to.to_path_buf()));
+        assert_eq!(
+            renamed_path(Path::new("/music/Older/01.mp3"), from, to),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_audio_changes_to() {
+        let dir = temp_dir("audio");
+        for name in ["a.mp3", "b.mp3"] {
+            fs::write(dir.join(name), name.as_bytes()).unwrap();
+        }
+        let mut library = MusicLibrary::new();
+        let roots = [dir.to_string_lossy().to_string()];
+        rescan::rescan_library_tracks(&mut library, &roots, &mut ScanReporter::silent())
+            .await
+            .unwrap();
+        let id_of = |library: &MusicLibrary, name: &str| {
+            library
+                .tracks
+                .iter()
+                .find(|t| t.file_path.ends_with(name))
+                .map(|t| t.id.clone())
+        };
+        let a_id = id_of(&library, "a.mp3").unwrap();
+        let b_id = id_of(&library, "b.mp3").unwrap();
+
+        fs::rename(dir.join("a.mp3"), dir.join("renamed.mp3")).unwrap();
+        fs::remove_file(dir.join("b.mp3")).unwrap();
+        fs::write(dir.join("c.mp3"), b"c").unwrap();
+        let changes = ChangeSet {
+            renames: vec![(dir.join("a.mp3"), dir.join("renamed.mp3"))],
+            paths: vec![dir.join("b.mp3"), dir.join("c.mp3")],
+        };
+        let event = apply_audio_changes_to(&mut library, &changes)
+            .await
+            .unwrap();
+
+        assert_eq!(event.updated, vec![a_id.clone()]);
+        assert_eq!(event.removed, vec![b_id]);
+        assert_eq!(event.added.len(), 1);
+        assert_eq!(id_of(&library, "renamed.mp3"), Some(a_id));
+        assert_eq!(id_of(&library, "c.mp3"), Some(event.added[0].clone()));
+        assert_eq!(library.tracks.len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_apply_video_changes_to() {
+        let dir = temp_dir("video");
+        fs::create_dir_all(dir.join("old")).unwrap();
+        fs::write(dir.join("old").join("clip.mp4"), b"clip").unwrap();
+        fs::write(dir.join("gone.mp4"), b"gone").unwrap();
+        let mut library = VideoLibrary::new();
+        library.videos = vec![
+            video(&dir.join("old").join("clip.mp4")),
+            video(&dir.join("gone.mp4")),
+        ];
+        let clip_id = library.videos[0].id.clone();
+        let gone_id = library.videos[1].id.clone();
+
+        fs::rename(dir.join("old"), dir.join("new")).unwrap();
+        fs::remove_file(dir.join("gone.mp4")).unwrap();
+        let changes = ChangeSet {
+            renames: vec![(dir.join("old"), dir.join("new"))],
+            paths: vec![dir.join("gone.mp4")],
+        };
+        let processor = VideoProcessor::new(VideoLibraryConfig::default()).unwrap();
+        let event = apply_video_changes_to(&processor, &mut library, &changes)
+            .await
+            .unwrap();
+
+        assert_eq!(event.updated, vec![clip_id.clone()]);
+        assert_eq!(event.removed, vec![gone_id]);
+        assert!(event.added.is_empty());
+        assert_eq!(library.videos.len(), 1);
+        assert_eq!(library.videos[0].id, clip_id);
+        assert_eq!(
+            library.videos[0].file_path,
+            dir.join("new").join("clip.mp4")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
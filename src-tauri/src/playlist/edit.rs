@@ -0,0 +1,395 @@
+use super::*;
+use crate::library::query::sort_by_track_keys;
+use crate::library::{get_saved_library, LibraryTrack, TrackSortKey};
+use std::path::Path;
+
+// Sorted, de-duplicated indices; fails when one is out of bounds
+fn checked_indices(indices: &[usize], len: usize) -> Result<Vec<usize>, String> {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.last().is_some_and(|last| *last >= len) {
+        return Err("Track index out of bounds".to_string());
+    }
+    Ok(sorted)
+}
+
+/// Move the items at `indices` so that they end up, in their current order,
+/// where `to` pointed before the move (0..=len, like a drop position).
+pub fn move_items<T>(items: &mut Vec<T>, indices: &[usize], to: usize) -> Result<(), String> {
+    let indices = checked_indices(indices, items.len())?;
+    if to > items.len() {
+        return Err("Track index out of bounds".to_string());
+    }
+
+    let selected: HashSet<usize> = indices.iter().copied().collect();
+    let mut moved = Vec::with_capacity(indices.len());
+    let mut rest = Vec::with_capacity(items.len() - indices.len());
+    for (index, item) in items.drain(..).enumerate() {
+        if selected.contains(&index) {
+            moved.push(item);
+        } else {
+            rest.push(item);
+        }
+    }
+
+    let position = to - indices.iter().filter(|index| **index < to).count();
+    rest.splice(position..position, moved);
+    *items = rest;
+    Ok(())
+}
+
+/// Insert `new_items` before `index`; `None` appends.
+pub fn insert_items<T>(
+    items: &mut Vec<T>,
+    index: Option<usize>,
+    new_items: Vec<T>,
+) -> Result<(), String> {
+    let index = index.unwrap_or(items.len());
+    if index > items.len() {
+        return Err("Track index out of bounds".to_string());
+    }
+    items.splice(index..index, new_items);
+    Ok(())
+}
+
+/// Remove the items at `indices`. Returns the number of removed items.
+pub fn remove_items<T>(items: &mut Vec<T>, indices: &[usize]) -> Result<usize, String> {
+    let indices = checked_indices(indices, items.len())?;
+    for index in indices.iter().rev() {
+        items.remove(*index);
+    }
+    Ok(indices.len())
+}
+
+/// Keep the first item of every key. Returns the number of removed items.
+pub fn remove_duplicate_items<T, K, F>(items: &mut Vec<T>, key: F) -> usize
+where
+    K: std::hash::Hash + Eq,
+    F: Fn(&T) -> K,
+{
+    let before = items.len();
+    let mut seen = HashSet::new();
+    items.retain(|item| seen.insert(key(item)));
+    before - items.len()
+}
+
+// SplitMix64, enough for shuffling without pulling in a random crate
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Fisher-Yates shuffle; the same seed always gives the same order.
+pub fn shuffle_items<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Stable sort of playlist tracks by library metadata. Tracks that are not in
+/// the library are compared by the fields stored in the playlist.
+pub fn sort_playlist_tracks(
+    tracks: &mut Vec<Track>,
+    library: &[LibraryTrack],
+    keys: &[TrackSortKey],
+) {
+    let lookup = LibraryLookup::new(library);
+    let mut keyed: Vec<(LibraryTrack, Track)> = tracks
+        .drain(..)
+        .map(|track| {
+            let metadata = lookup
+                .get(Path::new(&track.file_path))
+                .cloned()
+                .unwrap_or_else(|| LibraryTrack {
+                    title: track.title.clone(),
+                    artist: track.artist.clone(),
+                    album: track.album.clone(),
+                    duration: track.duration,
+                    file_path: track.file_path.clone(),
+                    ..Default::default()
+                });
+            (metadata, track)
+        })
+        .collect();
+
+    sort_by_track_keys(&mut keyed, keys, |(metadata, _)| metadata);
+    tracks.extend(keyed.into_iter().map(|(_, track)| track));
+}
+
+// Load all playlists, apply `edit` to one of them and save once
+async fn edit_playlist<F>(
+    app: &AppHandle,
+    id: &str,
+    label: &str,
+    edit: F,
+) -> Result<Playlist, String>
+where
+    F: FnOnce(&mut Playlist) -> Result<(), String>,
+{
+    let _lock = lock_playlists(app).await;
+    let mut playlists = load_playlists_from_file(app).await?;
+    let before = playlists.clone();
+    let playlist = playlists
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| "Playlist not found".to_string())?;
+    if playlist.smart.is_some() {
+        return Err("Smart playlists are updated automatically".to_string());
+    }
+
+    edit(playlist)?;
+    playlist.updated_at = chrono::Utc::now().to_rfc3339();
+    let updated = playlist.clone();
+    commit_playlists(app, label, &before, &playlists).await?;
+    Ok(updated)
+}
+
+#[tauri::command]
+pub async fn move_playlist_tracks(
+    app: AppHandle,
+    playlist_id: String,
+    track_indices: Vec<usize>,
+    to_index: usize,
+) -> Result<Playlist, String> {
+    edit_playlist(&app, &playlist_id, "Move tracks", |playlist| {
+        move_items(&mut playlist.tracks, &track_indices, to_index)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn insert_tracks_into_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    mut tracks: Vec<Track>,
+    index: Option<usize>,
+) -> Result<Playlist, String> {
+    let library = get_saved_library(app.clone()).await?;
+    let linker = TrackLinker::new(&library.tracks);
+    for track in tracks.iter_mut() {
+        linker.link(track);
+    }
+
+    edit_playlist(&app, &playlist_id, "Add to playlist", |playlist| {
+        insert_items(&mut playlist.tracks, index, tracks)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_tracks_from_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    track_indices: Vec<usize>,
+) -> Result<Playlist, String> {
+    edit_playlist(&app, &playlist_id, "Remove from playlist", |playlist| {
+        remove_items(&mut playlist.tracks, &track_indices).map(|_| ())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn remove_duplicate_playlist_tracks(
+    app: AppHandle,
+    playlist_id: String,
+) -> Result<Playlist, String> {
+    edit_playlist(&app, &playlist_id, "Remove duplicates", |playlist| {
+        remove_duplicate_items(&mut playlist.tracks, |track| track.file_path.clone());
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn sort_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    sort: Vec<TrackSortKey>,
+) -> Result<Playlist, String> {
+    let library = get_saved_library(app.clone()).await?;
+    edit_playlist(&app, &playlist_id, "Sort playlist", |playlist| {
+        sort_playlist_tracks(&mut playlist.tracks, &library.tracks, &sort);
+        Ok(())
+    })
+    .await
+}
+
+/// Shuffle the stored order of a playlist. A seed makes the order reproducible.
+#[tauri::command]
+pub async fn shuffle_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    seed: Option<u64>,
+) -> Result<Playlist, String> {
+    let seed = seed.unwrap_or_else(|| uuid::Uuid::new_v4().as_u128() as u64);
+    edit_playlist(&app, &playlist_id, "Shuffle playlist", |playlist| {
+        shuffle_items(&mut playlist.tracks, seed);
+        Ok(())
+    })
+    .await
+}
+
+/// Copy a playlist, including the rules of a smart playlist.
+#[tauri::command]
+pub async fn duplicate_playlist(
+    app: AppHandle,
+    playlist_id: String,
+    name: Option<String>,
+) -> Result<Playlist, String> {
+    let _lock = lock_playlists(&app).await;
+    let mut playlists = load_playlists_from_file(&app).await?;
+    let before = playlists.clone();
+    let source = playlists
+        .iter()
+        .find(|p| p.id == playlist_id)
+        .ok_or_else(|| "Playlist not found".to_string())?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let copy = Playlist {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.unwrap_or_else(|| format!("{} (Copy)", source.name)),
+        created_at: now.clone(),
+        updated_at: now,
+        ..source.clone()
+    };
+
+    playlists.push(copy.clone());
+    commit_playlists(&app, "Duplicate playlist", &before, &playlists).await?;
+    Ok(copy)
+}
+
+/// Combine playlists, in the given order, into a new regular playlist.
+/// The sources can be deleted in the same write.
+#[tauri::command]
+pub async fn merge_playlists(
+    app: AppHandle,
+    playlist_ids: Vec<String>,
+    name: String,
+    description: Option<String>,
+    remove_duplicates: Option<bool>,
+    delete_sources: Option<bool>,
+) -> Result<Playlist, String> {
+    let _lock = lock_playlists(&app).await;
+    let mut playlists = load_playlists_from_file(&app).await?;
+
+    let mut tracks = Vec::new();
+    for id in &playlist_ids {
+        let source = playlists
+            .iter()
+            .find(|p| &p.id == id)
+            .ok_or_else(|| format!("Playlist not found: {}", id))?;
+        tracks.extend(source.tracks.iter().cloned());
+    }
+    if remove_duplicates.unwrap_or(false) {
+        remove_duplicate_items(&mut tracks, |track| track.file_path.clone());
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let merged = Playlist {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description: description.unwrap_or_default(),
+        tracks,
+        created_at: now.clone(),
+        updated_at: now,
+        smart: None,
+    };
+
+    let before = playlists.clone();
+    if delete_sources.unwrap_or(false) {
+        // Deleted sources are kept in the journal and restored on undo
+        playlists.retain(|p| !playlist_ids.contains(&p.id));
+    }
+    playlists.push(merged.clone());
+    commit_playlists(&app, "Merge playlists", &before, &playlists).await?;
+    Ok(merged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_items() {
+        let mut items = vec!['a', 'b', 'c', 'd', 'e'];
+        // Drag "b" and "d" to the end
+        move_items(&mut items, &[3, 1], 5).unwrap();
+        assert_eq!(items, vec!['a', 'c', 'e', 'b', 'd']);
+
+        // Drop before "a"
+        move_items(&mut items, &[2], 0).unwrap();
+        assert_eq!(items, vec!['e', 'a', 'c', 'b', 'd']);
+
+        // Dropping onto its own position changes nothing
+        move_items(&mut items, &[1], 1).unwrap();
+        move_items(&mut items, &[1], 2).unwrap();
+        assert_eq!(items, vec!['e', 'a', 'c', 'b', 'd']);
+
+        assert!(move_items(&mut items, &[5], 0).is_err());
+        assert!(move_items(&mut items, &[0], 6).is_err());
+    }
+
+    #[test]
+    fn test_insert_and_remove_items() {
+        let mut items = vec![1, 2, 3];
+        insert_items(&mut items, Some(1), vec![7, 8]).unwrap();
+        assert_eq!(items, vec![1, 7, 8, 2, 3]);
+        insert_items(&mut items, None, vec![9]).unwrap();
+        assert_eq!(items, vec![1, 7, 8, 2, 3, 9]);
+        assert!(insert_items(&mut items, Some(7), vec![0]).is_err());
+
+        assert_eq!(remove_items(&mut items, &[5, 0, 0, 2]).unwrap(), 3);
+        assert_eq!(items, vec![7, 2, 3]);
+        // Nothing is removed when one index is invalid
+        assert!(remove_items(&mut items, &[0, 3]).is_err());
+        assert_eq!(items.len(), 3);
+
+        let mut items = vec![1, 2, 1, 3, 2];
+        assert_eq!(remove_duplicate_items(&mut items, |i| *i), 2);
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_shuffle_and_sort() {
+        let original: Vec<u32> = (0..20).collect();
+        let mut first = original.clone();
+        let mut second = original.clone();
+        shuffle_items(&mut first, 42);
+        shuffle_items(&mut second, 42);
+        assert_eq!(first, second);
+        assert_ne!(first, original);
+        first.sort_unstable();
+        assert_eq!(first, original);
+
+        let track = |title: &str, path: &str| Track {
+            title: title.to_string(),
+            file_path: path.to_string(),
+            ..Default::default()
+        };
+        let library = vec![LibraryTrack {
+            title: "Zebra".to_string(),
+            file_path: "/music/a.mp3".to_string(),
+            ..Default::default()
+        }];
+        // The stale title "Alpha" is ignored in favour of the library's
+        let mut tracks = vec![
+            track("Alpha", "/music/a.mp3"),
+            track("Mango", "/music/b.mp3"),
+        ];
+        let keys: Vec<TrackSortKey> =
+            serde_json::from_str(r#"[{"field":"Title","direction":"Ascending"}]"#).unwrap();
+        sort_playlist_tracks(&mut tracks, &library, &keys);
+        assert_eq!(tracks[0].title, "Mango");
+        assert_eq!(tracks[1].title, "Alpha");
+    }
+}
@@ -0,0 +1,688 @@
+use super::*;
+use crate::library::{get_saved_library, LibraryTrack};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use std::path::{Component, Path};
+
+/// One item of a playlist file, before it is matched to a local file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    /// Path or URL exactly as written in the file
+    pub location: Option<String>,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<f64>,
+    /// Canonical identifier such as a MusicBrainz URL (XSPF)
+    pub identifier: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedPlaylist {
+    pub name: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+}
+
+/// Supported playlist file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Pls,
+    Xspf,
+    Wpl,
+}
+
+impl PlaylistFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "m3u" => Ok(PlaylistFormat::M3u),
+            "m3u8" => Ok(PlaylistFormat::M3u8),
+            "pls" => Ok(PlaylistFormat::Pls),
+            "xspf" => Ok(PlaylistFormat::Xspf),
+            "wpl" => Ok(PlaylistFormat::Wpl),
+            _ => Err(format!("Unsupported playlist format: .{}", extension)),
+        }
+    }
+
+    // Formats whose specification requires UTF-8 (the XML formats are
+    // written with a UTF-8 declaration)
+    fn requires_utf8(self) -> bool {
+        matches!(
+            self,
+            PlaylistFormat::M3u8 | PlaylistFormat::Xspf | PlaylistFormat::Wpl
+        )
+    }
+
+    pub fn parse(self, content: &str) -> Result<ParsedPlaylist, String> {
+        match self {
+            PlaylistFormat::M3u | PlaylistFormat::M3u8 => Ok(parse_m3u(content)),
+            PlaylistFormat::Pls => Ok(parse_pls(content)),
+            PlaylistFormat::Xspf => parse_xspf(content),
+            PlaylistFormat::Wpl => parse_wpl(content),
+        }
+    }
+
+    pub fn write(self, name: &str, entries: &[PlaylistEntry]) -> String {
+        match self {
+            PlaylistFormat::M3u | PlaylistFormat::M3u8 => write_m3u(name, entries),
+            PlaylistFormat::Pls => write_pls(entries),
+            PlaylistFormat::Xspf => write_xspf(name, entries),
+            PlaylistFormat::Wpl => write_wpl(name, entries),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PathStyle {
+    #[default]
+    Absolute,
+    /// Relative to the playlist file, falling back to absolute paths for
+    /// files on another drive
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub path_style: PathStyle,
+    pub line_ending: LineEnding,
+    /// WHATWG encoding label such as "utf-8", "windows-1252" or "gbk"
+    pub encoding: String,
+    /// Write a byte order mark (UTF-8 only)
+    pub bom: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            path_style: PathStyle::default(),
+            line_ending: LineEnding::default(),
+            encoding: "utf-8".to_string(),
+            bom: false,
+        }
+    }
+}
+
+/// Entry that could not be matched to a local file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnresolvedEntry {
+    /// Position in the playlist file
+    pub index: usize,
+    pub entry: PlaylistEntry,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistImport {
+    pub playlist: Playlist,
+    pub unresolved: Vec<UnresolvedEntry>,
+}
+
+/// Decode playlist bytes. UTF-8 (with or without BOM) is used when valid,
+/// otherwise the legacy encoding is detected.
+pub fn decode_playlist_text(bytes: &[u8], format: PlaylistFormat) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return encoding
+            .decode_without_bom_handling(&bytes[bom_length..])
+            .0
+            .into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+    if format.requires_utf8() {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// Encode exported playlist text with the requested line endings and encoding.
+pub fn encode_playlist_text(
+    text: &str,
+    format: PlaylistFormat,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(options.encoding.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", options.encoding))?;
+    if format.requires_utf8() && encoding != UTF_8 {
+        return Err(format!("{:?} playlists must be UTF-8", format));
+    }
+
+    let text = match options.line_ending {
+        LineEnding::Lf => text.to_string(),
+        LineEnding::CrLf => text.replace('\n', "\r\n"),
+    };
+
+    let mut bytes = Vec::new();
+    if options.bom && encoding == UTF_8 {
+        bytes.extend_from_slice(b"\xEF\xBB\xBF");
+    }
+    let (encoded, _, had_errors) = encoding.encode(&text);
+    if had_errors {
+        return Err(format!(
+            "Some titles or paths cannot be represented in {}",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// Split an "Artist - Title" display string as used by M3U and PLS.
+pub(crate) fn split_display_title(display: &str, entry: &mut PlaylistEntry) {
+    let display = display.trim();
+    if display.is_empty() {
+        return;
+    }
+    match display.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            entry.artist = Some(artist.trim().to_string());
+            entry.title = Some(title.trim().to_string());
+        }
+        _ => entry.title = Some(display.to_string()),
+    }
+}
+
+/// "Artist - Title" display string for M3U and PLS.
+pub(crate) fn display_title(entry: &PlaylistEntry) -> String {
+    match (&entry.artist, &entry.title) {
+        (Some(artist), Some(title)) => format!("{} - {}", artist, title),
+        (None, Some(title)) => title.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Decode `%XX` escapes of URIs.
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Percent-encode a path for use in a URI, keeping `/` separators.
+pub(crate) fn percent_encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' | b':' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+// Resolve `.` and `..` without touching the file system
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Turn a playlist location into a local path. Relative paths are resolved
+/// against the directory of the playlist file; remote URLs return `None`.
+pub fn resolve_location(location: &str, base_dir: &Path) -> Option<PathBuf> {
+    let location = location.trim();
+    if location.is_empty() {
+        return None;
+    }
+
+    let path = if let Some(rest) = location
+        .strip_prefix("file://")
+        .or_else(|| location.strip_prefix("FILE://"))
+    {
+        // file:///C:/Music -> C:/Music, file://localhost/music -> /music
+        let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+        let decoded = percent_decode(rest);
+        let is_drive = decoded.len() > 2 && decoded.as_bytes()[2] == b':';
+        if cfg!(windows) && is_drive {
+            decoded[1..].to_string()
+        } else {
+            decoded
+        }
+    } else if location.contains("://") {
+        return None;
+    } else {
+        location.to_string()
+    };
+
+    // Playlists written on Windows use backslashes
+    let path = if cfg!(windows) {
+        path
+    } else {
+        path.replace('\\', "/")
+    };
+
+    let path = Path::new(&path);
+    Some(normalize_path(&if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base_dir.join(path)
+    }))
+}
+
+/// Path of `target` relative to `base_dir`, or `None` when they don't share
+/// a root (e.g. different drives).
+pub fn relative_path(target: &Path, base_dir: &Path) -> Option<PathBuf> {
+    let target = normalize_path(target);
+    let base = normalize_path(base_dir);
+    let target_components: Vec<Component> = target.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+
+    match (target_components.first(), base_components.first()) {
+        (Some(a), Some(b)) if a == b => {}
+        _ => return None,
+    }
+
+    let common = target_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &target_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    Some(relative)
+}
+
+/// Library tracks indexed by path, with a case-insensitive fallback for
+/// playlists written on case-insensitive file systems.
+pub struct LibraryLookup<'a> {
+    tracks: &'a [LibraryTrack],
+    exact: HashMap<&'a str, &'a LibraryTrack>,
+    folded: HashMap<String, &'a LibraryTrack>,
+}
+
+impl<'a> LibraryLookup<'a> {
+    pub fn new(tracks: &'a [LibraryTrack]) -> Self {
+        Self {
+            tracks,
+            exact: tracks.iter().map(|t| (t.file_path.as_str(), t)).collect(),
+            folded: tracks
+                .iter()
+                .map(|t| (t.file_path.to_lowercase(), t))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&'a LibraryTrack> {
+        let path = path.to_string_lossy();
+        self.exact
+            .get(path.as_ref())
+            .or_else(|| self.folded.get(&path.to_lowercase()))
+            .copied()
+    }
+
+    /// Find a track by the MusicBrainz recording or track id in an XSPF
+    /// identifier such as `https://musicbrainz.org/recording/<id>`.
+    pub fn find_by_identifier(&self, entry: &PlaylistEntry) -> Option<&'a LibraryTrack> {
+        let identifier = entry.identifier.as_deref()?.trim_end_matches('/');
+        let id = identifier.rsplit(['/', ':']).next()?;
+        if id.is_empty() {
+            return None;
+        }
+        self.tracks.iter().find(|track| {
+            let ids = &track.musicbrainz;
+            [&ids.recording_id, &ids.track_id]
+                .iter()
+                .any(|value| value.as_deref().is_some_and(|v| v.eq_ignore_ascii_case(id)))
+        })
+    }
+}
+
+// Playlist track for a file that exists but isn't in the library, using the
+// metadata from the playlist file
+pub(crate) fn track_from_entry(entry: &PlaylistEntry, path: &Path) -> Track {
+    Track {
+        track_id: None,
+        title: entry.title.clone().unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Unknown")
+                .to_string()
+        }),
+        artist: entry
+            .artist
+            .clone()
+            .unwrap_or_else(|| "Unknown Artist".to_string()),
+        album: entry
+            .album
+            .clone()
+            .unwrap_or_else(|| "Unknown Album".to_string()),
+        duration: entry.duration.unwrap_or(0.0),
+        file_path: path.to_string_lossy().to_string(),
+        missing: false,
+    }
+}
+
+/// Match parsed entries to library tracks or existing files. Only paths and
+/// confident metadata matches are used; everything else is reported.
+pub fn resolve_entries(
+    entries: &[PlaylistEntry],
+    base_dir: &Path,
+    resolver: &PlaylistResolver,
+) -> (Vec<Track>, Vec<UnresolvedEntry>) {
+    let mut tracks = Vec::new();
+    let mut unresolved = Vec::new();
+
+    for resolution in resolver.resolve_all(entries, base_dir) {
+        if let Some(file_path) = &resolution.file_path {
+            tracks.push(resolver.track(&resolution.entry, file_path));
+            continue;
+        }
+
+        let location = resolution.entry.location.as_deref();
+        let reason = match location.map(|location| (location, resolve_location(location, base_dir)))
+        {
+            None => "No location".to_string(),
+            Some((location, None)) => format!("Unsupported location: {}", location),
+            Some((_, Some(path))) => format!("File not found: {}", path.to_string_lossy()),
+        };
+        let reason = match resolution.status {
+            ResolutionStatus::Ambiguous => format!(
+                "{} and {} possible library tracks",
+                reason,
+                resolution.candidates.len()
+            ),
+            _ => format!("{} and no matching library track", reason),
+        };
+        unresolved.push(UnresolvedEntry {
+            index: resolution.index,
+            entry: resolution.entry,
+            reason,
+        });
+    }
+
+    (tracks, unresolved)
+}
+
+/// Playlist entries for export, with locations in the requested style.
+pub fn export_entries(
+    tracks: &[Track],
+    base_dir: &Path,
+    path_style: PathStyle,
+) -> Vec<PlaylistEntry> {
+    tracks
+        .iter()
+        .map(|track| {
+            let path = Path::new(&track.file_path);
+            let location = match path_style {
+                PathStyle::Absolute => None,
+                PathStyle::Relative => relative_path(path, base_dir),
+            }
+            .unwrap_or_else(|| path.to_path_buf());
+
+            let known = |value: &str, unknown: &str| {
+                Some(value.to_string()).filter(|v| !v.is_empty() && v != unknown)
+            };
+            PlaylistEntry {
+                location: Some(location.to_string_lossy().to_string()),
+                title: known(&track.title, ""),
+                artist: known(&track.artist, "Unknown Artist"),
+                album: known(&track.album, "Unknown Album"),
+                duration: Some(track.duration).filter(|d| *d > 0.0),
+                identifier: None,
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn parent_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Import a playlist file as a new playlist. Entries that can't be matched to
+/// a local file are reported and left out.
+#[tauri::command]
+pub async fn import_playlist(
+    app: AppHandle,
+    path: String,
+    name: Option<String>,
+) -> Result<PlaylistImport, String> {
+    let file_path = PathBuf::from(&path);
+    let format = PlaylistFormat::from_path(&file_path)?;
+    let bytes = fs::read(&file_path).map_err(|e| format!("Failed to read playlist: {}", e))?;
+    let parsed = format.parse(&decode_playlist_text(&bytes, format))?;
+
+    let library = get_saved_library(app.clone()).await?;
+    let base_dir = parent_dir(&file_path);
+    let entries = parsed.entries;
+    let (tracks, unresolved) = tokio::task::spawn_blocking(move || {
+        let resolver = PlaylistResolver::new(&library.tracks, ResolveOptions::default());
+        resolve_entries(&entries, &base_dir, &resolver)
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .or(parsed.name)
+        .or_else(|| {
+            file_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "Imported Playlist".to_string());
+    let playlist = Playlist {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description: String::new(),
+        tracks,
+        created_at: now.clone(),
+        updated_at: now,
+        smart: None,
+    };
+
+    save_playlist_to_file(&app, &playlist, "Import playlist").await?;
+    Ok(PlaylistImport {
+        playlist,
+        unresolved,
+    })
+}
+
+/// Export a playlist; the format follows the file extension.
+#[tauri::command]
+pub async fn export_playlist(
+    app: AppHandle,
+    id: String,
+    path: String,
+    options: Option<ExportOptions>,
+) -> Result<(), String> {
+    let options = options.unwrap_or_default();
+    let file_path = PathBuf::from(&path);
+    let format = PlaylistFormat::from_path(&file_path)?;
+
+    let playlists = load_linked_playlists(&app).await?;
+    let playlist = playlists
+        .iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| "Playlist not found".to_string())?;
+
+    let entries = export_entries(
+        &playlist.tracks,
+        &parent_dir(&file_path),
+        options.path_style,
+    );
+    let text = format.write(&playlist.name, &entries);
+    let bytes = encode_playlist_text(&text, format, &options)?;
+    fs::write(&file_path, bytes).map_err(|e| format!("Failed to write playlist: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_location() {
+        let base = Path::new("/music/playlists");
+        assert_eq!(
+            resolve_location("../Queen/01 Intro.flac", base),
+            Some(PathBuf::from("/music/Queen/01 Intro.flac"))
+        );
+        assert_eq!(
+            resolve_location("/abs/song.mp3", base),
+            Some(PathBuf::from("/abs/song.mp3"))
+        );
+        assert_eq!(
+            resolve_location("file:///music/My%20Song.mp3", base),
+            Some(PathBuf::from("/music/My Song.mp3"))
+        );
+        assert_eq!(resolve_location("http://example.com/a.mp3", base), None);
+        if !cfg!(windows) {
+            assert_eq!(
+                resolve_location("Sub\\Dir\\song.mp3", base),
+                Some(PathBuf::from("/music/playlists/Sub/Dir/song.mp3"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(
+                Path::new("/music/Queen/song.flac"),
+                Path::new("/music/playlists")
+            ),
+            Some(PathBuf::from("../Queen/song.flac"))
+        );
+        assert_eq!(
+            relative_path(Path::new("/music/a.mp3"), Path::new("/music")),
+            Some(PathBuf::from("a.mp3"))
+        );
+    }
+
+    #[test]
+    fn test_resolve_entries_by_metadata() {
+        let library = vec![
+            LibraryTrack {
+                title: "So What".to_string(),
+                artist: "Miles Davis".to_string(),
+                album: "Kind of Blue".to_string(),
+                duration: 562.0,
+                file_path: "/music/so-what.flac".to_string(),
+                ..Default::default()
+            },
+            LibraryTrack {
+                title: "So What".to_string(),
+                artist: "Miles Davis".to_string(),
+                album: "Live at Newport".to_string(),
+                duration: 720.0,
+                file_path: "/music/so-what-live.flac".to_string(),
+                ..Default::default()
+            },
+        ];
+        let resolver = PlaylistResolver::new(&library, ResolveOptions::default());
+        let entries = vec![
+            PlaylistEntry {
+                title: Some("so what".to_string()),
+                artist: Some("MILES DAVIS".to_string()),
+                duration: Some(719.0),
+                ..Default::default()
+            },
+            PlaylistEntry {
+                title: Some("Blue in Green".to_string()),
+                artist: Some("Miles Davis".to_string()),
+                ..Default::default()
+            },
+            PlaylistEntry {
+                location: Some("missing.mp3".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let (tracks, unresolved) = resolve_entries(&entries, Path::new("/nonexistent"), &resolver);
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].file_path, "/music/so-what-live.flac");
+        let indices: Vec<usize> = unresolved.iter().map(|u| u.index).collect();
+        assert_eq!(indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let text = "#EXTM3U\n#EXTINF:1,Björk - Jóga\n/music/jóga.mp3\n";
+        let options = ExportOptions {
+            line_ending: LineEnding::CrLf,
+            encoding: "windows-1252".to_string(),
+            ..Default::default()
+        };
+        let bytes = encode_playlist_text(text, PlaylistFormat::M3u, &options).unwrap();
+        assert!(bytes.windows(2).any(|w| w == b"\r\n"));
+        assert!(std::str::from_utf8(&bytes).is_err());
+        assert_eq!(
+            decode_playlist_text(&bytes, PlaylistFormat::M3u),
+            text.replace('\n', "\r\n")
+        );
+
+        // M3U8 is always UTF-8
+        assert!(encode_playlist_text(text, PlaylistFormat::M3u8, &options).is_err());
+        let chinese = ExportOptions {
+            encoding: "windows-1252".to_string(),
+            ..Default::default()
+        };
+        assert!(encode_playlist_text("晴天", PlaylistFormat::M3u, &chinese).is_err());
+
+        let bom = ExportOptions {
+            bom: true,
+            ..Default::default()
+        };
+        let bytes = encode_playlist_text(text, PlaylistFormat::M3u8, &bom).unwrap();
+        assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+        assert_eq!(decode_playlist_text(&bytes, PlaylistFormat::M3u8), text);
+    }
+}
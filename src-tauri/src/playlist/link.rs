@@ -0,0 +1,136 @@
+use super::*;
+use crate::library::LibraryTrack;
+use std::path::Path;
+
+/// Joins playlist entries to library tracks, by ID first and by path for
+/// entries without a (valid) reference.
+pub struct TrackLinker<'a> {
+    by_id: HashMap<&'a str, &'a LibraryTrack>,
+    by_path: LibraryLookup<'a>,
+}
+
+impl<'a> TrackLinker<'a> {
+    pub fn new(library: &'a [LibraryTrack]) -> Self {
+        Self {
+            by_id: library
+                .iter()
+                .map(|track| (track.id.as_str(), track))
+                .collect(),
+            by_path: LibraryLookup::new(library),
+        }
+    }
+
+    /// Refresh the entry from its library track, or mark it missing and keep
+    /// the snapshot. Returns true when the reference itself changed.
+    pub fn link(&self, track: &mut Track) -> bool {
+        let found = track
+            .track_id
+            .as_deref()
+            .and_then(|id| self.by_id.get(id).copied())
+            .or_else(|| self.by_path.get(Path::new(&track.file_path)));
+
+        match found {
+            Some(library_track) => {
+                let relinked = track.track_id.as_deref() != Some(library_track.id.as_str());
+                *track = Track::from(library_track);
+                relinked
+            }
+            None => {
+                track.missing = true;
+                false
+            }
+        }
+    }
+
+    /// Link every entry of the playlists. Returns true when any reference changed.
+    pub fn link_playlists(&self, playlists: &mut [Playlist]) -> bool {
+        let mut changed = false;
+        for track in playlists.iter_mut().flat_map(|p| p.tracks.iter_mut()) {
+            changed |= self.link(track);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn library_track(id: &str, title: &str, path: &str) -> LibraryTrack {
+        test_support::track(id).title(title).path(path).build()
+    }
+
+    #[test]
+    fn test_link_playlist_tracks() {
+        // Entries as written before playlists stored references
+        let mut tracks: Vec<Track> = serde_json::from_str(
+            r#"[
+                {"title":"Old Title","artist":"A","album":"B","duration":1.0,"file_path":"/music/a.flac"},
+                {"title":"Gone","artist":"A","album":"B","duration":1.0,"file_path":"/music/gone.flac"}
+            ]"#,
+        )
+        .unwrap();
+        let library = vec![library_track("1", "New Title", "/music/a.flac")];
+        let linker = TrackLinker::new(&library);
+
+        // Migration links by path
+        assert!(linker.link(&mut tracks[0]));
+        assert_eq!(tracks[0].track_id.as_deref(), Some("1"));
+        assert_eq!(tracks[0].title, "New Title");
+        assert!(!tracks[0].missing);
+
+        assert!(!linker.link(&mut tracks[1]));
+        assert!(tracks[1].missing);
+        assert_eq!(tracks[1].title, "Gone");
+
+        // A moved and retagged file keeps its reference
+        let library = vec![library_track("1", "Retagged", "/elsewhere/a.flac")];
+        assert!(!TrackLinker::new(&library).link(&mut tracks[0]));
+        assert_eq!(tracks[0].title, "Retagged");
+        assert_eq!(tracks[0].file_path, "/elsewhere/a.flac");
+
+        // After the library was rebuilt with new IDs the path relinks it
+        let library = vec![library_track("2", "Retagged", "/elsewhere/a.flac")];
+        assert!(TrackLinker::new(&library).link(&mut tracks[0]));
+        assert_eq!(tracks[0].track_id.as_deref(), Some("2"));
+
+        let json = serde_json::to_string(&tracks[0]).unwrap();
+        assert!(json.contains(r#""track_id":"2""#));
+        assert!(!json.contains("missing"));
+    }
+
+    #[test]
+    fn test_link_playlists_reports_migrations_only() {
+        let library = vec![
+            library_track("1", "One", "/music/one.flac"),
+            library_track("2", "Two", "/music/two.flac"),
+        ];
+        let linker = TrackLinker::new(&library);
+        let mut playlists: Vec<Playlist> = serde_json::from_str(
+            r#"[
+                {"id":"a","name":"A","description":"","created_at":"","updated_at":"","tracks":[
+                    {"track_id":"1","title":"Stale","artist":"","album":"","duration":0.0,"file_path":"/old/one.flac"}
+                ]},
+                {"id":"b","name":"B","description":"","created_at":"","updated_at":"","tracks":[
+                    {"title":"Two","artist":"","album":"","duration":0.0,"file_path":"/music/two.flac"},
+                    {"title":"Gone","artist":"","album":"","duration":0.0,"file_path":"/music/gone.flac","missing":false}
+                ]}
+            ]"#,
+        )
+        .unwrap();
+
+        // Only the entry without a reference counts as a migration
+        assert!(linker.link_playlists(&mut playlists));
+        assert_eq!(playlists[0].tracks[0].title, "One");
+        assert_eq!(playlists[1].tracks[0].track_id.as_deref(), Some("2"));
+        assert!(playlists[1].tracks[1].missing);
+        assert!(!linker.link_playlists(&mut playlists));
+
+        // The missing flag is written for the frontend but never read back
+        let json = serde_json::to_string(&playlists).unwrap();
+        let reread: Vec<Playlist> = serde_json::from_str(&json).unwrap();
+        assert!(json.contains(r#""missing":true"#));
+        assert!(!reread[1].tracks[1].missing);
+    }
+}
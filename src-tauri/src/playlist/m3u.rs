@@ -0,0 +1,143 @@
+use super::*;
+
+/// Parse an M3U/M3U8 playlist. Plain lists without `#EXTM3U` are accepted;
+/// `#EXTINF`, `#EXTART`, `#EXTALB` and `#PLAYLIST` are read when present.
+pub fn parse_m3u(content: &str) -> ParsedPlaylist {
+    let mut parsed = ParsedPlaylist::default();
+    let mut pending = PlaylistEntry::default();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(directive) = line.strip_prefix('#') {
+            let (key, value) = directive.split_once(':').unwrap_or((directive, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_uppercase().as_str() {
+                "EXTINF" => parse_extinf(value, &mut pending),
+                "EXTART" if !value.is_empty() => pending.artist = Some(value.to_string()),
+                "EXTALB" if !value.is_empty() => pending.album = Some(value.to_string()),
+                "PLAYLIST" if !value.is_empty() => parsed.name = Some(value.to_string()),
+                // Comments and unknown directives
+                _ => {}
+            }
+            continue;
+        }
+
+        pending.location = Some(line.to_string());
+        parsed.entries.push(std::mem::take(&mut pending));
+    }
+
+    parsed
+}
+
+// `#EXTINF:<seconds> [attributes],<artist> - <title>`
+fn parse_extinf(value: &str, entry: &mut PlaylistEntry) {
+    let (info, display) = value.split_once(',').unwrap_or((value, ""));
+    entry.duration = info
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .filter(|seconds| *seconds > 0.0);
+
+    split_display_title(display, entry);
+}
+
+/// Write an extended M3U playlist. Entries without a location are skipped.
+pub fn write_m3u(name: &str, entries: &[PlaylistEntry]) -> String {
+    let mut lines = vec!["#EXTM3U".to_string()];
+    if !name.trim().is_empty() {
+        lines.push(format!("#PLAYLIST:{}", name.trim()));
+    }
+
+    for entry in entries {
+        let Some(location) = &entry.location else {
+            continue;
+        };
+        let duration = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+        lines.push(format!("#EXTINF:{},{}", duration, display_title(entry)));
+        if let Some(album) = &entry.album {
+            lines.push(format!("#EXTALB:{}", album));
+        }
+        lines.push(location.clone());
+    }
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_extended_m3u() {
+        let content = "\u{feff}#EXTM3U\r\n#PLAYLIST:Road Trip\r\n\
+            #EXTINF:354,Queen - Bohemian Rhapsody\r\n\
+            #EXTALB:A Night at the Opera\r\n\
+            Queen/Bohemian Rhapsody.flac\r\n\
+            \r\n\
+            # a comment\r\n\
+            #EXTINF:-1 tvg-id=\"x\",Untitled Stream\r\n\
+            http://radio.example.com/stream\r\n\
+            /music/plain.mp3\r\n";
+        let parsed = parse_m3u(content.trim_start_matches('\u{feff}'));
+
+        assert_eq!(parsed.name.as_deref(), Some("Road Trip"));
+        assert_eq!(parsed.entries.len(), 3);
+
+        let first = &parsed.entries[0];
+        assert_eq!(
+            first.location.as_deref(),
+            Some("Queen/Bohemian Rhapsody.flac")
+        );
+        assert_eq!(first.artist.as_deref(), Some("Queen"));
+        assert_eq!(first.title.as_deref(), Some("Bohemian Rhapsody"));
+        assert_eq!(first.album.as_deref(), Some("A Night at the Opera"));
+        assert_eq!(first.duration, Some(354.0));
+
+        let stream = &parsed.entries[1];
+        assert_eq!(stream.duration, None);
+        assert_eq!(stream.title.as_deref(), Some("Untitled Stream"));
+        assert_eq!(stream.artist, None);
+
+        // Metadata doesn't leak into plain entries
+        assert_eq!(
+            parsed.entries[2],
+            PlaylistEntry {
+                location: Some("/music/plain.mp3".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let entries = vec![
+            PlaylistEntry {
+                location: Some("a/one.mp3".to_string()),
+                title: Some("One".to_string()),
+                artist: Some("U2".to_string()),
+                album: Some("Achtung Baby".to_string()),
+                duration: Some(276.4),
+                ..Default::default()
+            },
+            PlaylistEntry {
+                location: Some("two.flac".to_string()),
+                ..Default::default()
+            },
+        ];
+        let content = write_m3u("Mix", &entries);
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "#EXTM3U");
+        assert_eq!(lines[2], "#EXTINF:276,U2 - One");
+        assert_eq!(lines[5], "#EXTINF:-1,");
+
+        let parsed = parse_m3u(&content);
+        assert_eq!(parsed.name.as_deref(), Some("Mix"));
+        assert_eq!(parsed.entries[0].title.as_deref(), Some("One"));
+        assert_eq!(parsed.entries[0].album.as_deref(), Some("Achtung Baby"));
+        assert_eq!(parsed.entries[1].location.as_deref(), Some("two.flac"));
+    }
+}
@@ -1,16 +1,74 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod edit;
+pub mod io;
+pub mod link;
+pub mod m3u;
+pub mod pls;
+pub mod resolve;
+pub mod smart;
+pub mod wpl;
+pub mod xspf;
+
+pub use edit::*;
+pub use io::*;
+pub use link::*;
+pub use m3u::*;
+pub use pls::*;
+pub use resolve::*;
+pub use smart::*;
+pub use wpl::*;
+pub use xspf::*;
+
+/// Write lock for playlists.json
+///
+/// Commands, history and the smart playlist refresh all load, modify and save
+/// the whole file; holding this lock keeps their writes from overwriting each
+/// other. When both are needed, take the library lock first.
+pub type PlaylistLockState = Arc<tokio::sync::Mutex<()>>;
+
+/// Acquire the playlist write lock.
+pub(crate) async fn lock_playlists(app: &AppHandle) -> tokio::sync::OwnedMutexGuard<()> {
+    app.state::<PlaylistLockState>()
+        .inner()
+        .clone()
+        .lock_owned()
+        .await
+}
+
+/// Playlist entry: a reference to a library track plus a snapshot of its
+/// metadata, used when the track is no longer in the library.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Track {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<String>,
     pub title: String,
     pub artist: String,
     pub album: String,
     pub duration: f64,
     pub file_path: String,
+    /// Set on read when the referenced track can't be found in the library
+    #[serde(skip_deserializing, skip_serializing_if = "std::ops::Not::not")]
+    pub missing: bool,
+}
+
+impl From<&crate::library::LibraryTrack> for Track {
+    fn from(track: &crate::library::LibraryTrack) -> Self {
+        Self {
+            track_id: Some(track.id.clone()),
+            title: track.title.clone(),
+            artist: track.artist.clone(),
+            album: track.album.clone(),
+            duration: track.duration,
+            file_path: track.file_path.clone(),
+            missing: false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +79,9 @@ pub struct Playlist {
     pub tracks: Vec<Track>,
     pub created_at: String,
     pub updated_at: String,
+    /// Rules of a smart playlist; `tracks` then holds the latest evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub smart: Option<SmartPlaylist>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,40 +139,53 @@ pub async fn create_playlist(
         tracks: Vec::new(),
         created_at: now.clone(),
         updated_at: now,
+        smart: None,
     };
 
     // Save to file (you might want to implement persistent storage)
-    save_playlist_to_file(&app, &playlist).await?;
+    save_playlist_to_file(&app, &playlist, "Create playlist").await?;
 
     Ok(playlist)
 }
 
 #[tauri::command]
 pub async fn get_playlists(app: AppHandle) -> Result<Vec<Playlist>, String> {
-    load_playlists_from_file(&app).await
+    load_linked_playlists(&app).await
 }
 
 #[tauri::command]
 pub async fn delete_playlist(app: AppHandle, id: String) -> Result<(), String> {
+    let _lock = lock_playlists(&app).await;
     let mut playlists = load_playlists_from_file(&app).await?;
+    if !playlists.iter().any(|p| p.id == id) {
+        return Ok(());
+    }
+
+    // The journal keeps the deleted playlist so that it can be restored
+    let before = playlists.clone();
     playlists.retain(|p| p.id != id);
-    save_playlists_to_file(&app, &playlists).await?;
-    Ok(())
+    commit_playlists(&app, "Delete playlist", &before, &playlists).await
 }
 
 #[tauri::command]
 pub async fn add_track_to_playlist(
     app: AppHandle,
     playlist_id: String,
-    track: Track,
+    mut track: Track,
 ) -> Result<(), String> {
+    let _lock = lock_playlists(&app).await;
     let mut playlists = load_playlists_from_file(&app).await?;
+    let before = playlists.clone();
 
     if let Some(playlist) = playlists.iter_mut().find(|p| p.id == playlist_id) {
+        if playlist.smart.is_some() {
+            return Err("Smart playlists are updated automatically".to_string());
+        }
+        let library = crate::library::get_saved_library(app.clone()).await?;
+        TrackLinker::new(&library.tracks).link(&mut track);
         playlist.tracks.push(track);
         playlist.updated_at = chrono::Utc::now().to_rfc3339();
-        save_playlists_to_file(&app, &playlists).await?;
-        Ok(())
+        commit_playlists(&app, "Add to playlist", &before, &playlists).await
     } else {
         Err("Playlist not found".to_string())
     }
@@ -123,14 +197,18 @@ pub async fn remove_track_from_playlist(
     playlist_id: String,
     track_index: usize,
 ) -> Result<(), String> {
+    let _lock = lock_playlists(&app).await;
     let mut playlists = load_playlists_from_file(&app).await?;
+    let before = playlists.clone();
 
     if let Some(playlist) = playlists.iter_mut().find(|p| p.id == playlist_id) {
+        if playlist.smart.is_some() {
+            return Err("Smart playlists are updated automatically".to_string());
+        }
         if track_index < playlist.tracks.len() {
             playlist.tracks.remove(track_index);
             playlist.updated_at = chrono::Utc::now().to_rfc3339();
-            save_playlists_to_file(&app, &playlists).await?;
-            Ok(())
+            commit_playlists(&app, "Remove from playlist", &before, &playlists).await
         } else {
             Err("Track index out of bounds".to_string())
         }
@@ -146,7 +224,9 @@ pub async fn update_playlist_info(
     name: Option<String>,
     description: Option<String>,
 ) -> Result<(), String> {
+    let _lock = lock_playlists(&app).await;
     let mut playlists = load_playlists_from_file(&app).await?;
+    let before = playlists.clone();
 
     if let Some(playlist) = playlists.iter_mut().find(|p| p.id == id) {
         if let Some(new_name) = name {
@@ -156,13 +236,71 @@ pub async fn update_playlist_info(
             playlist.description = new_description;
         }
         playlist.updated_at = chrono::Utc::now().to_rfc3339();
-        save_playlists_to_file(&app, &playlists).await?;
-        Ok(())
+        commit_playlists(&app, "Edit playlist", &before, &playlists).await
     } else {
         Err("Playlist not found".to_string())
     }
 }
 
+/// Rewrite playlist entries whose files were moved on disk.
+/// Returns the number of entries that were updated.
+pub async fn update_playlist_paths(
+    app: &AppHandle,
+    moves: &HashMap<String, String>,
+) -> Result<usize, String> {
+    let _lock = lock_playlists(app).await;
+    let mut playlists = load_playlists_from_file(app).await?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut updated = 0;
+
+    for playlist in playlists.iter_mut() {
+        let mut changed = false;
+        for track in playlist.tracks.iter_mut() {
+            if let Some(new_path) = moves.get(&track.file_path) {
+                track.file_path = new_path.clone();
+                changed = true;
+                updated += 1;
+            }
+        }
+        if changed {
+            playlist.updated_at = now.clone();
+        }
+    }
+
+    if updated > 0 {
+        save_playlists_to_file(app, &playlists).await?;
+    }
+    Ok(updated)
+}
+
+/// Remove playlist entries pointing at the given files.
+/// Returns the number of entries that were removed.
+pub async fn remove_playlist_paths(
+    app: &AppHandle,
+    paths: &HashSet<String>,
+) -> Result<usize, String> {
+    let _lock = lock_playlists(app).await;
+    let mut playlists = load_playlists_from_file(app).await?;
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut removed = 0;
+
+    for playlist in playlists.iter_mut() {
+        let before = playlist.tracks.len();
+        playlist
+            .tracks
+            .retain(|track| !paths.contains(&track.file_path));
+        if playlist.tracks.len() != before {
+            removed += before - playlist.tracks.len();
+            playlist.updated_at = now.clone();
+        }
+    }
+
+    if removed > 0 {
+        save_playlists_to_file(app, &playlists).await?;
+    }
+    Ok(removed)
+}
+
 // Helper functions for file operations
 async fn get_playlists_file_path(app: &AppHandle) -> Result<PathBuf, String> {
     let app_data_dir = app
@@ -179,7 +317,7 @@ async fn get_playlists_file_path(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(app_data_dir.join("playlists.json"))
 }
 
-async fn load_playlists_from_file(app: &AppHandle) -> Result<Vec<Playlist>, String> {
+pub(crate) async fn load_playlists_from_file(app: &AppHandle) -> Result<Vec<Playlist>, String> {
     let file_path = get_playlists_file_path(app).await?;
 
     if !file_path.exists() {
@@ -189,13 +327,37 @@ async fn load_playlists_from_file(app: &AppHandle) -> Result<Vec<Playlist>, Stri
     let content = fs::read_to_string(&file_path)
         .map_err(|e| format!("Failed to read playlists file: {}", e))?;
 
-    let playlists: Vec<Playlist> = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse playlists file: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse playlists file: {}", e))
+}
 
+/// Playlists joined to current library metadata, for display and export.
+/// Nothing is saved; references are migrated once by `upgrade_playlists`.
+pub(crate) async fn load_linked_playlists(app: &AppHandle) -> Result<Vec<Playlist>, String> {
+    let mut playlists = load_playlists_from_file(app).await?;
+    let library = crate::library::get_saved_library(app.clone()).await?;
+    TrackLinker::new(&library.tracks).link_playlists(&mut playlists);
     Ok(playlists)
 }
 
-async fn save_playlists_to_file(app: &AppHandle, playlists: &[Playlist]) -> Result<(), String> {
+/// Link entries written by older versions, which only have a snapshot, to
+/// library tracks by path. Run once at startup after the library upgrade.
+pub async fn upgrade_playlists(app: &AppHandle) -> Result<(), String> {
+    let _lock = lock_playlists(app).await;
+    let mut playlists = load_playlists_from_file(app).await?;
+    if playlists.is_empty() {
+        return Ok(());
+    }
+    let library = crate::library::get_saved_library(app.clone()).await?;
+    if TrackLinker::new(&library.tracks).link_playlists(&mut playlists) {
+        save_playlists_to_file(app, &playlists).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn save_playlists_to_file(
+    app: &AppHandle,
+    playlists: &[Playlist],
+) -> Result<(), String> {
     let file_path = get_playlists_file_path(app).await?;
 
     let content = serde_json::to_string_pretty(playlists)
@@ -206,8 +368,26 @@ async fn save_playlists_to_file(app: &AppHandle, playlists: &[Playlist]) -> Resu
     Ok(())
 }
 
-async fn save_playlist_to_file(app: &AppHandle, playlist: &Playlist) -> Result<(), String> {
+// Save playlists changed by a user command and record the change so that it
+// can be undone
+async fn commit_playlists(
+    app: &AppHandle,
+    label: &str,
+    before: &[Playlist],
+    after: &[Playlist],
+) -> Result<(), String> {
+    save_playlists_to_file(app, after).await?;
+    crate::history::record_playlist_changes(app, label, before, after)
+}
+
+async fn save_playlist_to_file(
+    app: &AppHandle,
+    playlist: &Playlist,
+    label: &str,
+) -> Result<(), String> {
+    let _lock = lock_playlists(app).await;
     let mut playlists = load_playlists_from_file(app).await?;
+    let before = playlists.clone();
     playlists.push(playlist.clone());
-    save_playlists_to_file(app, &playlists).await
+    commit_playlists(app, label, &before, &playlists).await
 }
@@ -0,0 +1,115 @@
+use super::*;
+use std::collections::BTreeMap;
+
+/// Parse a PLS (Winamp) playlist. Entries are ordered by their number, not by
+/// their position in the file. Entries with a title but no `FileN` are kept
+/// without a location so that they are reported as unresolved.
+pub fn parse_pls(content: &str) -> ParsedPlaylist {
+    let mut entries: BTreeMap<usize, PlaylistEntry> = BTreeMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('[') || line.starts_with(';') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+
+        // File1, Title1, Length1, ...
+        let Some(digits) = key.find(|c: char| c.is_ascii_digit()) else {
+            continue;
+        };
+        let Ok(index) = key[digits..].parse::<usize>() else {
+            continue;
+        };
+        let entry = entries.entry(index).or_default();
+        match &key[..digits] {
+            "file" if !value.is_empty() => entry.location = Some(value.to_string()),
+            "title" => split_display_title(value, entry),
+            "length" => entry.duration = value.parse::<f64>().ok().filter(|seconds| *seconds > 0.0),
+            _ => {}
+        }
+    }
+
+    ParsedPlaylist {
+        name: None,
+        entries: entries
+            .into_values()
+            .filter(|entry| entry.location.is_some() || entry.title.is_some())
+            .collect(),
+    }
+}
+
+/// Write a PLS version 2 playlist. Entries without a location are skipped.
+pub fn write_pls(entries: &[PlaylistEntry]) -> String {
+    let mut lines = vec!["[playlist]".to_string()];
+    let mut count = 0;
+
+    for entry in entries {
+        let Some(location) = &entry.location else {
+            continue;
+        };
+        count += 1;
+        lines.push(format!("File{}={}", count, location));
+        let title = display_title(entry);
+        if !title.is_empty() {
+            lines.push(format!("Title{}={}", count, title));
+        }
+        let duration = entry.duration.map(|d| d.round() as i64).unwrap_or(-1);
+        lines.push(format!("Length{}={}", count, duration));
+    }
+
+    lines.push(format!("NumberOfEntries={}", count));
+    lines.push("Version=2".to_string());
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_write_pls() {
+        let content = "[playlist]\n\
+            File2=C:\\Music\\b.mp3\n\
+            Title2=Second\n\
+            File1=a.flac\n\
+            Title1=Miles Davis - So What\n\
+            Length1=562\n\
+            Length2=-1\n\
+            NumberOfEntries=2\n\
+            Version=2\n";
+        let parsed = parse_pls(content);
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].location.as_deref(), Some("a.flac"));
+        assert_eq!(parsed.entries[0].artist.as_deref(), Some("Miles Davis"));
+        assert_eq!(parsed.entries[0].title.as_deref(), Some("So What"));
+        assert_eq!(parsed.entries[0].duration, Some(562.0));
+        assert_eq!(parsed.entries[1].title.as_deref(), Some("Second"));
+        assert_eq!(parsed.entries[1].duration, None);
+
+        let written = write_pls(&parsed.entries);
+        assert!(written.contains("File2=C:\\Music\\b.mp3\n"));
+        assert!(written.contains("Title1=Miles Davis - So What\n"));
+        assert!(written.contains("NumberOfEntries=2\n"));
+        assert_eq!(parse_pls(&written), parsed);
+    }
+
+    #[test]
+    fn test_entry_without_file_is_kept() {
+        let content = "[playlist]\n\
+            File1=a.flac\n\
+            Title2=Lost Song\n\
+            Length2=200\n\
+            Length3=100\n\
+            NumberOfEntries=3\n";
+        let parsed = parse_pls(content);
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].location, None);
+        assert_eq!(parsed.entries[1].title.as_deref(), Some("Lost Song"));
+        assert_eq!(parsed.entries[1].duration, Some(200.0));
+    }
+}
@@ -0,0 +1,573 @@
+use super::*;
+use crate::library::normalize::normalize_text;
+use crate::library::{get_saved_library, LibraryTrack};
+use std::path::Path;
+
+// Weights of the compared fields; fields missing on either side are left out
+const TITLE_WEIGHT: f32 = 0.5;
+const ARTIST_WEIGHT: f32 = 0.3;
+const ALBUM_WEIGHT: f32 = 0.1;
+const DURATION_WEIGHT: f32 = 0.1;
+// Titles that only match after removing "(Remastered)" etc. score slightly lower
+const DECORATION_PENALTY: f32 = 0.95;
+// Suffixes after " - " that describe a version rather than the song
+const VERSION_WORDS: [&str; 9] = [
+    "remaster", "live", "version", "edit", "mix", "mono", "stereo", "demo", "acoustic",
+];
+
+/// Thresholds for matching playlist entries to library tracks by metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResolveOptions {
+    /// Candidates scoring below this (0-1) are not offered
+    pub min_score: f32,
+    /// The best candidate is accepted without confirmation at or above this score
+    pub confident_score: f32,
+    /// ...and when it leads the second best candidate by at least this much
+    pub confident_margin: f32,
+    /// Durations within this many seconds count as equal
+    pub duration_tolerance: f64,
+    pub max_candidates: usize,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self {
+            min_score: 0.6,
+            confident_score: 0.9,
+            confident_margin: 0.05,
+            duration_tolerance: 3.0,
+            max_candidates: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResolutionStatus {
+    /// The path exists locally
+    Path,
+    /// A single library track matches well enough
+    Confident,
+    /// Several or only weak candidates; the user has to choose
+    Ambiguous,
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchCandidate {
+    pub track: LibraryTrack,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryResolution {
+    /// Position in the playlist file
+    pub index: usize,
+    pub entry: PlaylistEntry,
+    pub status: ResolutionStatus,
+    /// File suggested for the entry (set for `Path` and `Confident`)
+    pub file_path: Option<String>,
+    pub candidates: Vec<MatchCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistResolution {
+    pub name: Option<String>,
+    pub entries: Vec<EntryResolution>,
+}
+
+/// Entry confirmed by the user, pointing at a local file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionChoice {
+    pub entry: PlaylistEntry,
+    pub file_path: String,
+}
+
+// Levenshtein similarity (0-1) of two normalized strings
+fn similarity(a: &str, b: &str) -> f32 {
+    if a == b {
+        return 1.0;
+    }
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f32 / a.len().max(b.len()) as f32
+}
+
+/// Remove bracketed parts and version suffixes: "Help! (Remastered 2009)" and
+/// "Help! - Live" become "Help!". Expects normalized text.
+pub fn strip_decorations(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '(' | '[' | '（' | '【' => depth += 1,
+            ')' | ']' | '）' | '】' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => stripped.push(c),
+            _ => {}
+        }
+    }
+
+    if let Some((song, suffix)) = stripped.split_once(" - ") {
+        if VERSION_WORDS.iter().any(|word| suffix.contains(word)) {
+            stripped = song.to_string();
+        }
+    }
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Main artist without "feat." guests. Expects normalized text.
+fn main_artist(artist: &str) -> &str {
+    [" feat. ", " feat ", " ft. ", " featuring "]
+        .iter()
+        .filter_map(|separator| artist.find(separator))
+        .min()
+        .map(|end| artist[..end].trim())
+        .unwrap_or(artist)
+}
+
+// Normalized title or album name, also kept without decorations
+struct PreparedTitle {
+    text: String,
+    stripped: String,
+}
+
+impl PreparedTitle {
+    fn new(text: &str) -> Self {
+        let text = normalize_text(text);
+        let stripped = strip_decorations(&text);
+        Self { text, stripped }
+    }
+}
+
+fn title_similarity(a: &PreparedTitle, b: &PreparedTitle) -> f32 {
+    let exact = similarity(&a.text, &b.text);
+    if exact == 1.0 {
+        return exact;
+    }
+    let stripped = similarity(&a.stripped, &b.stripped) * DECORATION_PENALTY;
+    exact.max(stripped)
+}
+
+// Library track with its comparison strings prepared once
+struct PreparedTrack<'a> {
+    track: &'a LibraryTrack,
+    title: PreparedTitle,
+    artists: Vec<String>,
+    album: PreparedTitle,
+}
+
+// Normalized metadata of a playlist entry
+struct PreparedEntry {
+    title: PreparedTitle,
+    artist: Option<String>,
+    album: Option<PreparedTitle>,
+    duration: Option<f64>,
+}
+
+impl PreparedEntry {
+    // Entries without a title fall back to the file name, minus a leading
+    // track number
+    fn new(entry: &PlaylistEntry) -> Option<Self> {
+        let title = entry.title.clone().or_else(|| {
+            let location = entry.location.as_deref()?.replace('\\', "/");
+            let stem = Path::new(&location).file_stem()?.to_str()?.to_string();
+            let stem = stem
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .trim_start_matches(['.', '-', '_', ' ']);
+            Some(stem.to_string())
+        })?;
+        let title = PreparedTitle::new(&title);
+        if title.text.is_empty() {
+            return None;
+        }
+
+        let normalized = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(normalize_text)
+                .filter(|v| !v.is_empty())
+        };
+        Some(Self {
+            title,
+            artist: normalized(&entry.artist),
+            album: entry
+                .album
+                .as_deref()
+                .map(PreparedTitle::new)
+                .filter(|album| !album.text.is_empty()),
+            duration: entry.duration.filter(|d| *d > 0.0),
+        })
+    }
+}
+
+// Weighted similarity of an entry and a track (0-1)
+fn score_match(entry: &PreparedEntry, track: &PreparedTrack, options: &ResolveOptions) -> f32 {
+    let mut total = TITLE_WEIGHT * title_similarity(&entry.title, &track.title);
+    let mut weights = TITLE_WEIGHT;
+
+    if let Some(artist) = &entry.artist {
+        let best = track
+            .artists
+            .iter()
+            .map(|name| similarity(artist, name).max(similarity(main_artist(artist), name)))
+            .fold(0.0, f32::max);
+        total += ARTIST_WEIGHT * best;
+        weights += ARTIST_WEIGHT;
+    }
+    if let Some(album) = &entry.album {
+        total += ALBUM_WEIGHT * title_similarity(album, &track.album);
+        weights += ALBUM_WEIGHT;
+    }
+    if let Some(duration) = entry.duration.filter(|_| track.track.duration > 0.0) {
+        // Full score within the tolerance, none beyond four times the tolerance
+        let tolerance = options.duration_tolerance.max(0.5);
+        let difference = (track.track.duration - duration).abs();
+        let score = 1.0 - ((difference - tolerance) / (3.0 * tolerance)).clamp(0.0, 1.0);
+        total += DURATION_WEIGHT * score as f32;
+        weights += DURATION_WEIGHT;
+    }
+
+    total / weights
+}
+
+/// Matches playlist entries against a library.
+pub struct PlaylistResolver<'a> {
+    lookup: LibraryLookup<'a>,
+    tracks: Vec<PreparedTrack<'a>>,
+    options: ResolveOptions,
+}
+
+impl<'a> PlaylistResolver<'a> {
+    pub fn new(tracks: &'a [LibraryTrack], options: ResolveOptions) -> Self {
+        let prepared = tracks
+            .iter()
+            .map(|track| PreparedTrack {
+                track,
+                title: PreparedTitle::new(&track.title),
+                artists: track
+                    .artist_names()
+                    .iter()
+                    .map(|name| normalize_text(name))
+                    .collect(),
+                album: PreparedTitle::new(&track.album),
+            })
+            .collect();
+        Self {
+            lookup: LibraryLookup::new(tracks),
+            tracks: prepared,
+            options,
+        }
+    }
+
+    /// Library tracks ranked by similarity to the entry's metadata.
+    pub fn candidates(&self, entry: &PlaylistEntry) -> Vec<MatchCandidate> {
+        let Some(prepared) = PreparedEntry::new(entry) else {
+            return Vec::new();
+        };
+
+        let mut scored: Vec<(f32, &LibraryTrack)> = self
+            .tracks
+            .iter()
+            .map(|track| (score_match(&prepared, track, &self.options), track.track))
+            .filter(|(score, _)| *score >= self.options.min_score)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.truncate(self.options.max_candidates.max(1));
+
+        scored
+            .into_iter()
+            .map(|(score, track)| MatchCandidate {
+                track: track.clone(),
+                score,
+            })
+            .collect()
+    }
+
+    /// Playlist track for a resolved file, with the library's metadata when
+    /// the file is in the library.
+    pub fn track(&self, entry: &PlaylistEntry, file_path: &str) -> Track {
+        let path = Path::new(file_path);
+        match self.lookup.get(path) {
+            Some(track) => Track::from(track),
+            None => track_from_entry(entry, path),
+        }
+    }
+
+    /// Resolve one entry: by path when the file exists, then by MusicBrainz
+    /// identifier, otherwise by metadata.
+    pub fn resolve(&self, index: usize, entry: &PlaylistEntry, base_dir: &Path) -> EntryResolution {
+        let local = entry
+            .location
+            .as_deref()
+            .and_then(|location| resolve_location(location, base_dir))
+            .and_then(|path| match self.lookup.get(&path) {
+                Some(track) => Some(track.file_path.clone()),
+                None => path.is_file().then(|| path.to_string_lossy().to_string()),
+            });
+        if let Some(file_path) = local {
+            return EntryResolution {
+                index,
+                entry: entry.clone(),
+                status: ResolutionStatus::Path,
+                file_path: Some(file_path),
+                candidates: Vec::new(),
+            };
+        }
+        if let Some(track) = self.lookup.find_by_identifier(entry) {
+            return EntryResolution {
+                index,
+                entry: entry.clone(),
+                status: ResolutionStatus::Confident,
+                file_path: Some(track.file_path.clone()),
+                candidates: vec![MatchCandidate {
+                    track: track.clone(),
+                    score: 1.0,
+                }],
+            };
+        }
+
+        let candidates = self.candidates(entry);
+        let status = match candidates.as_slice() {
+            [] => ResolutionStatus::Missing,
+            [best, rest @ ..] => {
+                let lead = rest.first().map(|second| best.score - second.score);
+                if best.score >= self.options.confident_score
+                    && lead.is_none_or(|lead| lead >= self.options.confident_margin)
+                {
+                    ResolutionStatus::Confident
+                } else {
+                    ResolutionStatus::Ambiguous
+                }
+            }
+        };
+        let file_path =
+            (status == ResolutionStatus::Confident).then(|| candidates[0].track.file_path.clone());
+
+        EntryResolution {
+            index,
+            entry: entry.clone(),
+            status,
+            file_path,
+            candidates,
+        }
+    }
+
+    pub fn resolve_all(&self, entries: &[PlaylistEntry], base_dir: &Path) -> Vec<EntryResolution> {
+        entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| self.resolve(index, entry, base_dir))
+            .collect()
+    }
+}
+
+/// Match the entries of a playlist file against the library without creating
+/// a playlist, so that the user can review ambiguous entries and misses.
+#[tauri::command]
+pub async fn resolve_playlist(
+    app: AppHandle,
+    path: String,
+    options: Option<ResolveOptions>,
+) -> Result<PlaylistResolution, String> {
+    let file_path = PathBuf::from(&path);
+    let format = PlaylistFormat::from_path(&file_path)?;
+    let bytes = fs::read(&file_path).map_err(|e| format!("Failed to read playlist: {}", e))?;
+    let parsed = format.parse(&decode_playlist_text(&bytes, format))?;
+
+    let library = get_saved_library(app).await?;
+    let base_dir = parent_dir(&file_path);
+    let entries = tokio::task::spawn_blocking(move || {
+        PlaylistResolver::new(&library.tracks, options.unwrap_or_default())
+            .resolve_all(&parsed.entries, &base_dir)
+    })
+    .await
+    .map_err(|e| format!("Resolve task failed: {}", e))?;
+
+    Ok(PlaylistResolution {
+        name: parsed.name.or_else(|| {
+            file_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+        }),
+        entries,
+    })
+}
+
+/// Create a playlist from the entries the user confirmed after `resolve_playlist`.
+#[tauri::command]
+pub async fn create_playlist_from_resolution(
+    app: AppHandle,
+    name: String,
+    description: Option<String>,
+    choices: Vec<ResolutionChoice>,
+) -> Result<Playlist, String> {
+    let library = get_saved_library(app.clone()).await?;
+    let lookup = LibraryLookup::new(&library.tracks);
+
+    let tracks = choices
+        .iter()
+        .map(|choice| {
+            let path = Path::new(&choice.file_path);
+            match lookup.get(path) {
+                Some(track) => Ok(Track::from(track)),
+                None if path.is_file() => Ok(track_from_entry(&choice.entry, path)),
+                None => Err(format!("File not found: {}", choice.file_path)),
+            }
+        })
+        .collect::<Result<Vec<Track>, String>>()?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let playlist = Playlist {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description: description.unwrap_or_default(),
+        tracks,
+        created_at: now.clone(),
+        updated_at: now,
+        smart: None,
+    };
+
+    save_playlist_to_file(&app, &playlist, "Import playlist").await?;
+    Ok(playlist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::test_support;
+
+    fn track(title: &str, artist: &str, album: &str, duration: f64) -> LibraryTrack {
+        test_support::track(&format!("{}-{}", title, album))
+            .title(title)
+            .artist(artist)
+            .album(album)
+            .duration(duration)
+            .path(format!("/library/{} - {}.flac", album, title))
+            .build()
+    }
+
+    fn entry(title: &str, artist: &str, duration: Option<f64>) -> PlaylistEntry {
+        PlaylistEntry {
+            location: Some(format!("D:\\Music\\{}.mp3", title)),
+            title: Some(title.to_string()),
+            artist: Some(artist.to_string()),
+            duration,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_similarity_helpers() {
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert!((similarity("kitten", "sitting") - (1.0 - 3.0 / 7.0)).abs() < 1e-6);
+        assert_eq!(similarity("", "abc"), 0.0);
+
+        assert_eq!(strip_decorations("help! (remastered 2009)"), "help!");
+        assert_eq!(strip_decorations("layla - live at msg"), "layla");
+        assert_eq!(strip_decorations("a - b"), "a - b");
+        assert_eq!(
+            main_artist("daft punk feat. pharrell williams"),
+            "daft punk"
+        );
+    }
+
+    #[test]
+    fn test_resolve_statuses() {
+        let library = vec![
+            track("So What", "Miles Davis", "Kind of Blue", 562.0),
+            track("Bohemian Rhapsody", "Queen", "A Night at the Opera", 354.0),
+            track("Bohemian Rhapsody", "Queen", "Greatest Hits", 355.0),
+            track("Get Lucky", "Daft Punk", "Random Access Memories", 369.0),
+        ];
+        let resolver = PlaylistResolver::new(&library, ResolveOptions::default());
+        let base = Path::new("/nonexistent");
+
+        let so_what = resolver.resolve(
+            0,
+            &entry("So What (Remastered)", "Miles Davis", Some(563.0)),
+            base,
+        );
+        assert_eq!(so_what.status, ResolutionStatus::Confident);
+        assert_eq!(
+            so_what.file_path.as_deref(),
+            Some("/library/Kind of Blue - So What.flac")
+        );
+
+        // Same song on two albums
+        let rhapsody = resolver.resolve(1, &entry("Bohemian Rhapsody", "Queen", None), base);
+        assert_eq!(rhapsody.status, ResolutionStatus::Ambiguous);
+        assert_eq!(rhapsody.candidates.len(), 2);
+        assert_eq!(rhapsody.file_path, None);
+
+        let lucky = resolver.resolve(
+            2,
+            &entry(
+                "Get Lucky",
+                "Daft Punk feat. Pharrell Williams",
+                Some(248.0),
+            ),
+            base,
+        );
+        assert_eq!(lucky.status, ResolutionStatus::Ambiguous);
+        assert_eq!(lucky.candidates[0].track.title, "Get Lucky");
+
+        let missing = resolver.resolve(3, &entry("Hey Jude", "The Beatles", None), base);
+        assert_eq!(missing.status, ResolutionStatus::Missing);
+        assert!(missing.candidates.is_empty());
+
+        // Title taken from the file name
+        let untitled = PlaylistEntry {
+            location: Some("C:\\Music\\07 - So What.mp3".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolver.resolve(4, &untitled, base);
+        assert_eq!(resolved.candidates[0].track.title, "So What");
+    }
+
+    #[test]
+    fn test_resolve_by_identifier() {
+        let mut library = vec![track("Intro", "The xx", "xx", 127.0)];
+        library[0].musicbrainz.recording_id = Some("abc-123".to_string());
+        let resolver = PlaylistResolver::new(&library, ResolveOptions::default());
+
+        let tagged = PlaylistEntry {
+            identifier: Some("https://musicbrainz.org/recording/ABC-123".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolver.resolve(0, &tagged, Path::new("/nonexistent"));
+        assert_eq!(resolved.status, ResolutionStatus::Confident);
+        assert_eq!(resolved.file_path, Some(library[0].file_path.clone()));
+    }
+
+    #[test]
+    fn test_thresholds_are_configurable() {
+        let library = vec![track("Yesterday", "The Beatles", "Help!", 125.0)];
+        let strict = ResolveOptions {
+            min_score: 0.95,
+            ..Default::default()
+        };
+        let resolver = PlaylistResolver::new(&library, strict);
+        let typo = entry("Yesterdy", "Beatles", None);
+        assert!(resolver.candidates(&typo).is_empty());
+
+        let resolver = PlaylistResolver::new(&library, ResolveOptions::default());
+        assert_eq!(resolver.candidates(&typo).len(), 1);
+    }
+}
@@ -0,0 +1,577 @@
+use super::*;
+use crate::library::normalize::{normalize_text, normalized_eq};
+use crate::library::query::sort_tracks;
+use crate::library::{get_saved_library, LibraryTrack, TrackSortKey};
+use chrono::{DateTime, Duration, Utc};
+use std::path::Path;
+use tauri::Emitter;
+
+/// Definition of a smart playlist. Its tracks are re-evaluated from the
+/// library whenever the library changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartPlaylist {
+    pub rule: SmartRule,
+    #[serde(default)]
+    pub sort: Vec<TrackSortKey>,
+    /// Maximum number of tracks, unlimited when `None`
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Rule tree: conditions combined with all/any/not.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SmartRule {
+    All {
+        rules: Vec<SmartRule>,
+    },
+    Any {
+        rules: Vec<SmartRule>,
+    },
+    Not {
+        rule: Box<SmartRule>,
+    },
+    Condition {
+        field: SmartField,
+        condition: Condition,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SmartField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Composer,
+    Format,
+    Codec,
+    FilePath,
+    Year,
+    /// Duration in seconds
+    Duration,
+    /// Bitrate in kbps
+    Bitrate,
+    /// Rating in stars (0-5)
+    Rating,
+    PlayCount,
+    SkipCount,
+    Loved,
+    DateAdded,
+    LastPlayed,
+}
+
+/// Comparison applied to a field. Text comparisons ignore case, width and
+/// simplified/traditional differences like library queries do.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "value", rename_all = "camelCase")]
+pub enum Condition {
+    Is(RuleValue),
+    IsNot(RuleValue),
+    Contains(String),
+    NotContains(String),
+    StartsWith(String),
+    LessThan(f64),
+    GreaterThan(f64),
+    AtLeast(f64),
+    AtMost(f64),
+    Between(f64, f64),
+    InLastDays(u32),
+    NotInLastDays(u32),
+    IsEmpty,
+    IsNotEmpty,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RuleValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+// Value of a track field as seen by the rule evaluator
+enum FieldValue<'a> {
+    Text(Vec<&'a str>),
+    Number(Option<f64>),
+    Bool(bool),
+    Date(Option<DateTime<Utc>>),
+}
+
+fn parse_time(value: &Option<String>) -> Option<DateTime<Utc>> {
+    value
+        .as_deref()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn text(value: Option<&str>) -> FieldValue<'_> {
+    FieldValue::Text(value.into_iter().collect())
+}
+
+fn field_value(track: &LibraryTrack, field: SmartField) -> FieldValue<'_> {
+    match field {
+        SmartField::Title => text(Some(&track.title)),
+        SmartField::Artist => FieldValue::Text(track.artist_names()),
+        SmartField::Album => text(Some(&track.album)),
+        SmartField::AlbumArtist => text(track.album_artist.as_deref()),
+        SmartField::Genre => FieldValue::Text(track.genre_names()),
+        SmartField::Composer => text(track.composer.as_deref()),
+        SmartField::Format => {
+            let extension = Path::new(&track.file_path)
+                .extension()
+                .and_then(|s| s.to_str());
+            text(extension)
+        }
+        SmartField::Codec => text(track.audio.codec.as_deref()),
+        SmartField::FilePath => text(Some(&track.file_path)),
+        SmartField::Year => FieldValue::Number(track.year.map(f64::from)),
+        SmartField::Duration => FieldValue::Number(Some(track.duration)),
+        SmartField::Bitrate => FieldValue::Number(track.audio.bitrate().map(f64::from)),
+        SmartField::Rating => FieldValue::Number(track.rating.map(|r| r as f64 / 2.0)),
+        SmartField::PlayCount => FieldValue::Number(Some(track.play_count as f64)),
+        SmartField::SkipCount => FieldValue::Number(Some(track.skip_count as f64)),
+        SmartField::Loved => FieldValue::Bool(track.loved),
+        SmartField::DateAdded => FieldValue::Date(track.added_time()),
+        SmartField::LastPlayed => FieldValue::Date(parse_time(&track.last_played)),
+    }
+}
+
+fn matches_text(values: &[&str], condition: &Condition) -> bool {
+    let contains = |needle: &str| {
+        let needle = normalize_text(needle);
+        values.iter().any(|v| normalize_text(v).contains(&needle))
+    };
+    match condition {
+        Condition::Is(RuleValue::Text(expected)) => {
+            values.iter().any(|v| normalized_eq(v, expected))
+        }
+        Condition::IsNot(RuleValue::Text(expected)) => {
+            !values.iter().any(|v| normalized_eq(v, expected))
+        }
+        Condition::Contains(needle) => contains(needle),
+        Condition::NotContains(needle) => !contains(needle),
+        Condition::StartsWith(prefix) => {
+            let prefix = normalize_text(prefix);
+            values
+                .iter()
+                .any(|v| normalize_text(v).starts_with(&prefix))
+        }
+        Condition::IsEmpty => values.iter().all(|v| v.trim().is_empty()),
+        Condition::IsNotEmpty => values.iter().any(|v| !v.trim().is_empty()),
+        _ => false,
+    }
+}
+
+// Missing numbers only match `isEmpty` and `isNot`
+fn matches_number(value: Option<f64>, condition: &Condition) -> bool {
+    match (condition, value) {
+        (Condition::IsEmpty, value) => value.is_none(),
+        (Condition::IsNotEmpty, value) => value.is_some(),
+        (Condition::IsNot(RuleValue::Number(expected)), value) => value != Some(*expected),
+        (_, None) => false,
+        (Condition::Is(RuleValue::Number(expected)), Some(value)) => value == *expected,
+        (Condition::LessThan(max), Some(value)) => value < *max,
+        (Condition::GreaterThan(min), Some(value)) => value > *min,
+        (Condition::AtLeast(min), Some(value)) => value >= *min,
+        (Condition::AtMost(max), Some(value)) => value <= *max,
+        (Condition::Between(low, high), Some(value)) => *low <= value && value <= *high,
+        _ => false,
+    }
+}
+
+fn matches_date(value: Option<DateTime<Utc>>, condition: &Condition, now: DateTime<Utc>) -> bool {
+    let within = |days: u32| value.is_some_and(|time| time >= now - Duration::days(days as i64));
+    match condition {
+        Condition::InLastDays(days) => within(*days),
+        Condition::NotInLastDays(days) => !within(*days),
+        Condition::IsEmpty => value.is_none(),
+        Condition::IsNotEmpty => value.is_some(),
+        _ => false,
+    }
+}
+
+impl SmartRule {
+    pub fn matches(&self, track: &LibraryTrack, now: DateTime<Utc>) -> bool {
+        match self {
+            SmartRule::All { rules } => rules.iter().all(|rule| rule.matches(track, now)),
+            SmartRule::Any { rules } => rules.iter().any(|rule| rule.matches(track, now)),
+            SmartRule::Not { rule } => !rule.matches(track, now),
+            SmartRule::Condition { field, condition } => match field_value(track, *field) {
+                FieldValue::Text(values) => matches_text(&values, condition),
+                FieldValue::Number(value) => matches_number(value, condition),
+                FieldValue::Bool(value) => match condition {
+                    Condition::Is(RuleValue::Bool(expected)) => value == *expected,
+                    Condition::IsNot(RuleValue::Bool(expected)) => value != *expected,
+                    _ => false,
+                },
+                FieldValue::Date(value) => matches_date(value, condition, now),
+            },
+        }
+    }
+
+    /// Reject conditions that can never apply to their field, so that a typo
+    /// in the editor doesn't silently produce an empty playlist.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SmartRule::All { rules } | SmartRule::Any { rules } => {
+                rules.iter().try_for_each(SmartRule::validate)
+            }
+            SmartRule::Not { rule } => rule.validate(),
+            SmartRule::Condition { field, condition } => {
+                let valid = match field_value(&LibraryTrack::default(), *field) {
+                    FieldValue::Text(_) => matches!(
+                        condition,
+                        Condition::Is(RuleValue::Text(_))
+                            | Condition::IsNot(RuleValue::Text(_))
+                            | Condition::Contains(_)
+                            | Condition::NotContains(_)
+                            | Condition::StartsWith(_)
+                            | Condition::IsEmpty
+                            | Condition::IsNotEmpty
+                    ),
+                    FieldValue::Number(_) => matches!(
+                        condition,
+                        Condition::Is(RuleValue::Number(_))
+                            | Condition::IsNot(RuleValue::Number(_))
+                            | Condition::LessThan(_)
+                            | Condition::GreaterThan(_)
+                            | Condition::AtLeast(_)
+                            | Condition::AtMost(_)
+                            | Condition::Between(_, _)
+                            | Condition::IsEmpty
+                            | Condition::IsNotEmpty
+                    ),
+                    FieldValue::Bool(_) => matches!(
+                        condition,
+                        Condition::Is(RuleValue::Bool(_)) | Condition::IsNot(RuleValue::Bool(_))
+                    ),
+                    FieldValue::Date(_) => matches!(
+                        condition,
+                        Condition::InLastDays(_)
+                            | Condition::NotInLastDays(_)
+                            | Condition::IsEmpty
+                            | Condition::IsNotEmpty
+                    ),
+                };
+                if valid {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Condition {:?} cannot be applied to {:?}",
+                        condition, field
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl SmartPlaylist {
+    /// Select, sort and limit the matching library tracks.
+    pub fn evaluate(&self, tracks: &[LibraryTrack], now: DateTime<Utc>) -> Vec<LibraryTrack> {
+        let mut matched: Vec<LibraryTrack> = tracks
+            .iter()
+            .filter(|track| self.rule.matches(track, now))
+            .cloned()
+            .collect();
+        sort_tracks(&mut matched, &self.sort);
+        matched.truncate(self.limit.unwrap_or(usize::MAX));
+        matched
+    }
+}
+
+/// Re-evaluate every smart playlist against the library.
+/// Only playlists whose entries changed are touched; returns their ids.
+pub fn refresh_smart_playlists(
+    playlists: &mut [Playlist],
+    tracks: &[LibraryTrack],
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut changed = Vec::new();
+    for playlist in playlists.iter_mut() {
+        let Some(smart) = &playlist.smart else {
+            continue;
+        };
+        let evaluated: Vec<Track> = smart
+            .evaluate(tracks, now)
+            .iter()
+            .map(Track::from)
+            .collect();
+        // Metadata changes count too, so that the snapshots stay up to date
+        if evaluated != playlist.tracks {
+            playlist.tracks = evaluated;
+            playlist.updated_at = now.to_rfc3339();
+            changed.push(playlist.id.clone());
+        }
+    }
+    changed
+}
+
+async fn refresh_saved_smart_playlists(app: &AppHandle) -> Result<Vec<String>, String> {
+    let _lock = lock_playlists(app).await;
+    let mut playlists = load_playlists_from_file(app).await?;
+    if !playlists.iter().any(|p| p.smart.is_some()) {
+        return Ok(Vec::new());
+    }
+
+    let library = get_saved_library(app.clone()).await?;
+    let changed = refresh_smart_playlists(&mut playlists, &library.tracks, Utc::now());
+    if !changed.is_empty() {
+        save_playlists_to_file(app, &playlists).await?;
+    }
+    Ok(changed)
+}
+
+/// Refresh smart playlists in the background after the library changed and
+/// emit `playlist://changed` with the ids of playlists whose tracks changed.
+pub fn schedule_smart_playlist_refresh(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match refresh_saved_smart_playlists(&app).await {
+            Ok(changed) if !changed.is_empty() => {
+                if let Err(e) = app.emit("playlist://changed", &changed) {
+                    eprintln!("Failed to emit playlist change event: {}", e);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to refresh smart playlists: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn create_smart_playlist(
+    app: AppHandle,
+    name: String,
+    description: Option<String>,
+    smart: SmartPlaylist,
+) -> Result<Playlist, String> {
+    smart.rule.validate()?;
+    let library = get_saved_library(app.clone()).await?;
+    let now = Utc::now();
+
+    let playlist = Playlist {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        description: description.unwrap_or_default(),
+        tracks: smart
+            .evaluate(&library.tracks, now)
+            .iter()
+            .map(Track::from)
+            .collect(),
+        created_at: now.to_rfc3339(),
+        updated_at: now.to_rfc3339(),
+        smart: Some(smart),
+    };
+
+    save_playlist_to_file(&app, &playlist, "Create smart playlist").await?;
+    Ok(playlist)
+}
+
+#[tauri::command]
+pub async fn update_smart_playlist(
+    app: AppHandle,
+    id: String,
+    smart: SmartPlaylist,
+) -> Result<Playlist, String> {
+    smart.rule.validate()?;
+    let _lock = lock_playlists(&app).await;
+    let mut playlists = load_playlists_from_file(&app).await?;
+    let before = playlists.clone();
+    let playlist = playlists
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| "Playlist not found".to_string())?;
+    if playlist.smart.is_none() {
+        return Err("Playlist is not a smart playlist".to_string());
+    }
+
+    let library = get_saved_library(app.clone()).await?;
+    let now = Utc::now();
+    playlist.tracks = smart
+        .evaluate(&library.tracks, now)
+        .iter()
+        .map(Track::from)
+        .collect();
+    playlist.smart = Some(smart);
+    playlist.updated_at = now.to_rfc3339();
+    let updated = playlist.clone();
+
+    commit_playlists(&app, "Edit smart playlist", &before, &playlists).await?;
+    Ok(updated)
+}
+
+/// Evaluate a smart playlist definition without saving it, for the rule editor.
+#[tauri::command]
+pub async fn preview_smart_playlist(
+    app: AppHandle,
+    smart: SmartPlaylist,
+) -> Result<Vec<LibraryTrack>, String> {
+    smart.rule.validate()?;
+    let library = get_saved_library(app).await?;
+    Ok(smart.evaluate(&library.tracks, Utc::now()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::query::TrackSortField;
+    use crate::library::test_support;
+    use crate::video::SortDirection;
+
+    fn condition(field: SmartField, condition: Condition) -> SmartRule {
+        SmartRule::Condition { field, condition }
+    }
+
+    fn track(title: &str, genre: &str, year: u32, rating: Option<u8>) -> LibraryTrack {
+        test_support::track(title)
+            .artist("Artist")
+            .album("Album")
+            .genre(genre)
+            .year(year)
+            .rating(rating)
+            .path(format!("/music/{}.flac", title))
+            .build()
+    }
+
+    fn sample_tracks() -> Vec<LibraryTrack> {
+        vec![
+            track("So What", "Jazz", 1959, Some(10)),
+            track("Take Five", "jazz", 1959, Some(7)),
+            track("Giant Steps", "Jazz", 1960, None),
+            track("Tutu", "Jazz", 1986, Some(9)),
+            track("Yesterday", "Pop", 1965, Some(10)),
+        ]
+    }
+
+    fn titles(tracks: &[LibraryTrack]) -> Vec<&str> {
+        tracks.iter().map(|t| t.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rule_tree() {
+        let now = Utc::now();
+        let tracks = sample_tracks();
+
+        // genre is Jazz AND year < 1970 AND rating >= 4
+        let smart = SmartPlaylist {
+            rule: SmartRule::All {
+                rules: vec![
+                    condition(
+                        SmartField::Genre,
+                        Condition::Is(RuleValue::Text("Jazz".to_string())),
+                    ),
+                    condition(SmartField::Year, Condition::LessThan(1970.0)),
+                    condition(SmartField::Rating, Condition::AtLeast(4.0)),
+                ],
+            },
+            sort: Vec::new(),
+            limit: None,
+        };
+        assert_eq!(titles(&smart.evaluate(&tracks, now)), vec!["So What"]);
+
+        let smart = SmartPlaylist {
+            rule: SmartRule::Any {
+                rules: vec![
+                    condition(SmartField::Rating, Condition::IsEmpty),
+                    SmartRule::Not {
+                        rule: Box::new(condition(
+                            SmartField::Genre,
+                            Condition::Contains("jaz".to_string()),
+                        )),
+                    },
+                ],
+            },
+            sort: Vec::new(),
+            limit: None,
+        };
+        assert_eq!(
+            titles(&smart.evaluate(&tracks, now)),
+            vec!["Giant Steps", "Yesterday"]
+        );
+    }
+
+    #[test]
+    fn test_dates_sort_and_limit() {
+        let now = Utc::now();
+        let mut tracks = sample_tracks();
+        tracks[0].added_at = Some((now - Duration::days(3)).to_rfc3339());
+        tracks[1].added_at = Some((now - Duration::days(90)).to_rfc3339());
+        tracks[0].play_count = 5;
+        tracks[0].last_played = Some(now.to_rfc3339());
+        tracks[3].play_count = 12;
+        tracks[3].last_played = Some((now - Duration::days(60)).to_rfc3339());
+
+        let recent = condition(SmartField::DateAdded, Condition::InLastDays(30));
+        assert!(recent.matches(&tracks[0], now));
+        assert!(!recent.matches(&tracks[1], now));
+
+        let never_played = condition(SmartField::LastPlayed, Condition::IsEmpty);
+        let never: Vec<&str> = tracks
+            .iter()
+            .filter(|t| never_played.matches(t, now))
+            .map(|t| t.title.as_str())
+            .collect();
+        assert_eq!(never, vec!["Take Five", "Giant Steps", "Yesterday"]);
+
+        // Most played, top 2
+        let smart = SmartPlaylist {
+            rule: condition(SmartField::PlayCount, Condition::GreaterThan(0.0)),
+            sort: vec![TrackSortKey {
+                field: TrackSortField::PlayCount,
+                direction: SortDirection::Descending,
+            }],
+            limit: Some(2),
+        };
+        assert_eq!(
+            titles(&smart.evaluate(&tracks, now)),
+            vec!["Tutu", "So What"]
+        );
+    }
+
+    #[test]
+    fn test_refresh_and_validate() {
+        let now = Utc::now();
+        let mut tracks = sample_tracks();
+        let smart = SmartPlaylist {
+            rule: condition(SmartField::Loved, Condition::Is(RuleValue::Bool(true))),
+            sort: Vec::new(),
+            limit: None,
+        };
+        let mut playlists = vec![Playlist {
+            id: "loved".to_string(),
+            name: "Loved".to_string(),
+            description: String::new(),
+            tracks: Vec::new(),
+            created_at: now.to_rfc3339(),
+            updated_at: now.to_rfc3339(),
+            smart: Some(smart),
+        }];
+
+        assert!(refresh_smart_playlists(&mut playlists, &tracks, now).is_empty());
+        tracks[2].loved = true;
+        assert_eq!(
+            refresh_smart_playlists(&mut playlists, &tracks, now),
+            vec!["loved"]
+        );
+        assert_eq!(playlists[0].tracks[0].title, "Giant Steps");
+        // Unchanged results leave the playlist alone, metadata edits don't
+        assert!(refresh_smart_playlists(&mut playlists, &tracks, now).is_empty());
+        tracks[2].title = "Giant Steps (Remastered)".to_string();
+        assert_eq!(
+            refresh_smart_playlists(&mut playlists, &tracks, now),
+            vec!["loved"]
+        );
+
+        let invalid = condition(SmartField::Year, Condition::Contains("19".to_string()));
+        assert!(invalid.validate().is_err());
+        assert!(SmartRule::All {
+            rules: vec![condition(SmartField::Title, Condition::IsNotEmpty)]
+        }
+        .validate()
+        .is_ok());
+    }
+}
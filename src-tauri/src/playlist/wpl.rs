@@ -0,0 +1,160 @@
+use super::*;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+// `<media>` element: `src` plus the optional metadata attributes written by
+// Windows Media Player and Zune
+fn media_entry(element: &BytesStart) -> Result<PlaylistEntry, String> {
+    let mut entry = PlaylistEntry::default();
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| format!("Invalid WPL: {}", e))?;
+        let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_lowercase();
+        let value = attribute
+            .unescape_value()
+            .map_err(|e| format!("Invalid WPL: {}", e))?
+            .trim()
+            .to_string();
+        if value.is_empty() {
+            continue;
+        }
+        match key.as_str() {
+            "src" => entry.location = Some(value),
+            "tracktitle" => entry.title = Some(value),
+            "trackartist" => entry.artist = Some(value),
+            "albumtitle" => entry.album = Some(value),
+            // Milliseconds
+            "duration" => {
+                entry.duration = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|ms| *ms > 0.0)
+                    .map(|ms| ms / 1000.0)
+            }
+            _ => {}
+        }
+    }
+    Ok(entry)
+}
+
+/// Parse a Windows Media Player playlist (`<smil>` with `<media src>` items).
+pub fn parse_wpl(content: &str) -> Result<ParsedPlaylist, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut parsed = ParsedPlaylist::default();
+    let mut elements: Vec<String> = Vec::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).to_lowercase();
+                if name == "media" {
+                    parsed.entries.push(media_entry(&element)?);
+                }
+                elements.push(name);
+            }
+            Ok(Event::Empty(element)) => {
+                if element.local_name().as_ref().eq_ignore_ascii_case(b"media") {
+                    parsed.entries.push(media_entry(&element)?);
+                }
+            }
+            Ok(Event::End(_)) => {
+                elements.pop();
+            }
+            Ok(Event::Text(text)) => {
+                let in_head_title = elements.len() >= 2
+                    && elements[elements.len() - 1] == "title"
+                    && elements[elements.len() - 2] == "head";
+                if in_head_title {
+                    let title = text.unescape().map_err(|e| format!("Invalid WPL: {}", e))?;
+                    parsed.name = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => return Err(format!("Invalid WPL: {}", e)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Write a Windows Media Player playlist. Entries without a location are
+/// skipped.
+pub fn write_wpl(name: &str, entries: &[PlaylistEntry]) -> String {
+    let locations: Vec<&String> = entries.iter().filter_map(|e| e.location.as_ref()).collect();
+
+    let mut xml = String::from("<?wpl version=\"1.0\"?>\n<smil>\n  <head>\n");
+    xml.push_str(&format!(
+        "    <meta name=\"Generator\" content=\"{}\"/>\n",
+        env!("CARGO_PKG_NAME")
+    ));
+    xml.push_str(&format!(
+        "    <meta name=\"ItemCount\" content=\"{}\"/>\n",
+        locations.len()
+    ));
+    xml.push_str(&format!("    <title>{}</title>\n", escape(name.trim())));
+    xml.push_str("  </head>\n  <body>\n    <seq>\n");
+    for location in locations {
+        xml.push_str(&format!(
+            "      <media src=\"{}\"/>\n",
+            escape(location.as_str())
+        ));
+    }
+    xml.push_str("    </seq>\n  </body>\n</smil>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wpl() {
+        let content = r#"<?wpl version="1.0"?>
+<smil>
+  <head>
+    <meta name="Generator" content="Microsoft Windows Media Player -- 12.0.19041.1"/>
+    <title>Chill &amp; Relax</title>
+  </head>
+  <body>
+    <seq>
+      <media src="..\Music\Air\La Femme d'Argent.mp3" tid="{ABC}"/>
+      <media src="D:\Music\Moby\Porcelain.flac" trackTitle="Porcelain" trackArtist="Moby" duration="241000"></media>
+    </seq>
+  </body>
+</smil>"#;
+        let parsed = parse_wpl(content).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("Chill & Relax"));
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(
+            parsed.entries[0].location.as_deref(),
+            Some("..\\Music\\Air\\La Femme d'Argent.mp3")
+        );
+        assert_eq!(parsed.entries[1].title.as_deref(), Some("Porcelain"));
+        assert_eq!(parsed.entries[1].artist.as_deref(), Some("Moby"));
+        assert_eq!(parsed.entries[1].duration, Some(241.0));
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let entries = vec![
+            PlaylistEntry {
+                location: Some("/music/Simon & Garfunkel/\"Cecilia\".mp3".to_string()),
+                ..Default::default()
+            },
+            PlaylistEntry::default(),
+        ];
+        let xml = write_wpl("S&G", &entries);
+        assert!(xml.contains("<meta name=\"ItemCount\" content=\"1\"/>"));
+
+        let parsed = parse_wpl(&xml).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("S&G"));
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(
+            parsed.entries[0].location.as_deref(),
+            Some("/music/Simon & Garfunkel/\"Cecilia\".mp3")
+        );
+    }
+}
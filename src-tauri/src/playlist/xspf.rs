@@ -0,0 +1,233 @@
+use super::*;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::path::Path;
+
+// Lowercase element name without namespace prefix
+fn element_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).to_lowercase()
+}
+
+// XSPF locations are URIs; relative ones are percent-encoded paths
+fn location_from_uri(uri: &str) -> String {
+    if uri.contains("://") {
+        uri.to_string()
+    } else {
+        percent_decode(uri)
+    }
+}
+
+fn location_to_uri(location: &str) -> String {
+    let path = location.replace('\\', "/");
+    if !Path::new(location).is_absolute() {
+        percent_encode_path(&path)
+    } else if path.starts_with('/') {
+        format!("file://{}", percent_encode_path(&path))
+    } else {
+        // Windows drive path
+        format!("file:///{}", percent_encode_path(&path))
+    }
+}
+
+fn set_track_field(entry: &mut PlaylistEntry, field: &str, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    match field {
+        // Only the first location is used
+        "location" if entry.location.is_none() => entry.location = Some(location_from_uri(text)),
+        "identifier" if entry.identifier.is_none() => entry.identifier = Some(text.to_string()),
+        "title" => entry.title = Some(text.to_string()),
+        "creator" => entry.artist = Some(text.to_string()),
+        "album" => entry.album = Some(text.to_string()),
+        "duration" => {
+            entry.duration = text
+                .parse::<f64>()
+                .ok()
+                .filter(|ms| *ms > 0.0)
+                .map(|ms| ms / 1000.0)
+        }
+        _ => {}
+    }
+}
+
+/// Parse an XSPF playlist. Tracks may have no location, in which case they
+/// are matched to the library by metadata.
+pub fn parse_xspf(content: &str) -> Result<ParsedPlaylist, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut parsed = ParsedPlaylist::default();
+    let mut elements: Vec<String> = Vec::new();
+    let mut track: Option<PlaylistEntry> = None;
+
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = element_name(element.local_name().as_ref());
+                if name == "track" && elements.last().is_some_and(|e| e == "tracklist") {
+                    track = Some(PlaylistEntry::default());
+                }
+                elements.push(name);
+                continue;
+            }
+            Ok(Event::End(_)) => {
+                if elements.pop().as_deref() == Some("track") {
+                    if let Some(entry) = track.take() {
+                        parsed.entries.push(entry);
+                    }
+                }
+                continue;
+            }
+            Ok(Event::Text(text)) => text
+                .unescape()
+                .map_err(|e| format!("Invalid XSPF: {}", e))?
+                .into_owned(),
+            Ok(Event::CData(data)) => String::from_utf8_lossy(&data).into_owned(),
+            Ok(Event::Eof) => break,
+            Ok(_) => continue,
+            Err(e) => return Err(format!("Invalid XSPF: {}", e)),
+        };
+
+        let depth = elements.len();
+        let Some(field) = elements.last() else {
+            continue;
+        };
+        match &mut track {
+            Some(entry) if depth >= 2 && elements[depth - 2] == "track" => {
+                set_track_field(entry, field, &text)
+            }
+            None if field == "title" && depth == 2 && elements[0] == "playlist" => {
+                parsed.name = Some(text.trim().to_string()).filter(|t| !t.is_empty())
+            }
+            _ => {}
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Write an XSPF playlist with file URIs (or relative URIs) as locations.
+pub fn write_xspf(name: &str, entries: &[PlaylistEntry]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n",
+    );
+    if !name.trim().is_empty() {
+        xml.push_str(&format!("  <title>{}</title>\n", escape(name.trim())));
+    }
+    xml.push_str("  <trackList>\n");
+
+    for entry in entries {
+        xml.push_str("    <track>\n");
+        let mut element = |tag: &str, value: &str| {
+            xml.push_str(&format!("      <{0}>{1}</{0}>\n", tag, escape(value)));
+        };
+        if let Some(location) = &entry.location {
+            element("location", &location_to_uri(location));
+        }
+        if let Some(identifier) = &entry.identifier {
+            element("identifier", identifier);
+        }
+        if let Some(title) = &entry.title {
+            element("title", title);
+        }
+        if let Some(artist) = &entry.artist {
+            element("creator", artist);
+        }
+        if let Some(album) = &entry.album {
+            element("album", album);
+        }
+        if let Some(duration) = entry.duration {
+            element(
+                "duration",
+                &((duration * 1000.0).round() as u64).to_string(),
+            );
+        }
+        xml.push_str("    </track>\n");
+    }
+
+    xml.push_str("  </trackList>\n</playlist>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xspf() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Kind of Blue &amp; More</title>
+  <trackList>
+    <track>
+      <location>file:///music/Miles%20Davis/So%20What.flac</location>
+      <location>http://example.com/mirror.flac</location>
+      <title>So What</title>
+      <creator>Miles Davis</creator>
+      <album>Kind of Blue</album>
+      <duration>562000</duration>
+    </track>
+    <track>
+      <identifier>https://musicbrainz.org/recording/1234</identifier>
+      <title><![CDATA[Blue in Green]]></title>
+      <creator>Miles Davis</creator>
+    </track>
+  </trackList>
+</playlist>"#;
+        let parsed = parse_xspf(content).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("Kind of Blue & More"));
+        assert_eq!(parsed.entries.len(), 2);
+
+        let first = &parsed.entries[0];
+        assert_eq!(
+            first.location.as_deref(),
+            Some("file:///music/Miles%20Davis/So%20What.flac")
+        );
+        assert_eq!(first.title.as_deref(), Some("So What"));
+        assert_eq!(first.artist.as_deref(), Some("Miles Davis"));
+        assert_eq!(first.duration, Some(562.0));
+
+        let second = &parsed.entries[1];
+        assert_eq!(second.location, None);
+        assert_eq!(second.title.as_deref(), Some("Blue in Green"));
+        assert_eq!(
+            second.identifier.as_deref(),
+            Some("https://musicbrainz.org/recording/1234")
+        );
+
+        assert!(parse_xspf("<playlist><trackList><track></playlist>").is_err());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let entries = vec![
+            PlaylistEntry {
+                location: Some("/music/AC/DC/Back in Black.mp3".to_string()),
+                title: Some("Back in Black".to_string()),
+                artist: Some("AC/DC".to_string()),
+                duration: Some(255.5),
+                ..Default::default()
+            },
+            PlaylistEntry {
+                location: Some("../Rock & Roll/song #1.mp3".to_string()),
+                ..Default::default()
+            },
+        ];
+        let xml = write_xspf("Rock <3", &entries);
+        assert!(xml.contains("<title>Rock &lt;3</title>"));
+        assert!(xml.contains("<location>file:///music/AC/DC/Back%20in%20Black.mp3</location>"));
+        assert!(xml.contains("<location>../Rock%20%26%20Roll/song%20%231.mp3</location>"));
+
+        let parsed = parse_xspf(&xml).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("Rock <3"));
+        assert_eq!(parsed.entries[0].duration, Some(255.5));
+        assert_eq!(
+            parsed.entries[1].location.as_deref(),
+            Some("../Rock & Roll/song #1.mp3")
+        );
+    }
+}
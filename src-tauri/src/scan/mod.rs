@@ -0,0 +1,327 @@
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+use tauri::State;
+
+/// 进度事件的最小发送间隔，避免大库扫描时刷屏
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 扫描任务句柄，可跨线程共享用于取消
+#[derive(Debug, Clone)]
+pub struct ScanJob {
+    pub id: String,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ScanJob {
+    pub fn new() -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for ScanJob {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type ScanJobsState = Arc<Mutex<HashMap<String, ScanJob>>>;
+
+/// 注册一个新的扫描任务
+pub fn register_job(jobs: &ScanJobsState) -> Result<ScanJob, String> {
+    let job = ScanJob::new();
+    jobs.lock()
+        .map_err(|e| e.to_string())?
+        .insert(job.id.clone(), job.clone());
+    Ok(job)
+}
+
+/// 任务结束后从注册表中移除
+pub fn unregister_job(jobs: &ScanJobsState, job_id: &str) {
+    if let Ok(mut jobs) = jobs.lock() {
+        jobs.remove(job_id);
+    }
+}
+
+/// 扫描进度
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanProgress {
+    pub job_id: String,
+    pub found: usize,
+    pub processed: usize,
+    pub failed: usize,
+    pub current_path: Option<String>,
+    pub eta_seconds: Option<f64>,
+    pub cancelled: bool,
+}
+
+/// 通过Tauri通道推送给前端的扫描事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum ScanEvent<T> {
+    Progress(ScanProgress),
+    Item(T),
+    Failed { path: String, error: String },
+    Removed { ids: Vec<String> },
+    Finished(ScanProgress),
+}
+
+/// 统计扫描进度并推送事件，没有通道时只做统计
+pub struct ScanReporter<T> {
+    job: ScanJob,
+    channel: Option<Channel<ScanEvent<T>>>,
+    progress: ScanProgress,
+    started: Instant,
+    last_progress: Option<Instant>,
+}
+
+impl<T: Serialize + Clone> ScanReporter<T> {
+    pub fn new(job: ScanJob, channel: Option<Channel<ScanEvent<T>>>) -> Self {
+        let progress = ScanProgress {
+            job_id: job.id.clone(),
+            ..Default::default()
+        };
+
+        Self {
+            job,
+            channel,
+            progress,
+            started: Instant::now(),
+            last_progress: None,
+        }
+    }
+
+    /// 不推送事件、不可取消的报告器，用于同步命令和后台监听
+    pub fn silent() -> Self {
+        Self::new(ScanJob::new(), None)
+    }
+
+    pub fn job(&self) -> &ScanJob {
+        &self.job
+    }
+
+    pub fn found(&mut self, count: usize) {
+        self.progress.found += count;
+        self.send_progress();
+    }
+
+    /// 未变化、无需重新读取的文件
+    pub fn skipped(&mut self, path: &Path) {
+        self.progress.processed += 1;
+        self.progress.current_path = Some(path.to_string_lossy().to_string());
+        self.send_progress();
+    }
+
+    pub fn item(&mut self, path: &Path, item: &T) {
+        self.progress.processed += 1;
+        self.progress.current_path = Some(path.to_string_lossy().to_string());
+        self.send(ScanEvent::Item(item.clone()));
+        self.send_progress();
+    }
+
+    pub fn failed(&mut self, path: &Path, error: &str) {
+        self.progress.failed += 1;
+        self.progress.current_path = Some(path.to_string_lossy().to_string());
+        self.send(ScanEvent::Failed {
+            path: path.to_string_lossy().to_string(),
+            error: error.to_string(),
+        });
+        self.send_progress();
+    }
+
+    pub fn removed(&mut self, ids: &[String]) {
+        if !ids.is_empty() {
+            self.send(ScanEvent::Removed { ids: ids.to_vec() });
+        }
+    }
+
+    /// 结束扫描并推送最终进度
+    pub fn finish(mut self) -> ScanProgress {
+        self.progress.cancelled = self.job.is_cancelled();
+        self.progress.current_path = None;
+        self.progress.eta_seconds = None;
+        self.send(ScanEvent::Finished(self.progress.clone()));
+        self.progress
+    }
+
+    fn send_progress(&mut self) {
+        if self.channel.is_none() {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_progress
+            .is_some_and(|last| now.duration_since(last) < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_progress = Some(now);
+
+        let done = self.progress.processed + self.progress.failed;
+        let remaining = self.progress.found.saturating_sub(done);
+        self.progress.eta_seconds = if done > 0 {
+            let per_file = now.duration_since(self.started).as_secs_f64() / done as f64;
+            Some(per_file * remaining as f64)
+        } else {
+            None
+        };
+
+        self.send(ScanEvent::Progress(self.progress.clone()));
+    }
+
+    fn send(&self, event: ScanEvent<T>) {
+        if let Some(channel) = &self.channel {
+            if let Err(e) = channel.send(event) {
+                eprintln!("Failed to send scan event: {}", e);
+            }
+        }
+    }
+}
+
+/// 并行读取的线程数
+fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(2, 8)
+}
+
+/// 在有界的阻塞线程池上并行处理文件，按完成顺序返回结果
+///
+/// 任务取消后尚未开始的文件会被跳过
+pub fn probe_in_parallel<R, F>(
+    job: &ScanJob,
+    files: Vec<PathBuf>,
+    probe: F,
+) -> BoxStream<'static, (PathBuf, Result<R, String>)>
+where
+    R: Send + 'static,
+    F: Fn(&Path) -> Result<R, String> + Send + Sync + 'static,
+{
+    let probe = Arc::new(probe);
+    let job = job.clone();
+
+    stream::iter(files)
+        .map(move |path| {
+            let probe = probe.clone();
+            let job = job.clone();
+            async move {
+                if job.is_cancelled() {
+                    return None;
+                }
+                let probe_path = path.clone();
+                let result = tokio::task::spawn_blocking(move || probe(&probe_path))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("Scan worker failed: {}", e)));
+                Some((path, result))
+            }
+        })
+        .buffer_unordered(worker_count())
+        .filter_map(|result| async move { result })
+        .boxed()
+}
+
+/// 取消正在进行的扫描任务
+#[tauri::command]
+pub fn cancel_scan_job(job_id: String, jobs: State<'_, ScanJobsState>) -> Result<bool, String> {
+    let jobs = jobs.lock().map_err(|e| e.to_string())?;
+    match jobs.get(&job_id) {
+        Some(job) => {
+            job.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 获取正在进行的扫描任务ID
+#[tauri::command]
+pub fn get_active_scan_jobs(jobs: State<'_, ScanJobsState>) -> Result<Vec<String>, String> {
+    let jobs = jobs.lock().map_err(|e| e.to_string())?;
+    Ok(jobs.keys().cloned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_in_parallel_processes_all_files() {
+        let files: Vec<PathBuf> = (0..20)
+            .map(|i| PathBuf::from(format!("{}.mp3", i)))
+            .collect();
+        let job = ScanJob::new();
+
+        let results: Vec<_> =
+            probe_in_parallel(&job, files, |path| Ok(path.to_string_lossy().len()))
+                .collect()
+                .await;
+
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_job_skips_pending_files() {
+        let files: Vec<PathBuf> = (0..20)
+            .map(|i| PathBuf::from(format!("{}.mp3", i)))
+            .collect();
+        let job = ScanJob::new();
+        job.cancel();
+
+        let results: Vec<_> = probe_in_parallel(&job, files, |_| Ok(())).collect().await;
+
+        assert!(results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_scan_stops_remaining_files() {
+        let files: Vec<PathBuf> = (0..200)
+            .map(|i| PathBuf::from(format!("{}.mp3", i)))
+            .collect();
+        let job = ScanJob::new();
+        let cancel = job.clone();
+
+        // 处理第一个文件时取消，已在处理中的文件完成，其余跳过
+        let results: Vec<_> = probe_in_parallel(&job, files, move |_| {
+            cancel.cancel();
+            Ok(())
+        })
+        .collect()
+        .await;
+
+        assert!(!results.is_empty());
+        assert!(results.len() <= worker_count());
+    }
+
+    #[test]
+    fn test_silent_reporter_counts_progress() {
+        let mut reporter: ScanReporter<String> = ScanReporter::silent();
+        reporter.found(3);
+        reporter.skipped(Path::new("a.mp3"));
+        reporter.item(Path::new("b.mp3"), &"b".to_string());
+        reporter.failed(Path::new("c.mp3"), "broken");
+
+        let progress = reporter.finish();
+        assert_eq!(progress.found, 3);
+        assert_eq!(progress.processed, 2);
+        assert_eq!(progress.failed, 1);
+        assert!(!progress.cancelled);
+    }
+}
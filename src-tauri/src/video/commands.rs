@@ -1,9 +1,13 @@
 use super::processor::VideoProcessor;
 use super::types::*;
+use crate::library::QueryPage;
+use crate::scan::{register_job, unregister_job, ScanEvent, ScanJobsState, ScanReporter};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use tauri::{command, AppHandle, Manager};
+use tauri::ipc::Channel;
+use tauri::{command, AppHandle, Manager, State};
 
 /// 视频库数据结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +25,66 @@ impl VideoLibrary {
             last_updated: chrono::Utc::now().to_rfc3339(),
         }
     }
+
+    /// 沿用旧视频库中相同路径视频的ID和缩略图，前端重新扫描后保存时ID保持不变
+    pub fn keep_ids(&mut self, previous: &VideoLibrary) {
+        let previous: HashMap<&PathBuf, &VideoFile> = previous
+            .videos
+            .iter()
+            .map(|video| (&video.file_path, video))
+            .collect();
+        for video in self.videos.iter_mut() {
+            if let Some(old) = previous.get(&video.file_path) {
+                video.id = old.id.clone();
+                if video.thumbnail_path.is_none() {
+                    video.thumbnail_path = old.thumbnail_path.clone();
+                }
+            }
+        }
+    }
+}
+
+impl VideoRescanDiff {
+    /// 把在快照上得到的扫描结果合并到最新的视频库，扫描期间监听器做的修改会保留
+    pub fn apply_to(&self, library: &mut VideoLibrary) {
+        library
+            .videos
+            .retain(|video| !self.removed.contains(&video.id));
+
+        for video in &self.updated {
+            if let Some(current) = library.videos.iter_mut().find(|v| v.id == video.id) {
+                *current = video.clone();
+            }
+        }
+
+        for video in &self.added {
+            if !library
+                .videos
+                .iter()
+                .any(|v| v.file_path == video.file_path)
+            {
+                library.videos.push(video.clone());
+            }
+        }
+    }
+}
+
+// 扫描在快照上进行，完成后持写锁合并到最新的视频库并保存
+async fn commit_video_rescan(
+    app: &AppHandle,
+    diff: &VideoRescanDiff,
+    roots: Vec<String>,
+) -> Result<(), String> {
+    let _lock = crate::library::lock_library(app).await;
+    let mut library = load_video_library_from_file(app)?;
+    diff.apply_to(&mut library);
+    for root in roots {
+        if !library.last_scanned_paths.contains(&root) {
+            library.last_scanned_paths.push(root);
+        }
+    }
+    library.last_updated = chrono::Utc::now().to_rfc3339();
+    save_video_library_to_file(app, &library)
 }
 
 /// 扫描视频文件
@@ -38,6 +102,92 @@ pub async fn scan_video_files(paths: Vec<String>) -> Result<VideoScanResult, Str
         .map_err(|e| format!("扫描视频文件失败: {}", e))
 }
 
+/// 增量重新扫描视频库，未指定路径时使用上次扫描的目录
+#[command]
+pub async fn rescan_video_library(
+    app: AppHandle,
+    paths: Option<Vec<String>>,
+) -> Result<VideoRescanDiff, String> {
+    let config = VideoLibraryConfig::default();
+    let processor =
+        VideoProcessor::new(config).map_err(|e| format!("创建视频处理器失败: {}", e))?;
+
+    let library = load_video_library_from_file(&app)?;
+    let roots = paths.unwrap_or_else(|| library.last_scanned_paths.clone());
+    let scan_paths: Vec<PathBuf> = roots.iter().map(PathBuf::from).collect();
+
+    let mut reporter = ScanReporter::silent();
+    let (_, diff) = processor
+        .rescan_and_process(&library.videos, &scan_paths, &mut reporter)
+        .await
+        .map_err(|e| format!("扫描视频文件失败: {}", e))?;
+
+    commit_video_rescan(&app, &diff, roots).await?;
+    crate::library::watcher::refresh_watching(&app);
+
+    Ok(diff)
+}
+
+/// 启动可取消的后台视频库扫描，立即返回任务ID，进度和结果通过 `on_event` 推送
+#[command]
+pub async fn start_video_scan(
+    app: AppHandle,
+    paths: Option<Vec<String>>,
+    on_event: Channel<ScanEvent<VideoFile>>,
+    jobs: State<'_, ScanJobsState>,
+) -> Result<String, String> {
+    let config = VideoLibraryConfig::default();
+    let processor =
+        VideoProcessor::new(config).map_err(|e| format!("创建视频处理器失败: {}", e))?;
+
+    let library = load_video_library_from_file(&app)?;
+    let roots = paths.unwrap_or_else(|| library.last_scanned_paths.clone());
+    let scan_paths: Vec<PathBuf> = roots.iter().map(PathBuf::from).collect();
+
+    let jobs = jobs.inner().clone();
+    let job = register_job(&jobs)?;
+    let job_id = job.id.clone();
+
+    tauri::async_runtime::spawn(async move {
+        let mut reporter = ScanReporter::new(job.clone(), Some(on_event));
+
+        let result = processor
+            .rescan_and_process(&library.videos, &scan_paths, &mut reporter)
+            .await
+            .map_err(|e| format!("扫描视频文件失败: {}", e));
+
+        match result {
+            Ok((_, diff)) => {
+                // 即使被取消，已完成的部分也会保存
+                match commit_video_rescan(&app, &diff, roots).await {
+                    Ok(()) => crate::library::watcher::refresh_watching(&app),
+                    Err(e) => eprintln!("保存视频库失败: {}", e),
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+
+        reporter.finish();
+        unregister_job(&jobs, &job.id);
+    });
+
+    Ok(job_id)
+}
+
+/// 分页查询视频库
+#[command]
+pub async fn query_videos(
+    app: AppHandle,
+    query: VideoQuery,
+) -> Result<QueryPage<VideoFile>, String> {
+    let config = VideoLibraryConfig::default();
+    let processor =
+        VideoProcessor::new(config).map_err(|e| format!("创建视频处理器失败: {}", e))?;
+
+    let library = load_video_library_from_file(&app)?;
+    Ok(processor.query_videos(&library.videos, &query))
+}
+
 /// 获取视频元数据
 #[command]
 pub async fn get_video_metadata(file_path: String) -> Result<VideoFile, String> {
@@ -188,7 +338,10 @@ fn get_video_library_file_path(app: &AppHandle) -> Result<PathBuf, String> {
 }
 
 // 保存视频库到文件
-fn save_video_library_to_file(app: &AppHandle, library: &VideoLibrary) -> Result<(), String> {
+pub(crate) fn save_video_library_to_file(
+    app: &AppHandle,
+    library: &VideoLibrary,
+) -> Result<(), String> {
     let file_path = get_video_library_file_path(app)?;
 
     let content = serde_json::to_string_pretty(library)
@@ -201,7 +354,7 @@ fn save_video_library_to_file(app: &AppHandle, library: &VideoLibrary) -> Result
 }
 
 // 从文件加载视频库
-fn load_video_library_from_file(app: &AppHandle) -> Result<VideoLibrary, String> {
+pub(crate) fn load_video_library_from_file(app: &AppHandle) -> Result<VideoLibrary, String> {
     let file_path = get_video_library_file_path(app)?;
 
     if !file_path.exists() {
@@ -230,13 +383,18 @@ pub async fn save_video_library(
     videos: Vec<VideoFile>,
     scanned_paths: Vec<String>,
 ) -> Result<(), String> {
-    let library = VideoLibrary {
+    let mut library = VideoLibrary {
         videos,
         last_scanned_paths: scanned_paths,
         last_updated: chrono::Utc::now().to_rfc3339(),
     };
 
-    save_video_library_to_file(&app, &library)
+    let _lock = crate::library::lock_library(&app).await;
+    library.keep_ids(&load_video_library_from_file(&app)?);
+    save_video_library_to_file(&app, &library)?;
+    crate::library::watcher::refresh_watching(&app);
+
+    Ok(())
 }
 
 /// 清除视频库
@@ -244,6 +402,7 @@ pub async fn save_video_library(
 pub async fn clear_video_library(app: AppHandle) -> Result<(), String> {
     let file_path = get_video_library_file_path(&app)?;
 
+    let _lock = crate::library::lock_library(&app).await;
     if file_path.exists() {
         fs::remove_file(&file_path)
             .map_err(|e| format!("Failed to remove video library file: {}", e))?;
@@ -251,3 +410,47 @@ pub async fn clear_video_library(app: AppHandle) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(id: &str, path: &str, thumbnail: Option<&str>) -> VideoFile {
+        VideoFile {
+            id: id.to_string(),
+            title: id.to_string(),
+            file_path: PathBuf::from(path),
+            duration: 60.0,
+            resolution: VideoResolution::new(1920, 1080),
+            format: VideoFormat::MP4,
+            codec: VideoCodec::H264,
+            file_size: 1000,
+            thumbnail_path: thumbnail.map(PathBuf::from),
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            bitrate: None,
+            frame_rate: None,
+        }
+    }
+
+    #[test]
+    fn test_keep_ids_by_path() {
+        let mut previous = VideoLibrary::new();
+        previous.videos = vec![video("old", "/videos/a.mp4", Some("thumbnails/old.jpg"))];
+
+        // 前端扫描得到的视频都带有新的ID
+        let mut library = VideoLibrary::new();
+        library.videos = vec![
+            video("new-a", "/videos/a.mp4", None),
+            video("new-b", "/videos/b.mp4", None),
+        ];
+        library.keep_ids(&previous);
+
+        assert_eq!(library.videos[0].id, "old");
+        assert_eq!(
+            library.videos[0].thumbnail_path,
+            Some(PathBuf::from("thumbnails/old.jpg"))
+        );
+        assert_eq!(library.videos[1].id, "new-b");
+    }
+}
@@ -2,9 +2,14 @@ use super::metadata::{VideoMetadata, VideoMetadataExtractor};
 use super::scanner::VideoScanner;
 use super::thumbnail::{AsyncThumbnailGenerator, ThumbnailConfig, ThumbnailError};
 use super::types::*;
+use crate::library::pinyin::{collated, compare_collated};
+use crate::library::QueryPage;
+use crate::scan::{probe_in_parallel, ScanJob, ScanReporter};
+use futures::StreamExt;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Once;
-use tokio::fs;
+use tokio::{fs, task};
 
 // 确保FFmpeg只初始化一次
 static FFMPEG_INIT: Once = Once::new();
@@ -57,7 +62,7 @@ impl VideoProcessor {
         &self,
         paths: &[PathBuf],
     ) -> Result<VideoScanResult, Box<dyn std::error::Error>> {
-        let mut scan_result = self.scanner.scan_paths(paths).await?;
+        let mut scan_result = self.scanner.scan_paths(paths, &ScanJob::new()).await?;
 
         // 为每个扫描到的视频文件提取元数据
         let mut processed_videos = Vec::new();
@@ -83,14 +88,131 @@ impl VideoProcessor {
         Ok(scan_result)
     }
 
+    /// 增量扫描：与已有视频列表比较大小和修改时间，只处理新增或已修改的文件
+    ///
+    /// 未变化的视频保留原有ID和缩略图，需要处理的文件在线程池中并行提取元数据，
+    /// 返回更新后的完整列表和差异
+    pub async fn rescan_and_process(
+        &self,
+        existing: &[VideoFile],
+        paths: &[PathBuf],
+        reporter: &mut ScanReporter<VideoFile>,
+    ) -> Result<(Vec<VideoFile>, VideoRescanDiff), Box<dyn std::error::Error>> {
+        let scan_result = self.scanner.scan_paths(paths, reporter.job()).await?;
+        let mut diff = VideoRescanDiff {
+            errors: scan_result.errors,
+            ..Default::default()
+        };
+        // 遍历被取消时文件列表不完整，保持视频库不变
+        if reporter.job().is_cancelled() {
+            return Ok((existing.to_vec(), diff));
+        }
+        reporter.found(scan_result.videos.len());
+
+        let mut videos: Vec<VideoFile> = existing.to_vec();
+        let existing_index: HashMap<PathBuf, usize> = videos
+            .iter()
+            .enumerate()
+            .map(|(index, video)| (video.file_path.clone(), index))
+            .collect();
+        let mut found = HashSet::new();
+        let mut pending: HashMap<PathBuf, (VideoFile, Option<usize>)> = HashMap::new();
+
+        for mut video_file in scan_result.videos {
+            found.insert(video_file.file_path.clone());
+
+            let index = existing_index.get(&video_file.file_path).copied();
+            if let Some(index) = index {
+                let old = &videos[index];
+                if old.file_size == video_file.file_size
+                    && old.modified_at == video_file.modified_at
+                {
+                    diff.unchanged += 1;
+                    reporter.skipped(&video_file.file_path);
+                    continue;
+                }
+                // 保留稳定ID和已有缩略图
+                video_file.id = old.id.clone();
+                video_file.thumbnail_path = old.thumbnail_path.clone();
+            }
+
+            pending.insert(video_file.file_path.clone(), (video_file, index));
+        }
+
+        let files = pending.keys().cloned().collect();
+        let mut results = probe_in_parallel(reporter.job(), files, |path| {
+            VideoMetadataExtractor::extract_metadata(path).map_err(|e| e.to_string())
+        });
+
+        while let Some((path, result)) = results.next().await {
+            let Some((mut video_file, index)) = pending.remove(&path) else {
+                continue;
+            };
+
+            let metadata = match result {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    reporter.failed(&path, &e);
+                    diff.errors
+                        .push(format!("元数据提取失败 {}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            self.apply_metadata(&mut video_file, metadata).await;
+            reporter.item(&path, &video_file);
+
+            match index {
+                Some(index) => {
+                    videos[index] = video_file.clone();
+                    diff.updated.push(video_file);
+                }
+                None => {
+                    videos.push(video_file.clone());
+                    diff.added.push(video_file);
+                }
+            }
+        }
+
+        // 只移除位于本次扫描路径下、但已不存在的文件，无法访问的路径不会导致视频被删除
+        let scanned_roots: Vec<&PathBuf> = paths
+            .iter()
+            .filter(|root| root.is_file() || std::fs::read_dir(root).is_ok())
+            .collect();
+        videos.retain(|video| {
+            let in_paths = scanned_roots
+                .iter()
+                .any(|root| video.file_path.starts_with(root));
+            if in_paths && !found.contains(&video.file_path) {
+                diff.removed.push(video.id.clone());
+                false
+            } else {
+                true
+            }
+        });
+        reporter.removed(&diff.removed);
+
+        Ok((videos, diff))
+    }
+
     /// 处理单个视频文件的元数据
     async fn process_video_metadata(
         &self,
         video_file: &mut VideoFile,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // 提取视频元数据
-        let metadata = VideoMetadataExtractor::extract_metadata(&video_file.file_path)?;
+        // FFmpeg为同步调用，放到阻塞线程池中执行，避免占用异步运行时
+        let path = video_file.file_path.clone();
+        let metadata = task::spawn_blocking(move || {
+            VideoMetadataExtractor::extract_metadata(&path).map_err(|e| e.to_string())
+        })
+        .await??;
 
+        self.apply_metadata(video_file, metadata).await;
+
+        Ok(())
+    }
+
+    /// 将提取到的元数据写入视频文件信息，并按配置生成缩略图
+    async fn apply_metadata(&self, video_file: &mut VideoFile, metadata: VideoMetadata) {
         // 更新视频文件信息
         video_file.duration = metadata.duration;
         video_file.resolution = metadata.resolution;
@@ -104,8 +226,6 @@ impl VideoProcessor {
                 video_file.thumbnail_path = Some(thumbnail_path);
             }
         }
-
-        Ok(())
     }
 
     /// 生成视频缩略图
@@ -274,6 +394,13 @@ impl VideoProcessor {
             }
         }
 
+        // 编码过滤
+        if let Some(ref codec) = filter.codec {
+            if video.codec != *codec {
+                return false;
+            }
+        }
+
         // 搜索查询过滤
         if let Some(ref query) = filter.search_query {
             let query_lower = query.to_lowercase();
@@ -294,32 +421,72 @@ impl VideoProcessor {
     /// 排序视频文件
     pub fn sort_videos(
         &self,
-        videos: &mut [VideoFile],
+        videos: &mut Vec<VideoFile>,
         sort_by: VideoSortBy,
         direction: SortDirection,
     ) {
-        videos.sort_by(|a, b| {
-            let comparison = match sort_by {
-                VideoSortBy::Title => a.title.cmp(&b.title),
-                VideoSortBy::Duration => a
-                    .duration
-                    .partial_cmp(&b.duration)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-                VideoSortBy::FileSize => a.file_size.cmp(&b.file_size),
-                VideoSortBy::CreatedAt => a.created_at.cmp(&b.created_at),
-                VideoSortBy::ModifiedAt => a.modified_at.cmp(&b.modified_at),
-                VideoSortBy::Resolution => {
-                    let a_pixels = a.resolution.width * a.resolution.height;
-                    let b_pixels = b.resolution.width * b.resolution.height;
-                    a_pixels.cmp(&b_pixels)
-                }
-            };
+        self.sort_videos_by_keys(
+            videos,
+            &[VideoSortKey {
+                field: sort_by,
+                direction,
+            }],
+        );
+    }
 
-            match direction {
-                SortDirection::Ascending => comparison,
-                SortDirection::Descending => comparison.reverse(),
-            }
+    /// 按多个排序键排序视频文件，标题的拼音排序键每个视频只生成一次
+    pub fn sort_videos_by_keys(&self, videos: &mut Vec<VideoFile>, keys: &[VideoSortKey]) {
+        let by_title = keys
+            .iter()
+            .any(|key| matches!(key.field, VideoSortBy::Title));
+        let mut keyed: Vec<(Option<(String, String)>, VideoFile)> = videos
+            .drain(..)
+            .map(|video| (by_title.then(|| collated(&video.title)), video))
+            .collect();
+
+        keyed.sort_by(|(a_key, a), (b_key, b)| {
+            keys.iter()
+                .map(|key| {
+                    let comparison = match (&key.field, a_key, b_key) {
+                        (VideoSortBy::Title, Some(a_key), Some(b_key)) => a_key.cmp(b_key),
+                        _ => Self::compare_videos(a, b, &key.field),
+                    };
+                    match key.direction {
+                        SortDirection::Ascending => comparison,
+                        SortDirection::Descending => comparison.reverse(),
+                    }
+                })
+                .find(|comparison| *comparison != std::cmp::Ordering::Equal)
+                .unwrap_or(std::cmp::Ordering::Equal)
         });
+
+        videos.extend(keyed.into_iter().map(|(_, video)| video));
+    }
+
+    /// 过滤、排序并分页视频列表
+    pub fn query_videos(&self, videos: &[VideoFile], query: &VideoQuery) -> QueryPage<VideoFile> {
+        let mut matched = self.filter_videos(videos, &query.filter);
+        self.sort_videos_by_keys(&mut matched, &query.sort);
+        QueryPage::from_sorted(matched, query.offset, query.limit)
+    }
+
+    /// 按单个字段比较两个视频
+    fn compare_videos(a: &VideoFile, b: &VideoFile, sort_by: &VideoSortBy) -> std::cmp::Ordering {
+        match sort_by {
+            VideoSortBy::Title => compare_collated(&a.title, &b.title),
+            VideoSortBy::Duration => a
+                .duration
+                .partial_cmp(&b.duration)
+                .unwrap_or(std::cmp::Ordering::Equal),
+            VideoSortBy::FileSize => a.file_size.cmp(&b.file_size),
+            VideoSortBy::CreatedAt => a.created_at.cmp(&b.created_at),
+            VideoSortBy::ModifiedAt => a.modified_at.cmp(&b.modified_at),
+            VideoSortBy::Resolution => {
+                let a_pixels = a.resolution.width * a.resolution.height;
+                let b_pixels = b.resolution.width * b.resolution.height;
+                a_pixels.cmp(&b_pixels)
+            }
+        }
     }
 }
 
@@ -438,4 +605,55 @@ mod tests {
         );
         assert!(videos[0].duration > videos[1].duration);
     }
+
+    #[test]
+    fn test_query_videos() {
+        let processor = VideoProcessor::new(VideoLibraryConfig::default()).unwrap();
+        let video = |id: &str, title: &str, duration: f64, format: VideoFormat| VideoFile {
+            id: id.to_string(),
+            title: title.to_string(),
+            file_path: PathBuf::from(format!("{}.mp4", id)),
+            duration,
+            resolution: VideoResolution::new(1920, 1080),
+            format,
+            codec: VideoCodec::H264,
+            file_size: 1000000,
+            thumbnail_path: None,
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            bitrate: None,
+            frame_rate: None,
+        };
+        let videos = vec![
+            video("1", "B", 60.0, VideoFormat::MP4),
+            video("2", "A", 60.0, VideoFormat::MP4),
+            video("3", "C", 120.0, VideoFormat::MP4),
+            video("4", "D", 90.0, VideoFormat::AVI),
+        ];
+
+        // 先按时长降序，时长相同再按标题升序，跳过第一条
+        let query = VideoQuery {
+            filter: VideoFilter {
+                format: Some(VideoFormat::MP4),
+                ..VideoFilter::default()
+            },
+            sort: vec![
+                VideoSortKey {
+                    field: VideoSortBy::Duration,
+                    direction: SortDirection::Descending,
+                },
+                VideoSortKey {
+                    field: VideoSortBy::Title,
+                    direction: SortDirection::Ascending,
+                },
+            ],
+            offset: 1,
+            limit: Some(1),
+        };
+        let page = processor.query_videos(&videos, &query);
+
+        assert_eq!(page.total, 3);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "2");
+    }
 }
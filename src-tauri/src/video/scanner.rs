@@ -1,4 +1,5 @@
 use super::types::*;
+use crate::scan::ScanJob;
 use chrono::Utc;
 use std::path::{Path, PathBuf};
 use tokio::fs;
@@ -15,14 +16,18 @@ impl VideoScanner {
         Self { config }
     }
 
-    /// 扫描指定路径中的视频文件
+    /// 扫描指定路径中的视频文件，任务取消后停止遍历
     pub async fn scan_paths(
         &self,
         paths: &[PathBuf],
+        job: &ScanJob,
     ) -> Result<VideoScanResult, Box<dyn std::error::Error>> {
         let mut result = VideoScanResult::default();
 
         for path in paths {
+            if job.is_cancelled() {
+                break;
+            }
             if !path.exists() {
                 result
                     .errors
@@ -30,7 +35,7 @@ impl VideoScanner {
                 continue;
             }
 
-            let scan_result = self.scan_directory(path).await?;
+            let scan_result = self.scan_directory(path, job).await?;
             result.total_files += scan_result.total_files;
             result.processed_files += scan_result.processed_files;
             result.failed_files += scan_result.failed_files;
@@ -45,6 +50,7 @@ impl VideoScanner {
     async fn scan_directory(
         &self,
         path: &Path,
+        job: &ScanJob,
     ) -> Result<VideoScanResult, Box<dyn std::error::Error>> {
         let mut result = VideoScanResult::default();
 
@@ -53,6 +59,9 @@ impl VideoScanner {
             .into_iter()
             .filter_map(|e| e.ok())
         {
+            if job.is_cancelled() {
+                break;
+            }
             let path = entry.path();
 
             if !path.is_file() {
@@ -237,6 +237,16 @@ impl Default for VideoScanResult {
     }
 }
 
+/// 视频库增量扫描结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VideoRescanDiff {
+    pub added: Vec<VideoFile>,
+    pub updated: Vec<VideoFile>,
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    pub errors: Vec<String>,
+}
+
 /// 视频过滤器
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VideoFilter {
@@ -246,6 +256,8 @@ pub struct VideoFilter {
     pub min_resolution: Option<VideoResolution>,
     pub max_resolution: Option<VideoResolution>,
     pub search_query: Option<String>,
+    #[serde(default)]
+    pub codec: Option<VideoCodec>,
 }
 
 impl Default for VideoFilter {
@@ -257,6 +269,7 @@ impl Default for VideoFilter {
             min_resolution: None,
             max_resolution: None,
             search_query: None,
+            codec: None,
         }
     }
 }
@@ -278,3 +291,20 @@ pub enum SortDirection {
     Ascending,
     Descending,
 }
+
+/// 排序键，多个键按顺序依次比较
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoSortKey {
+    pub field: VideoSortBy,
+    pub direction: SortDirection,
+}
+
+/// 视频查询参数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoQuery {
+    pub filter: VideoFilter,
+    pub sort: Vec<VideoSortKey>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}